@@ -0,0 +1,177 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use chrono::{Local, NaiveDate};
+use common::start_of_day;
+use db::{stage_append_csv, Db, FinancialEntry, Money, Price, StagedCsv, Units};
+use owo_colors::OwoColorize;
+
+/// One side of an entry, written as ACCOUNT=AMOUNT or ACCOUNT=UNITS@PRICE, e.g. GROCERY=85.50.
+#[derive(Clone, Debug)]
+pub struct EntryArg {
+    account_key: String,
+    unit: Units,
+    price: Price,
+}
+
+impl FromStr for EntryArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error =
+            || format!("{s:?} is not an entry, expecting ACCOUNT=AMOUNT or ACCOUNT=UNITS@PRICE");
+
+        let (account_key, amount) = s.split_once('=').ok_or_else(error)?;
+        let account_key = account_key.trim().to_uppercase();
+        if account_key.is_empty() {
+            return Err(error());
+        }
+
+        let (unit, price) = match amount.split_once('@') {
+            Some((unit, price)) => (
+                unit.trim().parse().map_err(|_| error())?,
+                price.trim().parse().map_err(|_| error())?,
+            ),
+            None => (
+                Units::from_raw(Units::SCALE),
+                amount
+                    .trim()
+                    .parse::<Money>()
+                    .map(Price::from)
+                    .map_err(|_| error())?,
+            ),
+        };
+
+        Ok(Self {
+            account_key,
+            unit,
+            price,
+        })
+    }
+}
+
+fn prompt(label: &str) -> io::Result<String> {
+    print!("{label}: ");
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+
+    Ok(line.trim().to_string())
+}
+
+/// Reads the entries of one side until an empty line.
+fn prompt_entries(side: &str) -> Result<Vec<EntryArg>, Box<dyn std::error::Error>> {
+    let mut entries = vec![];
+
+    loop {
+        let line = prompt(&format!(
+            "{side} (ACCOUNT=AMOUNT or ACCOUNT=UNITS@PRICE, empty to finish)"
+        ))?;
+        if line.is_empty() {
+            return Ok(entries);
+        }
+
+        match line.parse() {
+            Ok(entry) => entries.push(entry),
+            Err(err) => println!("{}", err.red()),
+        }
+    }
+}
+
+/// Inserts a balanced transaction and appends its rows to the CSV files of `csv_folder`, so that the files
+/// and the database stay in sync. Prompts for what isn't given by the flags.
+#[allow(clippy::too_many_arguments)]
+pub async fn add(
+    db_path: &Path,
+    date: Option<NaiveDate>,
+    description: Option<&str>,
+    debits: &[EntryArg],
+    credits: &[EntryArg],
+    store_key: Option<&str>,
+    exchange_rate: Option<f64>,
+    csv_folder: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (debits, credits) = if debits.is_empty() && credits.is_empty() {
+        (prompt_entries("Debit")?, prompt_entries("Credit")?)
+    } else {
+        (debits.to_vec(), credits.to_vec())
+    };
+    let description = match description {
+        Some(description) => description.to_string(),
+        None => prompt("Description")?,
+    };
+    let date = date.unwrap_or_else(|| Local::now().date_naive());
+
+    let to_entry = |entry: EntryArg, is_debit: bool| FinancialEntry {
+        // assigned by add_transaction
+        transaction_id: 0,
+        item_id: 0,
+        date: start_of_day(&date).timestamp(),
+        account_key: entry.account_key,
+        unit: entry.unit,
+        debit: is_debit.then_some(entry.price),
+        credit: (!is_debit).then_some(entry.price),
+        description: description.clone(),
+    };
+    let entries = debits
+        .into_iter()
+        .map(|entry| to_entry(entry, true))
+        .chain(credits.into_iter().map(|entry| to_entry(entry, false)))
+        .collect();
+
+    let mut db = Db::from_path(db_path.to_path_buf()).await?;
+    let mut transaction = db.begin_wrapped_transaction().await?;
+
+    let transaction_csv = csv_folder.map(|csv_folder| csv_folder.join("transaction.csv"));
+    let added = transaction
+        .add_transaction(
+            entries,
+            store_key,
+            exchange_rate,
+            transaction_csv.as_deref(),
+        )
+        .await?;
+    let transaction_id = added.entries[0].transaction_id;
+
+    // the files are written before the commit, so that the database is unchanged when they cannot be written;
+    // they are all staged first, so that a file that cannot be written doesn't leave the others with a partial entry
+    match csv_folder {
+        Some(csv_folder) => {
+            let mut staged = vec![stage_append_csv(
+                &csv_folder.join("transaction.csv"),
+                &added.entries,
+            )?];
+            if let Some(store) = &added.store {
+                staged.push(stage_append_csv(
+                    &csv_folder.join("transaction_store.csv"),
+                    std::slice::from_ref(store),
+                )?);
+            }
+            if let Some(forex) = &added.forex {
+                staged.push(stage_append_csv(
+                    &csv_folder.join("transaction_forex.csv"),
+                    std::slice::from_ref(forex),
+                )?);
+            }
+            StagedCsv::commit_all(staged)?;
+        }
+        None => println!(
+            "{}: no --csv-folder, use `export all` to write the transaction into the CSV files",
+            "Warning".bold().yellow()
+        ),
+    }
+
+    transaction.commit().await?;
+    db.optimize().await?;
+
+    println!(
+        "Added transaction {}",
+        transaction_id.to_string().bold().yellow()
+    );
+
+    Ok(())
+}
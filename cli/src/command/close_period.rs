@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use db::Db;
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+/// Posts the closing entries of the year and locks its entries, a snapshot is taken first.
+pub async fn close_period(db_path: &Path, year: i32) -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(Tabled)]
+    struct ClosingEntryFormatted {
+        #[tabled(rename = "Account")]
+        account_key: String,
+        #[tabled(rename = "Debit")]
+        debit: String,
+        #[tabled(rename = "Credit")]
+        credit: String,
+    }
+
+    let mut db = Db::from_path(db_path.to_path_buf()).await?;
+
+    let snapshot = db.snapshot(db_path).await?;
+    println!("saved snapshot {}", snapshot.to_string_lossy());
+
+    let mut transaction = db.begin_wrapped_transaction().await?;
+    let closed_period = transaction.close_period(year).await?;
+    transaction.commit().await?;
+
+    match closed_period.transaction_id {
+        Some(transaction_id) => {
+            println!(
+                "Posted the closing entries of {} as transaction {}",
+                closed_period.year,
+                transaction_id.to_string().bold().yellow()
+            );
+            println!(
+                "{}",
+                Table::new(
+                    closed_period
+                        .entries
+                        .into_iter()
+                        .map(|entry| ClosingEntryFormatted {
+                            account_key: entry.account_key,
+                            debit: entry.debit.map(|x| x.to_string()).unwrap_or_default(),
+                            credit: entry.credit.map(|x| x.to_string()).unwrap_or_default(),
+                        })
+                )
+                .with(Style::rounded())
+                .with(Columns::new(1..).modify().with(Alignment::right()))
+            );
+        }
+        None => println!("{} has no revenue or expense to close", closed_period.year),
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Entries dated on or before {} can no longer be inserted, changed or deleted",
+            closed_period.closed_until.date_naive()
+        )
+        .green()
+        .bold()
+    );
+
+    db.optimize().await?;
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use clap::Subcommand;
+use common::start_of_day;
+use db::{Db, ExchangeRateRecord};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+#[derive(Tabled)]
+struct ExchangeRateFormatted {
+    #[tabled(rename = "Pair")]
+    pub pair: String,
+    #[tabled(rename = "Date")]
+    pub date: NaiveDate,
+    #[tabled(rename = "Rate")]
+    pub rate: String,
+}
+
+impl From<ExchangeRateRecord> for ExchangeRateFormatted {
+    fn from(value: ExchangeRateRecord) -> Self {
+        Self {
+            pair: format!("{}/{}", value.base_currency, value.quote_currency),
+            date: value.date.date_naive(),
+            rate: format!("{:.4}", value.rate),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExchangeRateCommand {
+    /// Load the daily exchange rates exported by the Bank of Canada (CSV).
+    Import {
+        /// The `csv_path` field is the path to the FX_RATES_DAILY export.
+        csv_path: PathBuf,
+    },
+    /// List the most recent rates of a currency pair.
+    List {
+        /// The `base_currency` field is the currency being converted, e.g. USD.
+        base_currency: String,
+        /// The `quote_currency` field is the currency being converted into, e.g. CAD.
+        quote_currency: String,
+        /// The `limit` field is an optional integer that specifies the maximum number of rows to be displayed.
+        #[clap(default_value_t = 30)]
+        limit: u32,
+    },
+    /// Record how much of the quote currency one unit of the base currency buys on a given date.
+    Set {
+        /// The `base_currency` field is the currency being converted, e.g. USD.
+        base_currency: String,
+        /// The `quote_currency` field is the currency being converted into, e.g. CAD.
+        quote_currency: String,
+        /// The `rate` field is the amount of quote currency per unit of base currency.
+        rate: f64,
+        /// The `date` field is an optional date of the rate. If the field is not provided,
+        /// the rate is recorded for the current day.
+        date: Option<NaiveDate>,
+    },
+}
+
+impl ExchangeRateCommand {
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Db::from_path(db_path.to_path_buf()).await?;
+        let mut transaction = db.begin_wrapped_transaction().await?;
+
+        match self {
+            Self::Import { csv_path } => {
+                let result = transaction
+                    .import_bank_of_canada_exchange_rate(csv_path)
+                    .await?;
+
+                println!("imported {} rates", result.num_imported);
+
+                if !result.skipped_series.is_empty() {
+                    println!(
+                        "{}: skipped series of unknown currencies: {}",
+                        "Warning".bold().yellow(),
+                        result.skipped_series.join(", ")
+                    );
+                }
+            }
+            Self::List {
+                base_currency,
+                quote_currency,
+                limit,
+            } => {
+                let records = transaction
+                    .get_exchange_rate_history(base_currency, quote_currency, *limit)
+                    .await?;
+
+                let formatted = records.into_iter().map(ExchangeRateFormatted::from);
+
+                println!(
+                    "{}",
+                    Table::new(formatted)
+                        .with(Style::rounded())
+                        .with(Columns::single(2).modify().with(Alignment::right()))
+                );
+            }
+            Self::Set {
+                base_currency,
+                quote_currency,
+                rate,
+                date,
+            } => {
+                let date = start_of_day(&date.unwrap_or_else(|| Local::now().date_naive()));
+
+                transaction
+                    .set_exchange_rate(base_currency, quote_currency, &date, *rate)
+                    .await?;
+            }
+        };
+
+        transaction.commit().await?;
+        db.optimize().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Subcommand, ValueEnum};
+use db::Db;
+
+use super::upsert::CsvTable;
+
+#[derive(Debug, Subcommand)]
+pub enum ExportCommand {
+    /// Writes every table into the CSV files read by `upsert all`.
+    All {
+        /// The directory that receives the CSV files. It is created when missing.
+        csv_folder: PathBuf,
+    },
+}
+
+impl ExportCommand {
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Db::from_path(db_path.to_path_buf()).await?;
+        let mut transaction = db.begin_wrapped_transaction().await?;
+
+        match self {
+            Self::All {
+                csv_folder: csv_path,
+            } => {
+                std::fs::create_dir_all(csv_path)?;
+
+                for table in CsvTable::value_variants() {
+                    table
+                        .export(&mut transaction, &csv_path.join(table.file_name()))
+                        .await?;
+                }
+            }
+        }
+
+        transaction.commit().await?;
+        db.optimize().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use chrono::{Local, TimeZone};
+use db::{stage_append_csv, Db, FinancialEntry, Price, StagedCsv};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+#[derive(Tabled)]
+struct DraftFormatted {
+    #[tabled(rename = "ID")]
+    transaction_id: i64,
+    #[tabled(rename = "Item")]
+    item_id: i64,
+    #[tabled(rename = "Date")]
+    date: String,
+    #[tabled(rename = "Account")]
+    account_key: String,
+    #[tabled(rename = "Debit")]
+    debit: String,
+    #[tabled(rename = "Credit")]
+    credit: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+impl From<&FinancialEntry> for DraftFormatted {
+    fn from(value: &FinancialEntry) -> Self {
+        let format_amount = |amount: Option<Price>| {
+            amount
+                .map(|amount| format!("${amount}"))
+                .unwrap_or_default()
+        };
+
+        Self {
+            transaction_id: value.transaction_id,
+            item_id: value.item_id,
+            date: Local
+                .timestamp_opt(value.date, 0)
+                .single()
+                .map(|date| date.date_naive().to_string())
+                .unwrap_or_default(),
+            account_key: value.account_key.clone(),
+            debit: format_amount(value.debit),
+            credit: format_amount(value.credit),
+            description: value.description.clone(),
+        }
+    }
+}
+
+/// Drafts the transactions of an OFX/QFX statement into the review CSV, which is upserted with
+/// `upsert transaction` once the counter accounts are corrected. The stores assigned by the categorization rules go
+/// into a sibling file, e.g. review_store.csv for review.csv.
+pub async fn import(
+    db_path: &Path,
+    statement_path: &Path,
+    account_key: &str,
+    counter_account_key: &str,
+    output_path: &Path,
+    reimport: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let account_key = account_key.to_uppercase();
+    let counter_account_key = counter_account_key.to_uppercase();
+
+    let mut db = Db::from_path(db_path.to_path_buf()).await?;
+    let mut transaction = db.begin_wrapped_transaction().await?;
+
+    let result = transaction
+        .import_ofx(statement_path, &account_key, &counter_account_key, reimport)
+        .await?;
+
+    if result.num_duplicates > 0 {
+        println!(
+            "Skipped {} statement transactions that were imported before",
+            result.num_duplicates
+        );
+    }
+
+    if result.entries.is_empty() {
+        println!("Nothing to import");
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        Table::new(result.entries.iter().map(DraftFormatted::from))
+            .with(Style::rounded())
+            .with(Columns::new(4..6).modify().with(Alignment::right()))
+    );
+
+    // the files are written before the commit, so that the FITIDs are only recorded when the drafts are saved
+    let mut staged = vec![stage_append_csv(output_path, &result.entries)?];
+
+    let store_path = if result.stores.is_empty() {
+        None
+    } else {
+        let stem = output_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let store_path = output_path.with_file_name(format!("{stem}_store.csv"));
+        staged.push(stage_append_csv(&store_path, &result.stores)?);
+
+        Some(store_path)
+    };
+
+    StagedCsv::commit_all(staged)?;
+
+    transaction.commit().await?;
+    db.optimize().await?;
+
+    println!(
+        "Drafted {} transactions into {}, review them and run `upsert transaction` on the file",
+        result.entries.len() / 2,
+        output_path.to_string_lossy().bold()
+    );
+    if let Some(store_path) = store_path {
+        println!(
+            "Assigned the store of {} transactions into {}, run `upsert transaction-store` on the file",
+            result.stores.len(),
+            store_path.to_string_lossy().bold()
+        );
+    }
+
+    Ok(())
+}
@@ -1,23 +1,114 @@
+mod add;
+mod close_period;
+mod exchange_rate;
+mod export;
+mod import;
+mod price;
 mod print_justify_amex;
+mod print_missing_valuation;
 mod print_transaction_check;
 mod rebalance;
+mod reconcile;
+mod recurring;
 mod report;
+mod rules;
+mod snapshot;
 mod upsert;
+mod validate;
 
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
 use clap::Subcommand;
-use db::Db;
+use db::{Db, Money};
 use owo_colors::OwoColorize;
 use rebalance::rebalance;
 
 use self::{
-    print_justify_amex::print_justify_amex, print_transaction_check::print_transaction_check,
-    report::ReportCommand, upsert::UpsertCommand,
+    add::{add, EntryArg},
+    close_period::close_period,
+    exchange_rate::ExchangeRateCommand,
+    export::ExportCommand,
+    import::import,
+    price::PriceCommand,
+    print_justify_amex::print_justify_amex,
+    print_transaction_check::print_transaction_check,
+    reconcile::reconcile,
+    recurring::RecurringCommand,
+    report::ReportCommand,
+    rules::RulesCommand,
+    snapshot::{backup, restore, SnapshotCommand},
+    upsert::UpsertCommand,
+    validate::validate,
 };
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
+    /// Adds a balanced transaction, prompting for the entries when neither --debit nor --credit is given.
+    Add {
+        /// The `date` field is the date of the transaction, today by default.
+        #[clap(long)]
+        date: Option<NaiveDate>,
+        #[clap(long)]
+        description: Option<String>,
+        /// The account debited, as ACCOUNT=AMOUNT or ACCOUNT=UNITS@PRICE. Can be repeated.
+        #[clap(long)]
+        debit: Vec<EntryArg>,
+        /// The account credited, as ACCOUNT=AMOUNT or ACCOUNT=UNITS@PRICE. Can be repeated.
+        #[clap(long)]
+        credit: Vec<EntryArg>,
+        /// The `store` field is the store_key of the purchase, for the cashback reports.
+        #[clap(long)]
+        store: Option<String>,
+        /// The `exchange_rate` field converts the amounts into the home currency.
+        #[clap(long)]
+        exchange_rate: Option<f64>,
+        /// The directory of the CSV files, the new rows are appended to transaction.csv (and to
+        /// transaction_store.csv and transaction_forex.csv).
+        #[clap(long)]
+        csv_folder: Option<PathBuf>,
+    },
+    /// Saves a copy of the database. Without a target, the copy is kept as a snapshot.
+    Backup {
+        /// The `target` field is an optional path of the copy.
+        target: Option<PathBuf>,
+    },
     /// Verify the coherence of the database.
     Check,
+    /// Posts the closing entries that move the revenue and expense of the year into retained earnings, then locks
+    /// the entries dated in or before the year.
+    ClosePeriod {
+        /// The `year` field is the calendar year to close, which must have ended.
+        year: i32,
+    },
+    /// Records and lists the exchange rates between currencies.
+    ExchangeRate {
+        #[command(subcommand)]
+        command: ExchangeRateCommand,
+    },
+    /// Writes the database back into CSV files.
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+    /// Drafts the transactions of an OFX/QFX bank or credit card statement into a CSV file for review. The
+    /// statement transactions that were imported before (by their FITID) are skipped, unless `--reimport` is given.
+    Import {
+        /// The `statement` field is the OFX or QFX file downloaded from the bank.
+        statement: PathBuf,
+        /// The `account_key` field is the cash account or the credit card of the statement, e.g. ALICE-BB-CHQ-CASH.
+        #[clap(long)]
+        account: String,
+        /// The `counter` field is the account on the other side of every draft, e.g. an uncategorized expense.
+        #[clap(long)]
+        counter: String,
+        /// The `output` field is the review CSV, in the layout of transaction.csv. The drafts are appended to it.
+        #[clap(long)]
+        output: PathBuf,
+        /// Drafts the statement transactions that were imported before again, e.g. when the review CSV was lost.
+        #[clap(long)]
+        reimport: bool,
+    },
     /// Calculates and evaluates whether using the Amex SimplyCash Preferred card is justified, based on the specified number of days.
     JustifyAmex {
         /// Specifies the number of days prior to the current date to be used for backtesting the Amex SimplyCash Preferred card justification.
@@ -26,25 +117,120 @@ pub enum Command {
     },
     /// Retrieves the next available transaction ID.
     Next,
+    /// Records and lists the price history of securities.
+    Price {
+        #[command(subcommand)]
+        command: PriceCommand,
+    },
+    /// Lists the named ledger profiles that can be selected with `--profile`.
+    Profiles,
     /// Displays the allocation required to rebalance the asset.
-    Rebalance,
+    Rebalance {
+        /// Shows the allocation with the entries and the security prices up to the end of the given date.
+        #[clap(long)]
+        as_of: Option<NaiveDate>,
+    },
+    /// Matches a bank or credit card statement with the entries of an account.
+    Reconcile {
+        /// The `account_key` field is the account of the statement, e.g. ALICE-BB-CHQ-CASH.
+        account_key: String,
+        /// The `statement` field is a CSV file with the date, amount and description columns. The amounts have the
+        /// sign of the account, e.g. purchases are positive on a credit card statement.
+        statement: PathBuf,
+        /// The `statement_date` field is the closing date of the statement, the last date of its lines by default.
+        #[clap(long)]
+        statement_date: Option<NaiveDate>,
+        /// The `closing_balance` field is the balance printed on the statement.
+        #[clap(long, allow_hyphen_values = true)]
+        closing_balance: Option<Money>,
+        /// The `window` field is the number of days that a statement line and an entry can be apart.
+        #[clap(long, default_value_t = 3)]
+        window: u64,
+        /// Records the entries up to the statement date as reconciled, once the closing balance agrees.
+        #[clap(long)]
+        mark: bool,
+    },
+    /// Posts the transactions that repeat on a schedule, e.g. paycheques and rent.
+    Recurring {
+        #[command(subcommand)]
+        command: RecurringCommand,
+    },
     /// Provides a report of important data from the database.
     Report {
         #[command(subcommand)]
         command: ReportCommand,
     },
+    /// Assigns stores and expense accounts from the descriptions, with the rules in rule.csv.
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommand,
+    },
+    /// Replaces the database with a snapshot. The current state is snapshotted first.
+    Restore {
+        /// The `snapshot` field is either the name of a snapshot (see `snapshots list`) or a path to a backup.
+        snapshot: String,
+    },
+    /// Manages the snapshots of the database.
+    Snapshots {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
     /// Synchronizes the database with the provided CSV file.
     Upsert {
-        #[command(subcommand)]
+        /// The `keep_snapshots` field specifies how many snapshots are kept, one is taken before every upsert.
+        /// Use 0 to skip taking a snapshot.
+        #[clap(long, default_value_t = 10)]
+        keep_snapshots: usize,
+        /// Prints what would be inserted and updated, then rolls everything back.
+        #[clap(long)]
+        dry_run: bool,
+        /// Deletes the rows that are no longer in the CSV files, the deleted rows are printed.
+        #[clap(long)]
+        prune: bool,
+        #[command(flatten)]
         command: UpsertCommand,
     },
+    /// Parses the CSV files read by `upsert all` and reports every unknown key, without writing anything.
+    Validate {
+        /// The directory containing all the CSV files.
+        csv_folder: PathBuf,
+    },
 }
 
 impl Command {
-    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
         match self {
+            Self::Add {
+                date,
+                description,
+                debit,
+                credit,
+                store,
+                exchange_rate,
+                csv_folder,
+            } => {
+                add(
+                    db_path,
+                    *date,
+                    description.as_deref(),
+                    debit,
+                    credit,
+                    store.as_deref(),
+                    *exchange_rate,
+                    csv_folder.as_deref(),
+                )
+                .await
+            }
+            Self::Backup { target } => backup(db_path, target.as_deref()).await,
+            Self::Import {
+                statement,
+                account,
+                counter,
+                output,
+                reimport,
+            } => import(db_path, statement, account, counter, output, *reimport).await,
             Self::JustifyAmex { num_days } => {
-                let mut db = Db::new().await?;
+                let mut db = Db::from_path(db_path.to_path_buf()).await?;
                 let mut transaction = db.begin_wrapped_transaction().await?;
 
                 print_justify_amex(&mut transaction, *num_days).await?;
@@ -55,7 +241,7 @@ impl Command {
                 Ok(())
             }
             Self::Check => {
-                let mut db = Db::new().await?;
+                let mut db = Db::from_path(db_path.to_path_buf()).await?;
                 let mut transaction = db.begin_wrapped_transaction().await?;
 
                 print_transaction_check(&mut transaction).await?;
@@ -65,8 +251,9 @@ impl Command {
 
                 Ok(())
             }
+            Self::ClosePeriod { year } => close_period(db_path, *year).await,
             Self::Next => {
-                let mut db = Db::new().await?;
+                let mut db = Db::from_path(db_path.to_path_buf()).await?;
                 let mut transaction = db.begin_wrapped_transaction().await?;
 
                 print_transaction_check(&mut transaction).await?;
@@ -83,12 +270,63 @@ impl Command {
 
                 Ok(())
             }
-            Self::Upsert { command } => command.run().await,
-            Self::Rebalance => {
-                rebalance().await?;
+            Self::Profiles => {
+                for profile in Db::list_profiles()? {
+                    println!("{profile}");
+                }
+
+                Ok(())
+            }
+            Self::Upsert {
+                keep_snapshots,
+                dry_run,
+                prune,
+                command,
+            } => {
+                command
+                    .run(db_path, *keep_snapshots, *dry_run, *prune)
+                    .await
+            }
+            Self::Validate { csv_folder } => {
+                let mut db = Db::from_path(db_path.to_path_buf()).await?;
+                let mut transaction = db.begin_wrapped_transaction().await?;
+
+                let result = validate(&mut transaction, csv_folder).await;
+                transaction.rollback().await?;
+
+                Ok(result?)
+            }
+            Self::ExchangeRate { command } => command.run(db_path).await,
+            Self::Export { command } => command.run(db_path).await,
+            Self::Price { command } => command.run(db_path).await,
+            Self::Rebalance { as_of } => {
+                rebalance(db_path, *as_of).await?;
                 Ok(())
             }
-            Self::Report { command } => command.run().await,
+            Self::Reconcile {
+                account_key,
+                statement,
+                statement_date,
+                closing_balance,
+                window,
+                mark,
+            } => {
+                reconcile(
+                    db_path,
+                    account_key,
+                    statement,
+                    *statement_date,
+                    *closing_balance,
+                    *window,
+                    *mark,
+                )
+                .await
+            }
+            Self::Recurring { command } => command.run(db_path).await,
+            Self::Report { command } => command.run(db_path).await,
+            Self::Rules { command } => command.run(db_path).await,
+            Self::Restore { snapshot } => restore(db_path, snapshot).await,
+            Self::Snapshots { command } => command.run(db_path).await,
         }
     }
 }
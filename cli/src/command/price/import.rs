@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use chrono::{Days, Local};
+use db::{StaleSecurityPrice, Transaction};
+use owo_colors::OwoColorize;
+use tabled::{Style, Table, Tabled};
+
+pub async fn import_price(
+    transaction: &mut Transaction<'_>,
+    csv_path: &Path,
+    ticker: Option<&str>,
+    stale_days: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let result = transaction.import_security_price(csv_path, ticker).await?;
+
+    println!(
+        "imported {} prices ({:?} format)",
+        result.num_imported, result.format
+    );
+
+    if !result.unknown_symbols.is_empty() {
+        println!(
+            "{}: unknown symbols, add them to security.csv or check the exchange: {}",
+            "Warning".bold().yellow(),
+            result
+                .unknown_symbols
+                .into_iter()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    #[derive(Tabled)]
+    struct StaleSecurityPriceFormatted {
+        #[tabled(rename = "Ticker")]
+        pub ticker: String,
+        #[tabled(rename = "Last Price")]
+        pub last_update: String,
+    }
+
+    impl From<StaleSecurityPrice> for StaleSecurityPriceFormatted {
+        fn from(value: StaleSecurityPrice) -> Self {
+            Self {
+                ticker: value.ticker,
+                last_update: value
+                    .last_update
+                    .map(|date| date.date_naive().to_string())
+                    .unwrap_or_else(|| "never".to_string()),
+            }
+        }
+    }
+
+    let since = Local::now()
+        .checked_sub_days(Days::new(stale_days))
+        .expect("unable to subtract days");
+
+    let stale = transaction.get_stale_security_price(&since).await?;
+
+    if !stale.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!("Held securities without a price in the last {stale_days} days")
+                .yellow()
+                .bold()
+        );
+        println!(
+            "{}",
+            Table::new(stale.into_iter().map(StaleSecurityPriceFormatted::from))
+                .with(Style::rounded())
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+mod import;
+
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use clap::Subcommand;
+use common::start_of_day;
+use db::{Db, Price, SecurityPriceRecord};
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+use self::import::import_price;
+
+#[derive(Tabled)]
+struct SecurityPriceFormatted {
+    #[tabled(rename = "Ticker")]
+    pub ticker: String,
+    #[tabled(rename = "Date")]
+    pub date: NaiveDate,
+    #[tabled(rename = "Price")]
+    pub price: String,
+}
+
+impl From<SecurityPriceRecord> for SecurityPriceFormatted {
+    fn from(value: SecurityPriceRecord) -> Self {
+        Self {
+            ticker: value.ticker,
+            date: value.date.date_naive(),
+            price: format!("${:.2}", value.price),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PriceCommand {
+    /// Record the closing prices of a downloaded quote file (Yahoo Finance CSV, Stooq CSV or ticker,date,close CSV).
+    Import {
+        /// The `csv_path` field is the path to the quote file.
+        csv_path: PathBuf,
+        /// The `ticker` field is an optional symbol for files that contain the quotes of a single security.
+        /// If the field is not provided, the file name is used (e.g. XEQT.TO.csv).
+        #[clap(long)]
+        ticker: Option<String>,
+        /// The `stale_days` field specifies how old the latest price of a held security can be before it is reported.
+        #[clap(long, default_value_t = 7)]
+        stale_days: u64,
+    },
+    /// List the most recent prices of a security.
+    List {
+        /// The `ticker` field is a string that specifies the security.
+        ticker: String,
+        /// The `limit` field is an optional integer that specifies the maximum number of rows to be displayed.
+        #[clap(default_value_t = 30)]
+        limit: u32,
+    },
+    /// Record the price of a security on a given date.
+    Set {
+        /// The `ticker` field is a string that specifies the security.
+        ticker: String,
+        /// The `price` field is the closing price in the currency of the security.
+        price: Price,
+        /// The `date` field is an optional date of the price. If the field is not provided,
+        /// the price is recorded for the current day and becomes the current quote.
+        date: Option<NaiveDate>,
+    },
+}
+
+impl PriceCommand {
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Db::from_path(db_path.to_path_buf()).await?;
+        let mut transaction = db.begin_wrapped_transaction().await?;
+
+        match self {
+            Self::Import {
+                csv_path,
+                ticker,
+                stale_days,
+            } => {
+                import_price(&mut transaction, csv_path, ticker.as_deref(), *stale_days).await?;
+            }
+            Self::List { ticker, limit } => {
+                let records = transaction
+                    .get_security_price_history(ticker, *limit)
+                    .await?;
+
+                let formatted = records.into_iter().map(SecurityPriceFormatted::from);
+
+                println!(
+                    "{}",
+                    Table::new(formatted)
+                        .with(Style::rounded())
+                        .with(Columns::single(2).modify().with(Alignment::right()))
+                );
+            }
+            Self::Set {
+                ticker,
+                price,
+                date,
+            } => {
+                let date = start_of_day(&date.unwrap_or_else(|| Local::now().date_naive()));
+
+                transaction
+                    .set_security_price(ticker, &date, *price)
+                    .await?;
+            }
+        };
+
+        transaction.commit().await?;
+        db.optimize().await?;
+
+        Ok(())
+    }
+}
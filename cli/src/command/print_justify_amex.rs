@@ -1,4 +1,4 @@
-use db::Transaction;
+use db::{Money, Transaction};
 use owo_colors::OwoColorize;
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
 
@@ -30,24 +30,24 @@ pub async fn print_justify_amex(
         missed_opportunities: String,
     }
 
-    fn colourize(value: f64, formatted: String) -> String {
-        if value >= 0.0 {
+    fn colourize(is_gain: bool, formatted: String) -> String {
+        if is_gain {
             formatted.green().to_string()
         } else {
             formatted.red().to_string()
         }
     }
 
-    fn format_balance(value: f64) -> String {
-        if value >= 0.0 {
-            format!("${:.2}", value)
+    fn format_balance(value: Money) -> String {
+        if value.is_negative() {
+            format!("(${})", value.abs())
         } else {
-            format!("(${:.2})", value.abs())
+            format!("${}", value)
         }
     }
 
-    fn format_coloured_balance(value: f64) -> String {
-        colourize(value, format_balance(value))
+    fn format_coloured_balance(value: Money) -> String {
+        colourize(!value.is_negative(), format_balance(value))
     }
 
     let records = records.into_iter().map(|row| {
@@ -61,7 +61,7 @@ pub async fn print_justify_amex(
             without_amex_cashback: format_coloured_balance(row.without_amex_cashback),
             extra_cashback: format_coloured_balance(row.extra_cashback),
             extra_cashback_rate: colourize(
-                extra_cashback_rate,
+                extra_cashback_rate >= 0.0,
                 format!("{:.2}%", extra_cashback_rate),
             ),
             extra_cashback_after_fee: format_coloured_balance(row.extra_cashback_after_fee),
@@ -0,0 +1,51 @@
+use db::{MissingValuation, SqlResult, Transaction};
+use owo_colors::OwoColorize;
+use tabled::{Style, Table, Tabled};
+
+/// Warns about the holdings that are left out of the valuation, like the stale prices of `price import`.
+pub async fn print_missing_valuation(transaction: &mut Transaction<'_>) -> SqlResult<()> {
+    #[derive(Tabled)]
+    struct MissingValuationFormatted {
+        #[tabled(rename = "Ticker")]
+        pub ticker: String,
+        #[tabled(rename = "Currency")]
+        pub currency: String,
+        #[tabled(rename = "Missing")]
+        pub missing: String,
+    }
+
+    impl From<MissingValuation> for MissingValuationFormatted {
+        fn from(value: MissingValuation) -> Self {
+            let missing = if value.ticker.is_some() {
+                "price"
+            } else {
+                "exchange rate"
+            };
+
+            Self {
+                ticker: value.ticker.unwrap_or_default(),
+                currency: value.currency,
+                missing: missing.into(),
+            }
+        }
+    }
+
+    let missing = transaction.get_missing_valuation().await?;
+
+    if !missing.is_empty() {
+        println!(
+            "{}",
+            "Holdings without a price or an exchange rate on the valuation date, which are left out"
+                .yellow()
+                .bold()
+        );
+        println!(
+            "{}",
+            Table::new(missing.into_iter().map(MissingValuationFormatted::from))
+                .with(Style::rounded())
+        );
+        println!();
+    }
+
+    Ok(())
+}
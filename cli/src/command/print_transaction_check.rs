@@ -1,4 +1,6 @@
-use db::{AssertTransactionBalance, CheckTransactionStore, SqlResult, Transaction};
+use db::{
+    AssertTransactionBalance, ChangedReconciledEntry, CheckTransactionStore, SqlResult, Transaction,
+};
 use owo_colors::OwoColorize;
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
 
@@ -33,6 +35,44 @@ pub async fn print_transaction_check(transaction: &mut Transaction<'_>) -> SqlRe
         }
     }
 
+    {
+        #[derive(Tabled)]
+        struct RecordFormatted {
+            transaction_id: i64,
+            item_id: i64,
+            account_key: String,
+            statement_date: String,
+            change: String,
+        }
+
+        impl From<ChangedReconciledEntry> for RecordFormatted {
+            fn from(value: ChangedReconciledEntry) -> Self {
+                Self {
+                    transaction_id: value.transaction_id,
+                    item_id: value.item_id,
+                    account_key: value.account_key,
+                    statement_date: value.statement_date.date_naive().to_string(),
+                    change: value.change,
+                }
+            }
+        }
+
+        let changed_entries = transaction.check_reconciled_entries().await?;
+
+        if !changed_entries.is_empty() {
+            let formatted = changed_entries.into_iter().map(RecordFormatted::from);
+
+            println!(
+                "{}",
+                "The following entries changed after they were reconciled, reconcile the account again to accept them"
+                    .yellow()
+                    .bold()
+            );
+            println!("{}", Table::new(formatted).with(Style::rounded()));
+            println!();
+        }
+    }
+
     let results = transaction.check_transaction_balance().await?;
 
     if !results.is_empty() {
@@ -83,6 +123,7 @@ pub async fn print_transaction_check(transaction: &mut Transaction<'_>) -> SqlRe
                 )
                 .bold()
             );
+            eprintln!("The trial-balance report lists the balance of every account");
         }
     }
 
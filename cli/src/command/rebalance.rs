@@ -1,8 +1,13 @@
-use chrono::Local;
+use std::path::Path;
+
+use chrono::{Local, NaiveDate};
+use common::end_of_day;
 use db::{AssetRebalance, CreditCardPadInjection, Db, EmergencyRebalance, SqlResult, Transaction};
 use owo_colors::OwoColorize;
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
 
+use super::print_missing_valuation::print_missing_valuation;
+
 async fn print_account_last_update(transaction: &mut Transaction<'_>) -> SqlResult<()> {
     #[derive(Tabled)]
     pub struct AccountLatestTransactionFormatted {
@@ -229,13 +234,28 @@ async fn print_rebalance(transaction: &mut Transaction<'_>) -> SqlResult<()> {
     Ok(())
 }
 
-pub async fn rebalance() -> Result<(), Box<dyn std::error::Error>> {
-    let mut db = Db::new().await?;
+pub async fn rebalance(
+    db_path: &Path,
+    as_of: Option<NaiveDate>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut db = Db::from_path(db_path.to_path_buf()).await?;
     let mut transaction = db.begin_wrapped_transaction().await?;
 
-    print_account_last_update(&mut transaction).await?;
+    if let Some(as_of) = as_of {
+        // past allocations only, the reminders below are about the present
+        transaction
+            .set_valuation_date(Some(&end_of_day(&as_of)))
+            .await?;
+
+        println!("{}", format!("As of {}", as_of).bold());
+        println!();
+    } else {
+        print_account_last_update(&mut transaction).await?;
+
+        print_current_credit(&mut transaction).await?;
+    }
 
-    print_current_credit(&mut transaction).await?;
+    print_missing_valuation(&mut transaction).await?;
 
     print_emergency_injecion(&mut transaction).await?;
 
@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use chrono::{Local, NaiveDate};
+use db::{read_statement, Db, LedgerLine, Money, StatementLine};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+#[derive(Tabled)]
+struct StatementLineFormatted {
+    #[tabled(rename = "Line")]
+    line: u64,
+    #[tabled(rename = "Date")]
+    date: NaiveDate,
+    #[tabled(rename = "Amount")]
+    amount: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+impl From<StatementLine> for StatementLineFormatted {
+    fn from(value: StatementLine) -> Self {
+        Self {
+            line: value.line,
+            date: value.date,
+            amount: value.amount.to_string(),
+            description: value.description,
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct LedgerLineFormatted {
+    #[tabled(rename = "ID")]
+    transaction_id: i64,
+    #[tabled(rename = "Item")]
+    item_id: i64,
+    #[tabled(rename = "Date")]
+    date: NaiveDate,
+    #[tabled(rename = "Amount")]
+    amount: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+impl From<LedgerLine> for LedgerLineFormatted {
+    fn from(value: LedgerLine) -> Self {
+        Self {
+            transaction_id: value.transaction_id,
+            item_id: value.item_id,
+            date: value.date.date_naive(),
+            amount: value.amount.to_string(),
+            description: value.description,
+        }
+    }
+}
+
+/// Matches the statement with the entries of the account and compares the balances. With `mark`, the entries up
+/// to the statement date are recorded as reconciled, so that the check command flags later changes to them.
+pub async fn reconcile(
+    db_path: &Path,
+    account_key: &str,
+    statement_path: &Path,
+    statement_date: Option<NaiveDate>,
+    closing_balance: Option<Money>,
+    window_days: u64,
+    mark: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let account_key = account_key.to_uppercase();
+    let statement = read_statement(statement_path)?;
+    let statement_date = statement_date
+        .or_else(|| statement.iter().map(|line| line.date).max())
+        .unwrap_or_else(|| Local::now().date_naive());
+
+    let mut db = Db::from_path(db_path.to_path_buf()).await?;
+    let mut transaction = db.begin_wrapped_transaction().await?;
+
+    let result = transaction
+        .match_statement(&account_key, &statement, &statement_date, window_days)
+        .await?;
+
+    println!(
+        "{}",
+        format!("Reconciling {account_key} up to {statement_date}").bold()
+    );
+    println!(
+        "Matched {} of {} statement lines",
+        result.matched.len(),
+        statement.len()
+    );
+    println!();
+
+    if !result.unmatched_statement.is_empty() {
+        println!("{}", "On the statement, but not in the ledger".bold());
+        println!(
+            "{}",
+            Table::new(
+                result
+                    .unmatched_statement
+                    .into_iter()
+                    .map(StatementLineFormatted::from)
+            )
+            .with(Style::rounded())
+            .with(Columns::single(2).modify().with(Alignment::right()))
+        );
+        println!();
+    }
+
+    if !result.unmatched_ledger.is_empty() {
+        println!("{}", "In the ledger, but not on the statement".bold());
+        println!(
+            "{}",
+            Table::new(
+                result
+                    .unmatched_ledger
+                    .into_iter()
+                    .map(LedgerLineFormatted::from)
+            )
+            .with(Style::rounded())
+            .with(Columns::single(3).modify().with(Alignment::right()))
+        );
+        println!();
+    }
+
+    println!(
+        "Ledger balance at the end of {statement_date}: ${}",
+        result.ledger_balance
+    );
+
+    match closing_balance {
+        Some(closing_balance) if closing_balance == result.ledger_balance => {
+            println!(
+                "{}",
+                format!("The statement closes at ${closing_balance}, the balances agree")
+                    .green()
+                    .bold()
+            );
+        }
+        Some(closing_balance) => {
+            println!(
+                "{}",
+                format!(
+                    "The statement closes at ${closing_balance}, off by ${}",
+                    (closing_balance - result.ledger_balance).abs()
+                )
+                .red()
+                .bold()
+            );
+        }
+        None => {}
+    }
+
+    if mark {
+        let closing_balance =
+            closing_balance.ok_or("--mark needs the --closing-balance of the statement")?;
+
+        let num_entries = transaction
+            .mark_reconciled(&account_key, &statement_date, closing_balance)
+            .await?;
+        println!("Marked {num_entries} more entries as reconciled up to {statement_date}");
+    }
+
+    transaction.commit().await?;
+    db.optimize().await?;
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, NaiveDate, TimeZone};
+use clap::Subcommand;
+use db::{stage_append_csv, Db, FinancialEntry, StagedCsv};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+use super::rules::AppliedRuleFormatted;
+
+#[derive(Tabled)]
+struct FinancialEntryFormatted {
+    #[tabled(rename = "ID")]
+    transaction_id: i64,
+    #[tabled(rename = "Item")]
+    item_id: i64,
+    #[tabled(rename = "Date")]
+    date: String,
+    #[tabled(rename = "Account")]
+    account_key: String,
+    #[tabled(rename = "Debit")]
+    debit: String,
+    #[tabled(rename = "Credit")]
+    credit: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+impl From<&FinancialEntry> for FinancialEntryFormatted {
+    fn from(value: &FinancialEntry) -> Self {
+        Self {
+            transaction_id: value.transaction_id,
+            item_id: value.item_id,
+            date: Local
+                .timestamp_opt(value.date, 0)
+                .single()
+                .map(|date| date.date_naive().to_string())
+                .unwrap_or_default(),
+            account_key: value.account_key.clone(),
+            debit: value.debit.map(|x| x.to_string()).unwrap_or_default(),
+            credit: value.credit.map(|x| x.to_string()).unwrap_or_default(),
+            description: value.description.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RecurringCommand {
+    /// Posts the occurrences of the templates in recurring.csv that aren't posted yet. The stores of the purchases
+    /// are assigned with the rules in rule.csv.
+    Generate {
+        /// The `until` field is the date of the last occurrence to post, today by default.
+        #[clap(long)]
+        until: Option<NaiveDate>,
+        /// Prints the entries that would be posted, then rolls everything back.
+        #[clap(long)]
+        dry_run: bool,
+        /// The directory of the CSV files, the new rows are appended to transaction.csv (and to
+        /// transaction_store.csv).
+        #[clap(long)]
+        csv_folder: Option<PathBuf>,
+    },
+}
+
+impl RecurringCommand {
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Db::from_path(db_path.to_path_buf()).await?;
+        let mut transaction = db.begin_wrapped_transaction().await?;
+
+        match self {
+            Self::Generate {
+                until,
+                dry_run,
+                csv_folder,
+            } => {
+                let until = until.unwrap_or_else(|| Local::now().date_naive());
+                let transaction_csv = csv_folder
+                    .as_ref()
+                    .map(|csv_folder| csv_folder.join("transaction.csv"));
+                let entries = transaction
+                    .generate_recurring(&until, transaction_csv.as_deref())
+                    .await?;
+                let transaction_ids: BTreeSet<_> =
+                    entries.iter().map(|entry| entry.transaction_id).collect();
+
+                if entries.is_empty() {
+                    println!("Every occurrence until {until} is already posted");
+                    return Ok(());
+                }
+
+                println!(
+                    "{}",
+                    Table::new(entries.iter().map(FinancialEntryFormatted::from))
+                        .with(Style::rounded())
+                        .with(Columns::new(4..6).modify().with(Alignment::right()))
+                );
+                println!("Posted {} entries until {until}", entries.len());
+
+                let applied = transaction.apply_rules(Some(&transaction_ids)).await?;
+                if !applied.is_empty() {
+                    println!(
+                        "{}",
+                        Table::new(applied.iter().map(AppliedRuleFormatted::from))
+                            .with(Style::rounded())
+                    );
+                    println!("Assigned the store of {} purchases", applied.len());
+                }
+
+                if *dry_run {
+                    transaction.rollback().await?;
+                    println!("{}", "Dry run, nothing was written".yellow().bold());
+                    return Ok(());
+                }
+
+                // like `add`, the entries must be in transaction.csv, or `upsert --prune` would delete them
+                match csv_folder {
+                    Some(csv_folder) => {
+                        let mut staged =
+                            vec![stage_append_csv(&csv_folder.join("transaction.csv"), &entries)?];
+                        if !applied.is_empty() {
+                            let stores: Vec<_> =
+                                applied.into_iter().map(|applied| applied.store).collect();
+                            staged.push(stage_append_csv(
+                                &csv_folder.join("transaction_store.csv"),
+                                &stores,
+                            )?);
+                        }
+                        StagedCsv::commit_all(staged)?;
+                    }
+                    None => println!(
+                        "{}: no --csv-folder, use `export all` to write the new entries into transaction.csv",
+                        "Warning".bold().yellow()
+                    ),
+                }
+            }
+        }
+
+        transaction.commit().await?;
+        db.optimize().await?;
+
+        Ok(())
+    }
+}
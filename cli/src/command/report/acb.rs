@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use chrono::{Datelike, Local};
 use common::all_time_in_year;
-use db::{Acb, Transaction};
+use db::{Acb, Money, Transaction};
 use owo_colors::OwoColorize;
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
 
@@ -22,18 +22,20 @@ pub async fn report_acb(
         pub total_distribution: String,
         #[tabled(rename = "Gain/Loss")]
         pub total_capital_gl: String,
+        #[tabled(rename = "Superficial Loss")]
+        pub total_superficial_loss: String,
         #[tabled(rename = "ACB")]
         pub acb: String,
     }
 
     impl From<Acb> for AcbFormatted {
         fn from(value: Acb) -> Self {
-            let format_colored_cad = |v: f64| {
-                if v == 0.0 {
+            let format_colored_cad = |v: Money| {
+                if v.is_zero() {
                     "".into()
                 } else {
                     let ret = format!("${:.2}", v);
-                    if v > 0.0 {
+                    if !v.is_negative() {
                         ret.green().to_string()
                     } else {
                         ret.red().to_string()
@@ -41,8 +43,8 @@ pub async fn report_acb(
                 }
             };
 
-            let format_cad = |v: f64| {
-                if v == 0.0 {
+            let format_cad = |v: Money| {
+                if v.is_zero() {
                     "".into()
                 } else {
                     format!("${:.2}", v)
@@ -56,6 +58,13 @@ pub async fn report_acb(
                 acb: format_cad(value.acb),
                 total_distribution: format_colored_cad(value.total_distribution),
                 total_capital_gl: format_colored_cad(value.total_capital_gl),
+                total_superficial_loss: if value.total_superficial_loss.is_zero() {
+                    "".into()
+                } else {
+                    format!("${:.2}", value.total_superficial_loss)
+                        .yellow()
+                        .to_string()
+                },
             }
         }
     }
@@ -76,7 +85,7 @@ pub async fn report_acb(
     }
 
     let everyone_capital_gl = records.iter().fold(
-        BTreeMap::<i64, (String, f64, f64)>::new(),
+        BTreeMap::<i64, (String, Money, Money)>::new(),
         |mut acc, record| {
             let entry = acc.entry(record.person_id);
             let value = entry.or_default();
@@ -97,10 +106,10 @@ pub async fn report_acb(
     );
 
     for (_, (name, total_distribution, total_capital_gl)) in everyone_capital_gl {
-        fn format_money(val: f64) -> String {
-            if val > 0.0 {
+        fn format_money(val: Money) -> String {
+            if val > Money::ZERO {
                 format!("${:.2}", val).green().to_string()
-            } else if val < 0.0 {
+            } else if val.is_negative() {
                 format!("${:.2}", val).red().to_string()
             } else {
                 "$0.00".yellow().to_string()
@@ -116,6 +125,10 @@ pub async fn report_acb(
     }
     println!();
 
+    let has_superficial_loss = records
+        .iter()
+        .any(|record| !record.total_superficial_loss.is_zero());
+
     let formatted = records.iter().cloned().map(AcbFormatted::from);
 
     println!("{}", format!("GL Breakdown By Security").bold());
@@ -129,6 +142,13 @@ pub async fn report_acb(
             .with(Columns::single(6).modify().with(Alignment::right()))
             .to_string()
     );
+    if has_superficial_loss {
+        println!(
+            "{}",
+            "Superficial losses are denied, since the security was bought back within 30 days of the sale, and added to the ACB of the units bought back outside of the registered accounts"
+                .yellow()
+        );
+    }
     println!();
 
     Ok(())
@@ -1,15 +1,32 @@
-use chrono::Local;
+use chrono::NaiveDate;
+use common::{all_time_until, end_of_day};
 use db::{BalanceRecord, Transaction};
 use owo_colors::OwoColorize;
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table};
 
-use crate::command::report::{
-    stock_unit::get_stock_unit_str, BalanceRecordWithOwnerFormatted, NetBalanceFormatted,
+use crate::command::{
+    print_missing_valuation::print_missing_valuation,
+    report::{
+        stock_unit::get_stock_unit_str, BalanceRecordWithOwnerFormatted, NetBalanceFormatted,
+    },
 };
 
 pub async fn report_balance(
     transaction: &mut Transaction<'_>,
+    as_of: Option<NaiveDate>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let as_of = as_of.map(|date| end_of_day(&date));
+    let range = as_of.map(all_time_until);
+
+    transaction.set_valuation_date(as_of.as_ref()).await?;
+
+    if let Some(as_of) = as_of {
+        println!("{}", format!("As of {}", as_of.date_naive()).bold());
+        println!();
+    }
+
+    print_missing_valuation(transaction).await?;
+
     let records = transaction.get_net_asset_balance().await?;
 
     let formatted = records.into_iter().map(NetBalanceFormatted::from);
@@ -34,19 +51,19 @@ pub async fn report_balance(
     let balance_sheet_by_kind = vec![
         (
             "Cash and Cash Equivalent (Asset)",
-            make_table_str(transaction.get_cash_balance(None).await?),
+            make_table_str(transaction.get_cash_balance(range.clone()).await?),
         ),
         (
             "Stock (Asset)",
-            get_stock_unit_str(transaction.get_stock_unit(&Local::now()).await?),
+            get_stock_unit_str(transaction.get_stock_unit().await?),
         ),
         (
             "Equity",
-            make_table_str(transaction.get_equity_balance(None).await?),
+            make_table_str(transaction.get_equity_balance(range.clone()).await?),
         ),
         (
             "Liabilities",
-            make_table_str(transaction.get_liabilities_balance(None).await?),
+            make_table_str(transaction.get_liabilities_balance(range).await?),
         ),
     ];
 
@@ -56,5 +73,7 @@ pub async fn report_balance(
         println!();
     }
 
+    transaction.set_valuation_date(None).await?;
+
     Ok(())
 }
@@ -0,0 +1,163 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::{Datelike, Local, TimeZone};
+use common::all_time_in_year;
+use db::{write_csv, Disposition, Money, Transaction};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+pub async fn report_disposition(
+    transaction: &mut Transaction<'_>,
+    year: Option<i32>,
+    csv_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(Tabled)]
+    struct DispositionFormatted {
+        #[tabled(rename = "Holder Name")]
+        pub name: String,
+        #[tabled(rename = "Ticker")]
+        pub ticker: String,
+        #[tabled(rename = "Trade Date")]
+        pub trade_date: String,
+        #[tabled(rename = "Units")]
+        pub units: String,
+        #[tabled(rename = "Proceeds")]
+        pub proceeds: String,
+        #[tabled(rename = "ACB")]
+        pub acb: String,
+        #[tabled(rename = "Outlays")]
+        pub outlays: String,
+        #[tabled(rename = "Gain/Loss")]
+        pub capital_gl: String,
+        #[tabled(rename = "Superficial Loss")]
+        pub superficial_loss: String,
+    }
+
+    impl TryFrom<&Disposition> for DispositionFormatted {
+        type Error = String;
+
+        fn try_from(value: &Disposition) -> Result<Self, Self::Error> {
+            let format_cad = |v: Money| {
+                if v.is_zero() {
+                    "".into()
+                } else {
+                    format!("${:.2}", v)
+                }
+            };
+
+            let capital_gl = format!("${:.2}", value.capital_gl);
+
+            // the report is for filing, so a date that can't be shown is an error rather than a blank
+            let trade_date = Local
+                .timestamp_opt(value.trade_date, 0)
+                .single()
+                .ok_or_else(|| {
+                    format!(
+                        "the sale of {} by {} has an invalid date (timestamp {})",
+                        value.ticker, value.name, value.trade_date
+                    )
+                })?;
+
+            Ok(Self {
+                name: value.name.clone(),
+                ticker: value.ticker.clone(),
+                trade_date: trade_date.date_naive().to_string(),
+                units: format!("{:.4}", value.units),
+                proceeds: format!("${:.2}", value.proceeds),
+                acb: format!("${:.2}", value.acb),
+                outlays: format_cad(value.outlays),
+                capital_gl: if value.capital_gl.is_negative() {
+                    capital_gl.red().to_string()
+                } else {
+                    capital_gl.green().to_string()
+                },
+                superficial_loss: if value.superficial_loss.is_zero() {
+                    "".into()
+                } else {
+                    format_cad(value.superficial_loss).yellow().to_string()
+                },
+            })
+        }
+    }
+
+    let this_year = Local::now().year();
+
+    let year = year.unwrap_or(this_year);
+    if year > this_year {
+        return Err("No crystal ball error: unable to show dispositions for the future".into());
+    }
+
+    let records = transaction.get_disposition(all_time_in_year(year)).await?;
+
+    if let Some(csv_path) = csv_path {
+        write_csv(csv_path, &records)?;
+        println!("saved the dispositions to {}", csv_path.to_string_lossy());
+    }
+
+    if records.is_empty() {
+        return Err(format!("No disposition for {year}").into());
+    }
+
+    // the totals are the same as the capital gains of `report acb`
+    let everyone_total = records.iter().fold(
+        BTreeMap::<&str, (Money, Money, Money, Money)>::new(),
+        |mut acc, record| {
+            let value = acc.entry(&record.name).or_default();
+
+            value.0 += record.proceeds;
+            value.1 += record.acb;
+            value.2 += record.outlays;
+            value.3 += record.capital_gl;
+
+            acc
+        },
+    );
+
+    println!(
+        "{}",
+        format!("Dispositions of Capital Property (Schedule 3) for {year}").bold()
+    );
+
+    for (name, (proceeds, acb, outlays, capital_gl)) in everyone_total {
+        let capital_gl = if capital_gl.is_negative() {
+            format!("${:.2}", capital_gl).red().to_string()
+        } else {
+            format!("${:.2}", capital_gl).green().to_string()
+        };
+
+        println!(
+            "{}: proceeds=${:.2}, ACB=${:.2}, outlays=${:.2}, GL={}",
+            name, proceeds, acb, outlays, capital_gl
+        );
+    }
+    println!();
+
+    let has_superficial_loss = records
+        .iter()
+        .any(|record| !record.superficial_loss.is_zero());
+
+    let formatted = records
+        .iter()
+        .map(DispositionFormatted::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    println!(
+        "{}",
+        Table::new(formatted)
+            .with(Style::rounded())
+            .with(Columns::new(3..).modify().with(Alignment::right()))
+    );
+    println!(
+        "The year and the 30-day window of the superficial loss go by the trade dates, check the sales near their ends against the settlement dates (T+1, or T+2 before May 27, 2024) of the Schedule 3"
+    );
+    if has_superficial_loss {
+        println!(
+            "{}",
+            "The gain/loss leaves out the superficial losses, which are denied and added to the ACB of the units bought back"
+                .yellow()
+        );
+    }
+    println!();
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Local};
+use common::all_time_in_year;
+use db::{InvestmentIncome, Money, Transaction};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+pub async fn report_investment_income(
+    transaction: &mut Transaction<'_>,
+    year: Option<i32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(Tabled)]
+    struct InvestmentIncomeFormatted {
+        #[tabled(rename = "Holder Name")]
+        pub name: String,
+        #[tabled(rename = "Institution")]
+        pub institution_name: String,
+        #[tabled(rename = "Account")]
+        pub account_name: String,
+        #[tabled(rename = "Ticker")]
+        pub ticker: String,
+        #[tabled(rename = "Tax Shelter")]
+        pub tax_shelter_type: String,
+        #[tabled(rename = "Interest")]
+        pub interest: String,
+        #[tabled(rename = "Distribution")]
+        pub distribution: String,
+        #[tabled(rename = "Foreign Income")]
+        pub foreign_income: String,
+        #[tabled(rename = "Foreign Tax Withheld")]
+        pub foreign_tax_withheld: String,
+    }
+
+    impl From<&InvestmentIncome> for InvestmentIncomeFormatted {
+        fn from(value: &InvestmentIncome) -> Self {
+            let format_cad = |v: Money| {
+                if v.is_zero() {
+                    "".into()
+                } else {
+                    format!("${:.2}", v)
+                }
+            };
+
+            Self {
+                name: value.name.clone(),
+                institution_name: value.institution_name.clone(),
+                account_name: value.account_name.clone(),
+                ticker: value.ticker.clone().unwrap_or_default(),
+                tax_shelter_type: value.tax_shelter_type.clone(),
+                interest: format_cad(value.interest),
+                distribution: format_cad(value.distribution),
+                foreign_income: format_cad(value.foreign_income),
+                foreign_tax_withheld: format_cad(value.foreign_tax_withheld),
+            }
+        }
+    }
+
+    let this_year = Local::now().year();
+
+    let year = year.unwrap_or(this_year);
+    if year > this_year {
+        return Err(
+            "No crystal ball error: unable to show investment income for the future".into(),
+        );
+    }
+
+    let records = transaction
+        .get_investment_income(all_time_in_year(year))
+        .await?;
+
+    if records.is_empty() {
+        return Err(format!("No investment income for {year}").into());
+    }
+
+    // only the non-registered income shows up on the T3/T5 slips
+    let everyone_total = records.iter().fold(
+        BTreeMap::<(&str, bool), (Money, Money, Money, Money)>::new(),
+        |mut acc, record| {
+            let value = acc
+                .entry((&record.name, record.is_registered()))
+                .or_default();
+
+            value.0 += record.interest;
+            value.1 += record.distribution;
+            value.2 += record.foreign_income;
+            value.3 += record.foreign_tax_withheld;
+
+            acc
+        },
+    );
+
+    println!("{}", format!("Investment Income (T3/T5) for {year}").bold());
+
+    for ((name, is_registered), (interest, distribution, foreign_income, foreign_tax_withheld)) in
+        everyone_total
+    {
+        let shelter = if is_registered {
+            "registered".yellow().to_string()
+        } else {
+            "non-registered".green().to_string()
+        };
+
+        println!(
+            "{} ({}): interest=${:.2}, distribution=${:.2}, foreign income=${:.2}, foreign tax withheld=${:.2}",
+            name, shelter, interest, distribution, foreign_income, foreign_tax_withheld
+        );
+    }
+    println!();
+
+    let formatted = records.iter().map(InvestmentIncomeFormatted::from);
+
+    println!(
+        "{}",
+        Table::new(formatted)
+            .with(Style::rounded())
+            .with(Columns::new(5..).modify().with(Alignment::right()))
+    );
+    println!();
+
+    Ok(())
+}
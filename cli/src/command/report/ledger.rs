@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use chrono::{Local, NaiveDate};
+use common::{all_time_until, end_of_day, start_of_day};
+use db::{running_balances, LedgerFilter, Money, Transaction};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+pub async fn report_ledger(
+    transaction: &mut Transaction<'_>,
+    filter: &LedgerFilter<'_>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(Tabled)]
+    struct LedgerFormatted {
+        #[tabled(rename = "Date")]
+        pub date: NaiveDate,
+        #[tabled(rename = "Transaction ID")]
+        pub transaction_id: i64,
+        #[tabled(rename = "Account Key")]
+        pub account_key: String,
+        #[tabled(rename = "Description")]
+        pub description: String,
+        #[tabled(rename = "Counter Accounts")]
+        pub counter_accounts: String,
+        #[tabled(rename = "Debit")]
+        pub debit: String,
+        #[tabled(rename = "Credit")]
+        pub credit: String,
+        #[tabled(rename = "Balance")]
+        pub balance: String,
+    }
+
+    fn format_amount(value: Money) -> String {
+        if value.is_zero() {
+            "".into()
+        } else {
+            format!("${}", value)
+        }
+    }
+
+    let end = to.map(|date| end_of_day(&date)).unwrap_or_else(Local::now);
+    let range = match from {
+        Some(from) => start_of_day(&from)..end,
+        None => all_time_until(end),
+    };
+    if range.start > range.end {
+        return Err(format!(
+            "The ledger starts ({}) after it ends ({})",
+            range.start.date_naive(),
+            range.end.date_naive()
+        )
+        .into());
+    }
+
+    let opening_balances = transaction
+        .get_ledger_opening_balances(filter, &range.start)
+        .await?;
+    let records = transaction.get_ledger(filter, range.clone()).await?;
+
+    // each row shows the balance of its own account, the accounts of a wildcard can be of different kinds
+    let mut closing_balances = opening_balances.clone();
+    let balances = running_balances(&mut closing_balances, &records);
+
+    let formatted: Vec<_> = records
+        .into_iter()
+        .zip(balances)
+        .map(|(record, balance)| LedgerFormatted {
+            date: record.date.date_naive(),
+            transaction_id: record.transaction_id,
+            account_key: record.account_key,
+            description: record.description,
+            counter_accounts: record.counter_accounts.replace(',', "\n"),
+            debit: format_amount(record.unit.times(record.debit.unwrap_or_default())),
+            credit: format_amount(record.unit.times(record.credit.unwrap_or_default())),
+            balance: format!("${}", balance),
+        })
+        .collect();
+
+    println!(
+        "{}",
+        format!(
+            "Ledger of {} from {} to {}",
+            filter.account_key,
+            range.start.date_naive(),
+            range.end.date_naive()
+        )
+        .bold()
+    );
+    print_balances("Opening balance", &opening_balances, &closing_balances);
+    println!(
+        "{}",
+        Table::new(formatted)
+            .with(Style::rounded())
+            .with(Columns::single(1).modify().with(Alignment::right()))
+            .with(Columns::new(5..).modify().with(Alignment::right()))
+    );
+    print_balances("Closing balance", &closing_balances, &closing_balances);
+
+    Ok(())
+}
+
+/// Prints the balance of every account in the ledger, i.e. the accounts of `accounts`, which is a single line when the
+/// filter matches one account.
+fn print_balances(
+    label: &str,
+    balances: &BTreeMap<String, Money>,
+    accounts: &BTreeMap<String, Money>,
+) {
+    match accounts.len() {
+        0 | 1 => {
+            let balance = accounts
+                .keys()
+                .next()
+                .and_then(|account_key| balances.get(account_key))
+                .copied()
+                .unwrap_or_default();
+            println!("{label}: ${balance}");
+        }
+        _ => {
+            println!("{label}s:");
+            for account_key in accounts.keys() {
+                let balance = balances.get(account_key).copied().unwrap_or_default();
+                println!("  {account_key}: ${balance}");
+            }
+        }
+    }
+}
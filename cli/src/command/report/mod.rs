@@ -1,23 +1,37 @@
 mod acb;
 mod balance;
 mod cashflow;
+mod disposition;
 mod expense;
+mod investment_income;
+mod ledger;
+mod statement;
 mod stock_transaction;
 mod stock_unit;
 mod transaction;
+mod trial_balance;
+
+use std::path::{Path, PathBuf};
 
 use balance::report_balance;
 use cashflow::report_cashflow;
-use chrono::{Local, NaiveDate};
+use chrono::NaiveDate;
 use clap::Subcommand;
-use common::start_of_day;
-use db::{BalanceRecord, Db, NetBalanceRecord};
+use common::{end_of_day, Period};
+use db::{BalanceRecord, Db, LedgerFilter, NetBalanceRecord};
 use expense::report_expense;
 use tabled::Tabled;
 
 use self::{
-    acb::report_acb, stock_transaction::report_stock_transaction, stock_unit::report_stock_unit,
+    acb::report_acb,
+    disposition::report_disposition,
+    investment_income::report_investment_income,
+    ledger::report_ledger,
+    statement::{report_balance_sheet, report_income_statement},
+    stock_transaction::report_stock_transaction,
+    stock_unit::report_stock_unit,
     transaction::report_transaction,
+    trial_balance::report_trial_balance,
 };
 
 #[derive(Tabled)]
@@ -88,8 +102,34 @@ pub enum ReportCommand {
         /// If the field is not provided, the default value is the current year.
         year: Option<i32>,
     },
+    /// List the sales of the securities held outside of the registered accounts in a specific year, with their
+    /// proceeds, ACB, outlays and capital gain/loss, for the Schedule 3. The sales are dated by their trade date, while
+    /// the Schedule 3 goes by the settlement date.
+    Disposition {
+        /// The `year` field is an optional integer that specifies the year for the report.
+        /// If the field is not provided, the default value is the current year.
+        year: Option<i32>,
+        /// Also writes the dispositions into the CSV file.
+        #[clap(long)]
+        csv: Option<PathBuf>,
+    },
     /// Display your total assets, calculated as equity minus liabilities.
-    Balance,
+    Balance {
+        /// Values the accounts with the entries and the security prices up to the end of the given date.
+        /// If the field is not provided, the current balances and prices are shown.
+        #[clap(long)]
+        as_of: Option<NaiveDate>,
+    },
+    /// Show the book value of the assets, liabilities and equity at a point in time, with the net income rolled into equity.
+    BalanceSheet {
+        /// Includes the entries up to the end of the given date.
+        /// If neither this nor `period` is provided, the entries up to now are included.
+        #[clap(long, conflicts_with = "period")]
+        as_of: Option<NaiveDate>,
+        /// Shows the balance sheet at the end of a year, quarter or month, e.g. 2024, 2024-Q2 or 2024-06.
+        #[clap(long)]
+        period: Option<Period>,
+    },
     /// Generate a report of your cash flow, including revenue and expenses, for a specific year.
     Cashflow {
         /// The `year` field is an optional integer that specifies the year for the report.
@@ -103,6 +143,44 @@ pub enum ReportCommand {
         #[clap(default_value_t = 30)]
         days_prior: u64,
     },
+    /// Break down the interest, distributions, foreign income and foreign tax withheld of a specific year by account and
+    /// security, with the registered and non-registered totals, to check against the T3/T5 slips.
+    InvestmentIncome {
+        /// The `year` field is an optional integer that specifies the year for the report.
+        /// If the field is not provided, the default value is the current year.
+        year: Option<i32>,
+    },
+    /// Show the revenue, expense and net income of a period.
+    IncomeStatement {
+        /// The first day of the statement. Defaults to the start of `period`.
+        #[clap(long)]
+        from: Option<NaiveDate>,
+        /// The last day of the statement. Defaults to the end of `period`.
+        #[clap(long)]
+        to: Option<NaiveDate>,
+        /// A year, quarter or month, e.g. 2024, 2024-Q2 or 2024-06.
+        /// If the field is not provided, the default value is the current year.
+        #[clap(long)]
+        period: Option<Period>,
+    },
+    /// List the entries of the matching accounts with the counter-accounts and a running balance, e.g. to reconcile a statement.
+    Ledger {
+        /// The account_key, which can have the wildcards `*` and `?`, e.g. ALICE-* for all the accounts of a person.
+        account_key: String,
+        /// Only includes the accounts of the given subtype, e.g. CASH.
+        #[clap(long)]
+        subtype: Option<String>,
+        /// Only includes the accounts owned by the given person_key.
+        #[clap(long)]
+        person: Option<String>,
+        /// The first day of the ledger, the entries before it are summed into the opening balance.
+        /// If the field is not provided, the ledger starts from the first entry.
+        #[clap(long)]
+        from: Option<NaiveDate>,
+        /// The last day of the ledger. If the field is not provided, the ledger ends now.
+        #[clap(long)]
+        to: Option<NaiveDate>,
+    },
     /// List all stock-related transactions for a specific ticker, up to a specified limit.
     StockTransaction {
         /// The `ticker` field is a string that specifies the ticker to be searched for.
@@ -113,10 +191,11 @@ pub enum ReportCommand {
     },
     /// Show the number of units each person has per account by the given date.
     StockUnit {
-        /// The `date` field is an optional date that specifies the date before which
-        /// stock ownership should be shown. If the field is not provided, ownership will be
-        /// shown up to the start of the current day (i.e., midnight). This command is useful
-        /// for calculating distributions by querying data with the ex-dividend date.
+        /// The `date` field is an optional date that specifies the date up to the end of which
+        /// stock ownership should be shown, like `report balance --as-of`. If the field is not provided,
+        /// ownership will be shown up to now. This command is useful for calculating distributions
+        /// by querying data with the day before the ex-dividend date.
+        /// Market values use the latest recorded price on or before the given date.
         date: Option<NaiveDate>,
     },
     /// Display all transactions for a specific account_key within the specified time period.
@@ -128,19 +207,35 @@ pub enum ReportCommand {
         #[clap(default_value_t = 30)]
         days_prior: u64,
     },
+    /// List the debit or credit balance of every account, grouped by account kind and subtype.
+    TrialBalance {
+        /// Only includes the entries up to the end of the given date.
+        /// If the field is not provided, the entries up to now are included.
+        #[clap(long)]
+        as_of: Option<NaiveDate>,
+        /// Only includes the transactions that touch an account of the given person_key.
+        #[clap(long)]
+        person: Option<String>,
+    },
 }
 
 impl ReportCommand {
-    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut db = Db::new().await?;
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Db::from_path(db_path.to_path_buf()).await?;
         let mut transaction = db.begin_wrapped_transaction().await?;
 
         match self {
             Self::Acb { year } => {
                 report_acb(&mut transaction, year.clone()).await?;
             }
-            Self::Balance => {
-                report_balance(&mut transaction).await?;
+            Self::Disposition { year, csv } => {
+                report_disposition(&mut transaction, *year, csv.as_deref()).await?;
+            }
+            Self::Balance { as_of } => {
+                report_balance(&mut transaction, *as_of).await?;
+            }
+            Self::BalanceSheet { as_of, period } => {
+                report_balance_sheet(&mut transaction, *as_of, *period).await?;
             }
             Self::Cashflow { year } => {
                 report_cashflow(&mut transaction, year.clone()).await?;
@@ -148,14 +243,36 @@ impl ReportCommand {
             Self::Expense { days_prior } => {
                 report_expense(&mut transaction, *days_prior).await?;
             }
+            Self::InvestmentIncome { year } => {
+                report_investment_income(&mut transaction, *year).await?;
+            }
+            Self::IncomeStatement { from, to, period } => {
+                report_income_statement(&mut transaction, *from, *to, *period).await?;
+            }
+            Self::Ledger {
+                account_key,
+                subtype,
+                person,
+                from,
+                to,
+            } => {
+                let account_key = account_key.to_uppercase();
+                let subtype = subtype.as_ref().map(|subtype| subtype.to_uppercase());
+                let person = person.as_ref().map(|person| person.to_uppercase());
+                let filter = LedgerFilter {
+                    account_key: &account_key,
+                    account_subtype: subtype.as_deref(),
+                    person_key: person.as_deref(),
+                };
+
+                report_ledger(&mut transaction, &filter, *from, *to).await?;
+            }
             Self::StockTransaction { ticker, limit } => {
                 report_stock_transaction(&mut transaction, ticker, *limit).await?;
             }
             Self::StockUnit { date } => {
-                let datetime = date
-                    .map(|date| start_of_day(&date))
-                    .unwrap_or_else(Local::now);
-                report_stock_unit(&mut transaction, datetime).await?;
+                let as_of = date.map(|date| end_of_day(&date));
+                report_stock_unit(&mut transaction, as_of).await?;
             }
             Self::Transaction {
                 account_key,
@@ -163,6 +280,9 @@ impl ReportCommand {
             } => {
                 report_transaction(&mut transaction, &account_key, *days_prior).await?;
             }
+            Self::TrialBalance { as_of, person } => {
+                report_trial_balance(&mut transaction, *as_of, person.as_deref()).await?;
+            }
         };
 
         transaction.commit().await?;
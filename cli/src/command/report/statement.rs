@@ -0,0 +1,152 @@
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use common::{all_time_until, end_of_day, start_of_day, Period};
+use db::{BalanceRecord, Money, Transaction};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table};
+
+use crate::command::report::BalanceRecordWithOwnerFormatted;
+
+/// Prints the accounts of one section of a statement and returns the section's total.
+fn print_section(title: &str, records: Vec<BalanceRecord>) -> Money {
+    let total: Money = records.iter().map(|record| record.balance).sum();
+
+    println!("{}", title.bold());
+    if !records.is_empty() {
+        println!(
+            "{}",
+            Table::new(
+                records
+                    .into_iter()
+                    .map(BalanceRecordWithOwnerFormatted::from)
+            )
+            .with(Style::rounded())
+            .with(Columns::single(2).modify().with(Alignment::right()))
+        );
+    }
+    println!("Total {}: ${}", title, total);
+    println!();
+
+    total
+}
+
+/// Book values of the asset, liabilities and equity accounts at the end of the given date.
+/// The net income since the last closed period is rolled into equity, the rest is in the retained earnings.
+pub async fn report_balance_sheet(
+    transaction: &mut Transaction<'_>,
+    as_of: Option<NaiveDate>,
+    period: Option<Period>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let as_of = as_of
+        .or(period.map(|period| period.last_day()))
+        .map(|date| end_of_day(&date))
+        .unwrap_or_else(Local::now);
+    let range = all_time_until(as_of);
+
+    println!(
+        "{}",
+        format!("Balance Sheet as of {}", as_of.date_naive()).bold()
+    );
+    println!();
+
+    let asset = print_section(
+        "Assets",
+        transaction.get_asset_balance(Some(range.clone())).await?,
+    );
+    let liabilities = print_section(
+        "Liabilities",
+        transaction
+            .get_liabilities_balance(Some(range.clone()))
+            .await?,
+    );
+
+    let closed_until = transaction.get_closed_until_as_of(&as_of).await?;
+    let open_range = match closed_until {
+        Some(closed_until) => (closed_until + Duration::seconds(1))..as_of,
+        None => range.clone(),
+    };
+
+    let revenue: Money = transaction
+        .get_revenue_balance(Some(open_range.clone()))
+        .await?
+        .iter()
+        .map(|record| record.balance)
+        .sum();
+    let expense: Money = transaction
+        .get_expense_balance(Some(open_range))
+        .await?
+        .iter()
+        .map(|record| record.balance)
+        .sum();
+    let net_income = revenue - expense;
+
+    let mut equity = transaction.get_equity_balance(Some(range)).await?;
+    equity.push(BalanceRecord {
+        name: "".into(),
+        account_type: "".into(),
+        account_name: match closed_until {
+            Some(closed_until) => format!("Net Income since {}", closed_until.date_naive()),
+            None => "Net Income".into(),
+        },
+        balance: net_income,
+    });
+    let equity = print_section("Equity", equity);
+
+    println!(
+        "{}",
+        format!("Total Liabilities and Equity: ${}", liabilities + equity).bold()
+    );
+
+    if asset != liabilities + equity {
+        println!(
+            "{}",
+            format!(
+                "Assets are off by ${} from liabilities and equity, see the trial-balance report",
+                (asset - liabilities - equity).abs()
+            )
+            .red()
+            .bold()
+        );
+    }
+
+    Ok(())
+}
+
+/// Revenue and expense of the accounts between the two dates, both inclusive.
+pub async fn report_income_statement(
+    transaction: &mut Transaction<'_>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    period: Option<Period>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let period = period.unwrap_or_else(|| Period::Year(Local::now().year()));
+
+    let from = from.unwrap_or_else(|| period.first_day());
+    let to = to.unwrap_or_else(|| period.last_day());
+    if from > to {
+        return Err(format!("The period starts ({from}) after it ends ({to})").into());
+    }
+
+    let range = start_of_day(&from)..end_of_day(&to);
+
+    println!("{}", format!("Income Statement from {from} to {to}").bold());
+    println!();
+
+    let revenue = print_section(
+        "Revenue",
+        transaction.get_revenue_balance(Some(range.clone())).await?,
+    );
+    let expense = print_section(
+        "Expense",
+        transaction.get_expense_balance(Some(range)).await?,
+    );
+
+    let net_income = revenue - expense;
+    let net_income_str = format!("Net Income: ${}", net_income);
+    if net_income.is_negative() {
+        println!("{}", net_income_str.red().bold());
+    } else {
+        println!("{}", net_income_str.green().bold());
+    }
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Local};
-use db::{SqlResult, StockTransaction, Transaction};
+use db::{Price, SqlResult, StockTransaction, Transaction};
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
 
 pub async fn report_stock_transaction(
@@ -23,14 +23,14 @@ pub async fn report_stock_transaction(
         pub debit: String,
         #[tabled(rename = "Credit")]
         pub credit: String,
+        #[tabled(rename = "Corporate Action")]
+        pub action: String,
     }
 
     impl From<StockTransaction> for StockTransactionFormatted {
         fn from(value: StockTransaction) -> Self {
-            fn format_currency(value: Option<f64>) -> String {
-                value
-                    .map(|value| format!("${:.2}", value))
-                    .unwrap_or_default()
+            fn format_currency(value: Option<Price>) -> String {
+                value.map(|value| format!("${}", value)).unwrap_or_default()
             }
 
             Self {
@@ -41,6 +41,7 @@ pub async fn report_stock_transaction(
                 unit: format!("{:.4}", value.unit),
                 debit: format_currency(value.debit),
                 credit: format_currency(value.credit),
+                action: value.action.unwrap_or_default(),
             }
         }
     }
@@ -55,8 +56,7 @@ pub async fn report_stock_transaction(
         "{}",
         Table::new(formatted)
             .with(Style::rounded())
-            .with(Columns::new(4..).modify().with(Alignment::right()))
-            .to_string()
+            .with(Columns::new(4..7).modify().with(Alignment::right()))
     );
 
     Ok(())
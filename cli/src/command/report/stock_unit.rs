@@ -1,5 +1,6 @@
 use chrono::{DateTime, Local};
 use db::{SqlResult, StockUnit, Transaction};
+use owo_colors::OwoColorize;
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
 
 pub fn get_stock_unit_str(records: Vec<StockUnit>) -> String {
@@ -24,7 +25,9 @@ pub fn get_stock_unit_str(records: Vec<StockUnit>) -> String {
                 account_name: value.account_name,
                 ticker: value.ticker,
                 unit: format!("{:.4}", value.total_unit),
-                market_value: format!("{:.2}", value.market_value),
+                market_value: value
+                    .market_value
+                    .map_or_else(|| "no price".yellow().to_string(), |v| format!("{:.2}", v)),
             }
         }
     }
@@ -41,15 +44,17 @@ pub fn get_stock_unit_str(records: Vec<StockUnit>) -> String {
 
 pub async fn report_stock_unit(
     transaction: &mut Transaction<'_>,
-    datetime: DateTime<Local>,
+    as_of: Option<DateTime<Local>>,
 ) -> SqlResult<()> {
-    let records = transaction.get_stock_unit(&datetime).await?;
+    transaction.set_valuation_date(as_of.as_ref()).await?;
+    let records = transaction.get_stock_unit().await?;
+    transaction.set_valuation_date(None).await?;
 
     println!();
-    println!(
-        r#"Stock ownership by {} (i.e. strictly "less than")"#,
-        datetime.date_naive()
-    );
+    match as_of {
+        Some(as_of) => println!("Stock ownership as of {}", as_of.date_naive()),
+        None => println!("Stock ownership"),
+    }
     println!("{}", get_stock_unit_str(records));
 
     Ok(())
@@ -1,6 +1,6 @@
 use chrono::{DateTime, Local};
 use common::days_prior_until_end_of_today;
-use db::{SqlResult, Transaction, TransactionByAccountKey};
+use db::{Money, SqlResult, Transaction, TransactionByAccountKey};
 use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
 
 pub async fn report_transaction(
@@ -38,12 +38,12 @@ pub async fn report_transaction(
                 date: value.date,
                 unit: format!("{:.4}", value.unit),
                 debit: if let Some(debit) = value.debit {
-                    format!("${:.2}", debit)
+                    format!("${}", debit)
                 } else {
                     "".into()
                 },
                 credit: if let Some(credit) = value.credit {
-                    format!("${:.2}", credit)
+                    format!("${}", credit)
                 } else {
                     "".into()
                 },
@@ -62,16 +62,16 @@ pub async fn report_transaction(
         .get_transaction_by_account_key(account_key, days_prior_until_end_of_today(days_prior))
         .await?;
 
-    let (total_debit, total_credit, total) =
-        records
-            .iter()
-            .fold((0.0, 0.0, 0.0), |(debit, credit, _), record| {
-                let unit = record.unit;
-                let debit = debit + unit * record.debit.unwrap_or_default();
-                let credit = credit + unit * record.credit.unwrap_or_default();
-                let total = debit - credit;
-                (debit, credit, total)
-            });
+    let (total_debit, total_credit, total) = records.iter().fold(
+        (Money::ZERO, Money::ZERO, Money::ZERO),
+        |(debit, credit, _), record| {
+            let unit = record.unit;
+            let debit = debit + unit.times(record.debit.unwrap_or_default());
+            let credit = credit + unit.times(record.credit.unwrap_or_default());
+            let total = debit - credit;
+            (debit, credit, total)
+        },
+    );
 
     let formatted = records
         .into_iter()
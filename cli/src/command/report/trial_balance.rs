@@ -0,0 +1,108 @@
+use chrono::{Local, NaiveDate};
+use common::end_of_day;
+use db::{Money, Transaction, TrialBalanceRecord};
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+pub async fn report_trial_balance(
+    transaction: &mut Transaction<'_>,
+    as_of: Option<NaiveDate>,
+    person_key: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(Tabled)]
+    struct TrialBalanceFormatted {
+        #[tabled(rename = "Kind")]
+        pub account_kind: String,
+        #[tabled(rename = "Subtype")]
+        pub account_subtype: String,
+        #[tabled(rename = "Account Key")]
+        pub account_key: String,
+        #[tabled(rename = "Account Name")]
+        pub account_name: String,
+        #[tabled(rename = "Debit")]
+        pub debit: String,
+        #[tabled(rename = "Credit")]
+        pub credit: String,
+    }
+
+    fn format_amount(value: Money) -> String {
+        if value.is_zero() {
+            "".into()
+        } else {
+            format!("${}", value)
+        }
+    }
+
+    let as_of = as_of
+        .map(|date| end_of_day(&date))
+        .unwrap_or_else(Local::now);
+
+    let records = transaction.get_trial_balance(&as_of, person_key).await?;
+
+    let total_debit: Money = records.iter().map(|record| record.debit).sum();
+    let total_credit: Money = records.iter().map(|record| record.credit).sum();
+
+    // only the first row of each kind and subtype is labelled, so that the groups stand out
+    let mut prev: Option<&TrialBalanceRecord> = None;
+    let mut formatted = Vec::with_capacity(records.len() + 1);
+    for record in &records {
+        let is_same_kind = prev.is_some_and(|prev| prev.account_kind == record.account_kind);
+        let is_same_subtype =
+            is_same_kind && prev.is_some_and(|prev| prev.account_subtype == record.account_subtype);
+
+        formatted.push(TrialBalanceFormatted {
+            account_kind: if is_same_kind {
+                "".into()
+            } else {
+                record.account_kind.clone()
+            },
+            account_subtype: if is_same_subtype {
+                "".into()
+            } else {
+                record.account_subtype.clone()
+            },
+            account_key: record.account_key.clone(),
+            account_name: record.account_name.clone(),
+            debit: format_amount(record.debit),
+            credit: format_amount(record.credit),
+        });
+        prev = Some(record);
+    }
+    formatted.push(TrialBalanceFormatted {
+        account_kind: "Total".bold().to_string(),
+        account_subtype: "".into(),
+        account_key: "".into(),
+        account_name: "".into(),
+        debit: format!("${}", total_debit).bold().to_string(),
+        credit: format!("${}", total_credit).bold().to_string(),
+    });
+
+    let title = match person_key {
+        Some(person_key) => format!("Trial Balance of {person_key} as of {}", as_of.date_naive()),
+        None => format!("Trial Balance as of {}", as_of.date_naive()),
+    };
+
+    println!("{}", title.bold());
+    println!(
+        "{}",
+        Table::new(formatted)
+            .with(Style::rounded())
+            .with(Columns::new(4..).modify().with(Alignment::right()))
+    );
+
+    if total_debit == total_credit {
+        println!("{}", "Debits and credits are balanced".green());
+    } else {
+        println!(
+            "{}",
+            format!(
+                "Debits and credits are off by ${}, the check command lists the unbalanced transactions",
+                (total_debit - total_credit).abs()
+            )
+            .red()
+            .bold()
+        );
+    }
+
+    Ok(())
+}
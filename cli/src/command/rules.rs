@@ -0,0 +1,135 @@
+use std::path::{Path, PathBuf};
+
+use clap::Subcommand;
+use db::{append_csv, AppliedRule, Db, Money};
+use owo_colors::OwoColorize;
+use tabled::{Style, Table, Tabled};
+
+#[derive(Tabled)]
+pub struct AppliedRuleFormatted {
+    #[tabled(rename = "ID")]
+    transaction_id: i64,
+    #[tabled(rename = "Description")]
+    description: String,
+    #[tabled(rename = "Store")]
+    store_key: String,
+    #[tabled(rename = "Rule")]
+    rule_id: i64,
+}
+
+impl From<&AppliedRule> for AppliedRuleFormatted {
+    fn from(value: &AppliedRule) -> Self {
+        Self {
+            transaction_id: value.store.transaction_id,
+            description: value.description.clone(),
+            store_key: value.store.store_key.clone(),
+            rule_id: value.rule_id,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RulesCommand {
+    /// Assigns a store to the purchases without one, with the rules in rule.csv.
+    Apply {
+        /// Prints the stores that would be assigned, then rolls everything back.
+        #[clap(long)]
+        dry_run: bool,
+        /// The directory of the CSV files, the new rows are appended to transaction_store.csv.
+        #[clap(long)]
+        csv_folder: Option<PathBuf>,
+    },
+    /// Shows which rule fires for a description.
+    Test {
+        /// The `description` field is the description of the transaction, e.g. the payee on the statement.
+        description: String,
+        /// The `amount` field is the amount of the transaction.
+        #[clap(long, default_value_t = Money::ZERO)]
+        amount: Money,
+        /// The `account` field is the account that pays, e.g. ALICE-BB-VISA-DEBT.
+        #[clap(long)]
+        account: Option<String>,
+    },
+}
+
+impl RulesCommand {
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Db::from_path(db_path.to_path_buf()).await?;
+        let mut transaction = db.begin_wrapped_transaction().await?;
+
+        match self {
+            Self::Apply {
+                dry_run,
+                csv_folder,
+            } => {
+                let applied = transaction.apply_rules(None).await?;
+
+                if applied.is_empty() {
+                    println!("No rule fires for the purchases without a store");
+                } else {
+                    println!(
+                        "{}",
+                        Table::new(applied.iter().map(AppliedRuleFormatted::from))
+                            .with(Style::rounded())
+                    );
+                    println!("Assigned the store of {} purchases", applied.len());
+                }
+
+                if *dry_run {
+                    transaction.rollback().await?;
+                    println!("{}", "Dry run, nothing was written".yellow().bold());
+                    return Ok(());
+                }
+
+                if !applied.is_empty() {
+                    let stores: Vec<_> = applied.into_iter().map(|applied| applied.store).collect();
+
+                    match csv_folder {
+                        Some(csv_folder) => {
+                            append_csv(&csv_folder.join("transaction_store.csv"), &stores)?
+                        }
+                        None => println!(
+                            "{}: no --csv-folder, use `export all` to write the stores into transaction_store.csv",
+                            "Warning".bold().yellow()
+                        ),
+                    }
+                }
+            }
+            Self::Test {
+                description,
+                amount,
+                account,
+            } => {
+                let rules = transaction.get_rules().await?;
+                let account = account.as_ref().map(|account| account.to_uppercase());
+
+                match rules.find(description, amount.abs(), account.as_deref()) {
+                    Some(rule) => {
+                        println!(
+                            "Rule {} fires, {:?}{}",
+                            rule.record.rule_id.to_string().bold().yellow(),
+                            rule.record.pattern,
+                            if rule.record.is_regex {
+                                " (regular expression)"
+                            } else {
+                                ""
+                            }
+                        );
+                        if let Some(store_key) = &rule.record.store_key {
+                            println!("Store: {store_key}");
+                        }
+                        if let Some(expense_account_key) = &rule.expense_account_key {
+                            println!("Expense account: {expense_account_key}");
+                        }
+                    }
+                    None => println!("No rule fires"),
+                }
+            }
+        }
+
+        transaction.commit().await?;
+        db.optimize().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use clap::Subcommand;
+use db::Db;
+use owo_colors::OwoColorize;
+use tabled::{object::Columns, Alignment, ModifyObject, Style, Table, Tabled};
+
+#[derive(Debug, Subcommand)]
+pub enum SnapshotCommand {
+    /// List the snapshots of the database, oldest first.
+    List,
+    /// Delete all but the most recent snapshots.
+    Prune {
+        /// The `keep` field specifies the number of snapshots to keep.
+        keep: usize,
+    },
+}
+
+impl SnapshotCommand {
+    pub async fn run(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::List => {
+                #[derive(Tabled)]
+                struct SnapshotFormatted {
+                    #[tabled(rename = "Snapshot")]
+                    pub name: String,
+                    #[tabled(rename = "Created")]
+                    pub created: String,
+                    #[tabled(rename = "Size")]
+                    pub size: String,
+                }
+
+                let mut records = Vec::new();
+
+                for path in Db::list_snapshots(db_path)? {
+                    let metadata = path.metadata()?;
+                    let created: DateTime<Local> = metadata.modified()?.into();
+
+                    records.push(SnapshotFormatted {
+                        name: path
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                        created: created.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        size: format!("{:.1} KiB", metadata.len() as f64 / 1024.0),
+                    });
+                }
+
+                println!(
+                    "Snapshots in {}",
+                    Db::snapshot_dir(db_path).to_string_lossy()
+                );
+                println!(
+                    "{}",
+                    Table::new(records)
+                        .with(Style::rounded())
+                        .with(Columns::single(2).modify().with(Alignment::right()))
+                );
+            }
+            Self::Prune { keep } => {
+                for path in Db::prune_snapshots(db_path, *keep)? {
+                    println!("deleted {}", path.to_string_lossy());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn backup(
+    db_path: &Path,
+    target: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut db = Db::from_path(db_path.to_path_buf()).await?;
+
+    let target = match target {
+        Some(target) => {
+            db.backup(target).await?;
+            target.to_path_buf()
+        }
+        None => db.snapshot(db_path).await?,
+    };
+
+    println!("saved backup to {}", target.to_string_lossy().green());
+
+    Ok(())
+}
+
+pub async fn restore(db_path: &Path, snapshot: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // either a path or the name of a snapshot as shown by `snapshots list`
+    let snapshot_path = PathBuf::from(snapshot);
+    let snapshot_path = if snapshot_path.is_file() {
+        snapshot_path
+    } else {
+        Db::snapshot_dir(db_path).join(format!("{snapshot}.sqlite3"))
+    };
+
+    let current = Db::restore(db_path, &snapshot_path).await?;
+
+    println!(
+        "restored {} from {}",
+        db_path.to_string_lossy(),
+        snapshot_path.to_string_lossy().green()
+    );
+    println!(
+        "the previous state was saved to {}",
+        current.to_string_lossy()
+    );
+
+    Ok(())
+}
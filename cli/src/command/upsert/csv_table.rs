@@ -0,0 +1,441 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use db::{
+    Account, AccountSubtype, AccountType, AssetAllocation, AssetClass, AssetClassName,
+    CashAccountHolder, CashAccountProduct, CashbackCategory, CashbackCategoryName,
+    CategorizationRule, CorporateAction, CreditCard, CreditCardHolder, Currency, Exchange,
+    ExchangeRate, FinancialEntry, GicAccount, GicAccountHolder, IncomeAccount, IncomeAccountHolder,
+    Institution, Person, PrepaidAccount, RecurringEntry, Security, SecurityPrice, StockAccount,
+    StockAccountHolder, Store, StoreCashbackMapping, TableDiff, TaxShelterType, Transaction,
+    TransactionForex, TransactionStore,
+};
+
+use super::upsert_transaction::upsert_transaction;
+
+/// Every table that is kept in a CSV file, in the order of `upsert all`: a table comes after the tables it references.
+/// `export all`, `upsert`, `--prune` and the dry-run diff all go through this list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CsvTable {
+    Person,
+    Institution,
+    Currency,
+    ExchangeRate,
+    Exchange,
+    Security,
+    SecurityPrice,
+    CorporateAction,
+    AssetClassName,
+    AssetClass,
+    AssetAllocation,
+    TaxShelterType,
+    AccountSubtype,
+    AccountType,
+    Account,
+    CashAccountProduct,
+    CashAccountHolder,
+    CashbackCategoryName,
+    CreditCardProduct,
+    CreditCardHolder,
+    CashbackCategory,
+    Store,
+    StoreCashbackMapping,
+    StockAccount,
+    StockAccountHolder,
+    GicAccount,
+    GicAccountHolder,
+    IncomeAccount,
+    IncomeAccountHolder,
+    PrepaidAccount,
+    TransactionStore,
+    TransactionForex,
+    Recurring,
+    Rule,
+    Transaction,
+}
+
+/// Runs `$body` with `$record` naming the record type of the table.
+macro_rules! with_record {
+    ($table:expr, $record:ident => $body:expr) => {
+        match $table {
+            CsvTable::Person => {
+                type $record = Person;
+                $body
+            }
+            CsvTable::Institution => {
+                type $record = Institution;
+                $body
+            }
+            CsvTable::Currency => {
+                type $record = Currency;
+                $body
+            }
+            CsvTable::ExchangeRate => {
+                type $record = ExchangeRate;
+                $body
+            }
+            CsvTable::Exchange => {
+                type $record = Exchange;
+                $body
+            }
+            CsvTable::Security => {
+                type $record = Security;
+                $body
+            }
+            CsvTable::SecurityPrice => {
+                type $record = SecurityPrice;
+                $body
+            }
+            CsvTable::CorporateAction => {
+                type $record = CorporateAction;
+                $body
+            }
+            CsvTable::AssetClassName => {
+                type $record = AssetClassName;
+                $body
+            }
+            CsvTable::AssetClass => {
+                type $record = AssetClass;
+                $body
+            }
+            CsvTable::AssetAllocation => {
+                type $record = AssetAllocation;
+                $body
+            }
+            CsvTable::TaxShelterType => {
+                type $record = TaxShelterType;
+                $body
+            }
+            CsvTable::AccountSubtype => {
+                type $record = AccountSubtype;
+                $body
+            }
+            CsvTable::AccountType => {
+                type $record = AccountType;
+                $body
+            }
+            CsvTable::Account => {
+                type $record = Account;
+                $body
+            }
+            CsvTable::CashAccountProduct => {
+                type $record = CashAccountProduct;
+                $body
+            }
+            CsvTable::CashAccountHolder => {
+                type $record = CashAccountHolder;
+                $body
+            }
+            CsvTable::CashbackCategoryName => {
+                type $record = CashbackCategoryName;
+                $body
+            }
+            CsvTable::CreditCardProduct => {
+                type $record = CreditCard;
+                $body
+            }
+            CsvTable::CreditCardHolder => {
+                type $record = CreditCardHolder;
+                $body
+            }
+            CsvTable::CashbackCategory => {
+                type $record = CashbackCategory;
+                $body
+            }
+            CsvTable::Store => {
+                type $record = Store;
+                $body
+            }
+            CsvTable::StoreCashbackMapping => {
+                type $record = StoreCashbackMapping;
+                $body
+            }
+            CsvTable::StockAccount => {
+                type $record = StockAccount;
+                $body
+            }
+            CsvTable::StockAccountHolder => {
+                type $record = StockAccountHolder;
+                $body
+            }
+            CsvTable::GicAccount => {
+                type $record = GicAccount;
+                $body
+            }
+            CsvTable::GicAccountHolder => {
+                type $record = GicAccountHolder;
+                $body
+            }
+            CsvTable::IncomeAccount => {
+                type $record = IncomeAccount;
+                $body
+            }
+            CsvTable::IncomeAccountHolder => {
+                type $record = IncomeAccountHolder;
+                $body
+            }
+            CsvTable::PrepaidAccount => {
+                type $record = PrepaidAccount;
+                $body
+            }
+            CsvTable::TransactionStore => {
+                type $record = TransactionStore;
+                $body
+            }
+            CsvTable::TransactionForex => {
+                type $record = TransactionForex;
+                $body
+            }
+            CsvTable::Recurring => {
+                type $record = RecurringEntry;
+                $body
+            }
+            CsvTable::Rule => {
+                type $record = CategorizationRule;
+                $body
+            }
+            CsvTable::Transaction => {
+                type $record = FinancialEntry;
+                $body
+            }
+        }
+    };
+}
+
+/// How the records of a table are read from its CSV file.
+trait CsvRecord {
+    const PRUNABLE: bool = false;
+
+    async fn upsert(
+        transaction: &mut Transaction<'_>,
+        csv_path: &Path,
+        prune: bool,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    async fn prune(
+        _transaction: &mut Transaction<'_>,
+        _csv_path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+/// Implements `CsvRecord` with `upsert_all` (and `prune_csv` for the prunable tables), or with the custom upsert
+/// of the table.
+macro_rules! impl_csv_record {
+    ($record:ty, $upsert:ident, prunable) => {
+        impl CsvRecord for $record {
+            const PRUNABLE: bool = true;
+
+            async fn upsert(
+                transaction: &mut Transaction<'_>,
+                csv_path: &Path,
+                _prune: bool,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                transaction.$upsert(csv_path).await?;
+                Ok(())
+            }
+
+            async fn prune(
+                transaction: &mut Transaction<'_>,
+                csv_path: &Path,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                transaction.prune_csv::<Self>(csv_path).await?;
+                Ok(())
+            }
+        }
+    };
+    ($record:ty, prunable) => {
+        impl CsvRecord for $record {
+            const PRUNABLE: bool = true;
+
+            async fn upsert(
+                transaction: &mut Transaction<'_>,
+                csv_path: &Path,
+                _prune: bool,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                transaction.upsert_all::<Self>(csv_path).await?;
+                Ok(())
+            }
+
+            async fn prune(
+                transaction: &mut Transaction<'_>,
+                csv_path: &Path,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                transaction.prune_csv::<Self>(csv_path).await?;
+                Ok(())
+            }
+        }
+    };
+    ($record:ty, upsert_all) => {
+        impl CsvRecord for $record {
+            async fn upsert(
+                transaction: &mut Transaction<'_>,
+                csv_path: &Path,
+                _prune: bool,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                transaction.upsert_all::<Self>(csv_path).await?;
+                Ok(())
+            }
+        }
+    };
+    ($record:ty, $upsert:ident) => {
+        impl CsvRecord for $record {
+            async fn upsert(
+                transaction: &mut Transaction<'_>,
+                csv_path: &Path,
+                _prune: bool,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                transaction.$upsert(csv_path).await?;
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_csv_record!(Person, prunable);
+impl_csv_record!(Institution, prunable);
+impl_csv_record!(Currency, upsert_currency, prunable);
+impl_csv_record!(ExchangeRate, upsert_all);
+impl_csv_record!(Exchange, prunable);
+impl_csv_record!(Security, prunable);
+impl_csv_record!(SecurityPrice, upsert_all);
+impl_csv_record!(CorporateAction, prunable);
+impl_csv_record!(AssetClassName, prunable);
+impl_csv_record!(AssetClass, upsert_asset_class);
+impl_csv_record!(AssetAllocation, prunable);
+impl_csv_record!(TaxShelterType, prunable);
+impl_csv_record!(AccountSubtype, upsert_all);
+impl_csv_record!(AccountType, upsert_account_type);
+impl_csv_record!(Account, prunable);
+impl_csv_record!(CashAccountProduct, upsert_cash_account);
+impl_csv_record!(CashAccountHolder, upsert_cash_account_holder);
+impl_csv_record!(CashbackCategoryName, prunable);
+impl_csv_record!(CreditCard, upsert_credit_card);
+impl_csv_record!(CreditCardHolder, upsert_credit_card_holder);
+impl_csv_record!(CashbackCategory, prunable);
+impl_csv_record!(Store, prunable);
+impl_csv_record!(StoreCashbackMapping, prunable);
+impl_csv_record!(StockAccount, upsert_stock_account);
+impl_csv_record!(StockAccountHolder, upsert_stock_account_holder);
+impl_csv_record!(GicAccount, upsert_gic_account);
+impl_csv_record!(GicAccountHolder, upsert_gic_account_holder);
+impl_csv_record!(IncomeAccount, upsert_income_account);
+impl_csv_record!(IncomeAccountHolder, upsert_income_account_holder);
+impl_csv_record!(PrepaidAccount, prunable);
+impl_csv_record!(TransactionStore, prunable);
+impl_csv_record!(TransactionForex, prunable);
+impl_csv_record!(RecurringEntry, prunable);
+impl_csv_record!(CategorizationRule, prunable);
+
+/// The entries are pruned by the upsert, before the checks are printed.
+impl CsvRecord for FinancialEntry {
+    const PRUNABLE: bool = true;
+
+    async fn upsert(
+        transaction: &mut Transaction<'_>,
+        csv_path: &Path,
+        prune: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        upsert_transaction(transaction, csv_path, prune).await
+    }
+}
+
+impl CsvTable {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Person => "person.csv",
+            Self::Institution => "institution.csv",
+            Self::Currency => "currency.csv",
+            Self::ExchangeRate => "exchange_rate.csv",
+            Self::Exchange => "exchange.csv",
+            Self::Security => "security.csv",
+            Self::SecurityPrice => "security_price.csv",
+            Self::CorporateAction => "corporate_action.csv",
+            Self::AssetClassName => "asset_class_name.csv",
+            Self::AssetClass => "asset_class.csv",
+            Self::AssetAllocation => "asset_allocation.csv",
+            Self::TaxShelterType => "tax_shelter_type.csv",
+            Self::AccountSubtype => "account_subtype.csv",
+            Self::AccountType => "account_type.csv",
+            Self::Account => "account.csv",
+            Self::CashAccountProduct => "cash_account.csv",
+            Self::CashAccountHolder => "cash_account_holder.csv",
+            Self::CashbackCategoryName => "cashback_category_name.csv",
+            Self::CreditCardProduct => "credit_card_account.csv",
+            Self::CreditCardHolder => "credit_card_account_holder.csv",
+            Self::CashbackCategory => "cashback_category.csv",
+            Self::Store => "store.csv",
+            Self::StoreCashbackMapping => "store_cashback_mapping.csv",
+            Self::StockAccount => "stock_account.csv",
+            Self::StockAccountHolder => "stock_account_holder.csv",
+            Self::GicAccount => "gic_account.csv",
+            Self::GicAccountHolder => "gic_account_holder.csv",
+            Self::IncomeAccount => "income_account.csv",
+            Self::IncomeAccountHolder => "income_account_holder.csv",
+            Self::PrepaidAccount => "prepaid_account.csv",
+            Self::TransactionStore => "transaction_store.csv",
+            Self::TransactionForex => "transaction_forex.csv",
+            Self::Recurring => "recurring.csv",
+            Self::Rule => "rule.csv",
+            Self::Transaction => "transaction.csv",
+        }
+    }
+
+    /// Older folders don't have the rate and price histories, the corporate actions, the templates and the rules.
+    pub fn is_optional(self) -> bool {
+        matches!(
+            self,
+            Self::ExchangeRate
+                | Self::SecurityPrice
+                | Self::CorporateAction
+                | Self::Recurring
+                | Self::Rule
+        )
+    }
+
+    /// The tables that are created on the side by the custom upserts (e.g. the accounts of holders),
+    /// the prepopulated subtypes and the price histories aren't pruned.
+    pub fn is_prunable(self) -> bool {
+        with_record!(self, Record => Record::PRUNABLE)
+    }
+
+    pub async fn upsert(
+        self,
+        transaction: &mut Transaction<'_>,
+        csv_path: &Path,
+        prune: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        with_record!(self, Record => Record::upsert(transaction, csv_path, prune).await)
+    }
+
+    /// Deletes the rows that are no longer in the CSV file, once every table is upserted. The dependents must be
+    /// pruned before what they reference.
+    pub async fn prune(
+        self,
+        transaction: &mut Transaction<'_>,
+        csv_path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        with_record!(self, Record => Record::prune(transaction, csv_path).await)
+    }
+
+    pub async fn export(
+        self,
+        transaction: &mut Transaction<'_>,
+        csv_path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        with_record!(self, Record => {
+            transaction.export_all::<Record>(csv_path).await?;
+        });
+
+        Ok(())
+    }
+
+    pub async fn diff(
+        self,
+        transaction: &mut Transaction<'_>,
+        csv_path: &Path,
+    ) -> Result<TableDiff, Box<dyn std::error::Error>> {
+        Ok(with_record!(self, Record => transaction.diff_all::<Record>(csv_path).await?))
+    }
+}
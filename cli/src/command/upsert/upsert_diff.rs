@@ -0,0 +1,60 @@
+use db::TableDiff;
+use owo_colors::OwoColorize;
+
+/// Prints the tables with new or changed records, the unchanged tables are only counted.
+pub fn print_upsert_diff(diffs: &[TableDiff]) {
+    let mut num_unchanged_tables = 0;
+
+    for diff in diffs {
+        if diff.new.is_empty() && diff.changed.is_empty() {
+            num_unchanged_tables += 1;
+            continue;
+        }
+
+        println!(
+            "{}: {} new, {} changed, {} unchanged",
+            diff.csv_path.to_string_lossy().bold(),
+            diff.new.len().to_string().green(),
+            diff.changed.len().to_string().yellow(),
+            diff.num_unchanged
+        );
+
+        for fields in &diff.new {
+            let record = diff
+                .columns
+                .iter()
+                .zip(fields)
+                .map(|(column, value)| format!("{column}={value}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            println!("  {} {}", "+".green(), record.green());
+        }
+
+        for record in &diff.changed {
+            println!("  {} {}", "~".yellow(), record.id.yellow());
+
+            for change in &record.changes {
+                println!(
+                    "      {}: {} → {}",
+                    change.column,
+                    display_value(&change.old).red(),
+                    display_value(&change.new).green()
+                );
+            }
+        }
+    }
+
+    if num_unchanged_tables > 0 {
+        println!("{num_unchanged_tables} table(s) unchanged");
+    }
+}
+
+/// Empty fields are NULL (or empty strings, which the CSV files don't distinguish).
+fn display_value(value: &str) -> String {
+    if value.is_empty() {
+        "NULL".to_string()
+    } else {
+        value.to_string()
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::Path;
 
 use db::{FinancialEntry, Transaction};
 
@@ -6,9 +6,14 @@ use crate::command::print_transaction_check::print_transaction_check;
 
 pub async fn upsert_transaction(
     transaction: &mut Transaction<'_>,
-    csv_path: &PathBuf,
+    csv_path: &Path,
+    prune: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    transaction.upsert_all::<FinancialEntry>(csv_path).await?;
+    let records = transaction.upsert_all::<FinancialEntry>(csv_path).await?;
+
+    if prune {
+        transaction.prune_all(&records).await?;
+    }
 
     print_transaction_check(transaction).await?;
 
@@ -0,0 +1,24 @@
+use std::path::Path;
+
+use db::{Error, Transaction};
+use owo_colors::OwoColorize;
+
+/// Prints every problem of the CSV folder, fails when there is any.
+pub async fn validate(transaction: &mut Transaction<'_>, csv_folder: &Path) -> Result<(), Error> {
+    let problems = transaction.validate_csv_folder(csv_folder).await?;
+
+    if problems.is_empty() {
+        println!("{} has no problem", csv_folder.to_string_lossy().bold());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}: {problem}", "Problem".bold().red());
+    }
+
+    Err(Error::invalid(format!(
+        "found {} problem(s) in {}",
+        problems.len(),
+        csv_folder.to_string_lossy()
+    )))
+}
@@ -1,7 +1,11 @@
 mod command;
 
+use std::{env, path::PathBuf};
+
 use clap::Parser;
 use command::Command;
+use db::Db;
+use owo_colors::OwoColorize;
 
 /// .____ ___ _____
 /// |  _ \_ _|_   _|
@@ -16,13 +20,59 @@ use command::Command;
 #[derive(Parser, Debug)]
 #[command(author, version, about, verbatim_doc_comment)]
 pub struct Args {
+    /// Path to the Sqlite database. Takes precedence over `--profile` and the `PIT_DB` environment variable.
+    #[arg(long, global = true)]
+    db: Option<PathBuf>,
+    /// Name of the ledger profile to use, stored as ~/.pit/profiles/<PROFILE>.sqlite3.
+    #[arg(long, global = true)]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Command,
 }
 
+impl Args {
+    /// Resolves the database in order of precedence: `--db`, `--profile`, `PIT_DB`, then ~/.pit/data.sqlite3.
+    fn db_path(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        if let Some(path) = &self.db {
+            return Ok(path.clone());
+        }
+
+        if let Some(profile) = &self.profile {
+            let is_valid = !profile.is_empty()
+                && profile
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+            if !is_valid {
+                return Err(format!(
+                    "invalid profile name {profile}, only letters, digits, '-' and '_' are allowed"
+                )
+                .into());
+            }
+
+            return Ok(Db::profile_path(profile));
+        }
+
+        if let Some(path) = env::var_os("PIT_DB") {
+            return Ok(path.into());
+        }
+
+        Ok(Db::default_path())
+    }
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    Args::parse().command.run().await?;
+async fn main() {
+    let args = Args::parse();
+
+    // print the Display form of the error, which points at the offending CSV line
+    let result = match args.db_path() {
+        Ok(db_path) => args.command.run(&db_path).await,
+        Err(err) => Err(err),
+    };
 
-    Ok(())
+    if let Err(err) = result {
+        eprintln!("{}: {err}", "Error".bold().red());
+        std::process::exit(1);
+    }
 }
@@ -5,11 +5,8 @@ use serde::{
     Deserialize, Deserializer,
 };
 
-pub fn bool_from_str<'de, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    match String::deserialize(deserializer)?.to_lowercase().as_str() {
+fn parse_bool<E: de::Error>(value: &str) -> Result<bool, E> {
+    match value.to_lowercase().as_str() {
         "t" | "true" | "1" | "on" | "y" | "yes" => Ok(true),
         "f" | "false" | "0" | "off" | "n" | "no" => Ok(false),
         other => Err(de::Error::invalid_value(
@@ -18,3 +15,21 @@ where
         )),
     }
 }
+
+pub fn bool_from_str<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    parse_bool(&String::deserialize(deserializer)?)
+}
+
+/// Like `bool_from_str`, but an empty value is `None`.
+pub fn option_bool_from_str<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) if !value.trim().is_empty() => parse_bool(value.trim()).map(Some),
+        _ => Ok(None),
+    }
+}
@@ -1,6 +1,7 @@
 use core::fmt;
 
-use chrono::{Local, NaiveDate, NaiveTime};
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use serde::de::Visitor;
 
 struct ExcelDateVisitor;
 
@@ -37,3 +38,38 @@ where
 {
     d.deserialize_str(ExcelDateVisitor)
 }
+
+pub fn serialize_excel_date<S>(timestamp: &i64, s: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::ser::Serializer,
+{
+    match Local.timestamp_opt(*timestamp, 0).single() {
+        Some(datetime) => s.serialize_str(&datetime.format("%m/%d/%Y").to_string()),
+        None => Err(serde::ser::Error::custom(format!(
+            "invalid timestamp {timestamp}"
+        ))),
+    }
+}
+
+/// Same as `excel_date_format`, but an empty field is `None`.
+pub fn optional_excel_date_format<'de, D>(d: D) -> Result<Option<i64>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    let s: Option<String> = serde::Deserialize::deserialize(d)?;
+
+    match s.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => ExcelDateVisitor.visit_str(s).map(Some),
+    }
+}
+
+pub fn serialize_optional_excel_date<S>(timestamp: &Option<i64>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::ser::Serializer,
+{
+    match timestamp {
+        Some(timestamp) => serialize_excel_date(timestamp, s),
+        None => s.serialize_none(),
+    }
+}
@@ -1,6 +1,6 @@
 use core::fmt;
 
-use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
 
 struct ExcelDateOptionalTimeVisitor;
 
@@ -42,3 +42,19 @@ where
 {
     d.deserialize_str(ExcelDateOptionalTimeVisitor)
 }
+
+/// Omits the time when it's midnight, same as how the date would be typed in.
+pub fn serialize_excel_date_optional_time<S>(timestamp: &i64, s: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::ser::Serializer,
+{
+    match Local.timestamp_opt(*timestamp, 0).single() {
+        Some(datetime) if datetime.num_seconds_from_midnight() == 0 => {
+            s.serialize_str(&datetime.format("%m/%d/%Y").to_string())
+        }
+        Some(datetime) => s.serialize_str(&datetime.format("%m/%d/%Y %H:%M:%S").to_string()),
+        None => Err(serde::ser::Error::custom(format!(
+            "invalid timestamp {timestamp}"
+        ))),
+    }
+}
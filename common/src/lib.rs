@@ -3,10 +3,13 @@ mod all_time_until_now;
 mod bool_from_str;
 mod days_prior_until_now;
 mod deserialize_into_map;
+mod end_of_day;
 mod excel_date_format;
 mod excel_date_optional_time_format;
 mod excel_datetime_format;
+mod period;
 mod start_of_day;
+mod struct_field_names;
 
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -15,12 +18,38 @@ use serde::{Deserialize, Serialize};
 pub struct Exchange {
     pub short: &'static str,
     pub name: &'static str,
+    /// Suffix of the exchange's symbols on Yahoo Finance, e.g. XEQT.TO
+    pub yahoo_suffix: Option<&'static str>,
+    /// Suffix of the exchange's symbols on Stooq, e.g. VT.US
+    pub stooq_suffix: Option<&'static str>,
 }
 
-pub const EXCHANGES: &[Exchange] = &[Exchange {
-    short: "TSX",
-    name: "Toronto Stock Exchange",
-}];
+pub const EXCHANGES: &[Exchange] = &[
+    Exchange {
+        short: "TSX",
+        name: "Toronto Stock Exchange",
+        yahoo_suffix: Some(".TO"),
+        stooq_suffix: None,
+    },
+    Exchange {
+        short: "NEO",
+        name: "Cboe Canada",
+        yahoo_suffix: Some(".NE"),
+        stooq_suffix: None,
+    },
+    Exchange {
+        short: "NYSE",
+        name: "New York Stock Exchange",
+        yahoo_suffix: None,
+        stooq_suffix: Some(".US"),
+    },
+    Exchange {
+        short: "NASDAQ",
+        name: "Nasdaq Stock Market",
+        yahoo_suffix: None,
+        stooq_suffix: Some(".US"),
+    },
+];
 
 #[derive(Serialize, Deserialize)]
 pub struct Quote {
@@ -37,11 +66,19 @@ pub trait Id {
 }
 
 pub use all_time_in_year::all_time_in_year;
-pub use all_time_until_now::all_time_until_now;
-pub use bool_from_str::bool_from_str;
+pub use all_time_until_now::{all_time_until, all_time_until_now};
+pub use bool_from_str::{bool_from_str, option_bool_from_str};
 pub use days_prior_until_now::days_prior_until_end_of_today;
 pub use deserialize_into_map::deserialize_into_map;
-pub use excel_date_format::excel_date_format;
-pub use excel_date_optional_time_format::excel_date_optional_time_format;
+pub use end_of_day::end_of_day;
+pub use excel_date_format::{
+    excel_date_format, optional_excel_date_format, serialize_excel_date,
+    serialize_optional_excel_date,
+};
+pub use excel_date_optional_time_format::{
+    excel_date_optional_time_format, serialize_excel_date_optional_time,
+};
 pub use excel_datetime_format::excel_datetime_format;
+pub use period::Period;
 pub use start_of_day::start_of_day;
+pub use struct_field_names::struct_field_names;
@@ -0,0 +1,80 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Days, Months, NaiveDate};
+
+/// A calendar year, quarter or month, written as 2024, 2024-Q2 or 2024-06.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Year(i32),
+    Quarter(i32, u32),
+    Month(i32, u32),
+}
+
+impl Period {
+    pub fn first_day(&self) -> NaiveDate {
+        let (year, month) = match *self {
+            Self::Year(year) => (year, 1),
+            Self::Quarter(year, quarter) => (year, (quarter - 1) * 3 + 1),
+            Self::Month(year, month) => (year, month),
+        };
+
+        NaiveDate::from_ymd_opt(year, month, 1).expect("to create the first day of the period")
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        let num_months = match self {
+            Self::Year(_) => 12,
+            Self::Quarter(_, _) => 3,
+            Self::Month(_, _) => 1,
+        };
+
+        self.first_day()
+            .checked_add_months(Months::new(num_months))
+            .and_then(|next_period| next_period.checked_sub_days(Days::new(1)))
+            .expect("to find the last day of the period")
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("{s:?} is not a period, expecting e.g. 2024, 2024-Q2 or 2024-06");
+
+        let s = s.trim();
+        let (year, rest) = match s.split_once('-') {
+            Some((year, rest)) => (year, Some(rest)),
+            None => (s, None),
+        };
+        let year: i32 = year.parse().map_err(|_| error())?;
+
+        let period = match rest {
+            None => Self::Year(year),
+            Some(rest) => match rest.strip_prefix(['Q', 'q']) {
+                Some(quarter) => Self::Quarter(year, quarter.parse().map_err(|_| error())?),
+                None => Self::Month(year, rest.parse().map_err(|_| error())?),
+            },
+        };
+
+        let is_valid = match period {
+            Self::Year(_) => true,
+            Self::Quarter(_, quarter) => (1..=4).contains(&quarter),
+            Self::Month(_, month) => (1..=12).contains(&month),
+        };
+        if !is_valid || NaiveDate::from_ymd_opt(year, 1, 1).is_none() {
+            return Err(error());
+        }
+
+        Ok(period)
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Year(year) => write!(f, "{year}"),
+            Self::Quarter(year, quarter) => write!(f, "{year}-Q{quarter}"),
+            Self::Month(year, month) => write!(f, "{year}-{month:02}"),
+        }
+    }
+}
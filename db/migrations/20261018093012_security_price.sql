@@ -0,0 +1,334 @@
+CREATE TABLE SecurityPrice (
+    security_id INTEGER NOT NULL REFERENCES SECURITY(security_id),
+    -- start of the trading day
+    date INTEGER NOT NULL CHECK (date >= 0),
+    price REAL NOT NULL CHECK (price >= 0),
+    PRIMARY KEY (security_id, date)
+) STRICT;
+
+-- seed the history with the prices that are known today
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+SELECT
+    security_id,
+    CAST(
+        strftime('%s', 'now', 'localtime', 'start of day', 'utc') AS INTEGER
+    ),
+    price
+FROM
+    SECURITY
+WHERE
+    price > 0;
+
+-- SECURITY.price is the current quote, so record every change of it in the history
+CREATE TRIGGER Security_trigger_insert_RecordPrice
+AFTER
+INSERT
+    ON SECURITY
+    WHEN NEW.price > 0 BEGIN
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+VALUES
+    (
+        NEW.security_id,
+        CAST(
+            strftime('%s', 'now', 'localtime', 'start of day', 'utc') AS INTEGER
+        ),
+        NEW.price
+    ) ON CONFLICT (security_id, date) DO
+UPDATE
+SET
+    price = excluded.price;
+
+END;
+
+CREATE TRIGGER Security_trigger_update_RecordPrice
+AFTER
+UPDATE
+    OF price ON SECURITY
+    WHEN NEW.price <> OLD.price BEGIN
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+VALUES
+    (
+        NEW.security_id,
+        CAST(
+            strftime('%s', 'now', 'localtime', 'start of day', 'utc') AS INTEGER
+        ),
+        NEW.price
+    ) ON CONFLICT (security_id, date) DO
+UPDATE
+SET
+    price = excluded.price;
+
+END;
+
+-- the most recent quote in the history becomes the current quote
+CREATE TRIGGER SecurityPrice_trigger_insert_UpdateCurrentPrice
+AFTER
+INSERT
+    ON SecurityPrice
+    WHEN NOT EXISTS (
+        SELECT
+            *
+        FROM
+            SecurityPrice
+        WHERE
+            security_id = NEW.security_id
+            AND date > NEW.date
+    ) BEGIN
+UPDATE
+    SECURITY
+SET
+    price = NEW.price
+WHERE
+    security_id = NEW.security_id
+    AND price <> NEW.price;
+
+END;
+
+CREATE TRIGGER SecurityPrice_trigger_update_UpdateCurrentPrice
+AFTER
+UPDATE
+    OF price ON SecurityPrice
+    WHEN NOT EXISTS (
+        SELECT
+            *
+        FROM
+            SecurityPrice
+        WHERE
+            security_id = NEW.security_id
+            AND date > NEW.date
+    ) BEGIN
+UPDATE
+    SECURITY
+SET
+    price = NEW.price
+WHERE
+    security_id = NEW.security_id
+    AND price <> NEW.price;
+
+END;
+
+-- Views that value holdings only look at entries up to this date; NULL means no cut-off.
+CREATE TABLE ValuationDate (
+    valuation_date_id INTEGER NOT NULL PRIMARY KEY CHECK (valuation_date_id = 1),
+    date INTEGER CHECK (
+        date IS NULL
+        OR date >= 0
+    )
+) STRICT;
+
+INSERT INTO
+    ValuationDate (valuation_date_id, date)
+VALUES
+    (1, NULL);
+
+CREATE VIEW ValuationCutoff AS
+SELECT
+    date IS NOT NULL AS is_historical,
+    COALESCE(date, 9223372036854775807) AS as_of
+FROM
+    ValuationDate;
+
+-- the price of each security on the valuation date, falls back to the current quote when there is no earlier record
+CREATE VIEW SecurityPriceAsOf AS
+SELECT
+    security_id,
+    CASE
+        WHEN (
+            SELECT
+                is_historical
+            FROM
+                ValuationCutoff
+        ) THEN COALESCE(
+            (
+                SELECT
+                    SecurityPrice.price
+                FROM
+                    SecurityPrice
+                WHERE
+                    SecurityPrice.security_id = SECURITY.security_id
+                    AND SecurityPrice.date <= (
+                        SELECT
+                            as_of
+                        FROM
+                            ValuationCutoff
+                    )
+                ORDER BY
+                    SecurityPrice.date DESC
+                LIMIT
+                    1
+            ), price
+        )
+        ELSE price
+    END AS price
+FROM
+    SECURITY;
+
+DROP VIEW NormalizedStockBalance;
+
+-- returns stock balance by asset class, normalized to currency
+CREATE VIEW NormalizedStockBalance AS WITH StockCount AS (
+    SELECT
+        person_id,
+        security_id,
+        ROUND(
+            ROUND(
+                ROUND(
+                    SUM(
+                        CASE
+                            WHEN debit IS NOT NULL THEN unit
+                            ELSE - unit
+                        END
+                    ),
+                    4
+                ) * SecurityPriceAsOf.price,
+                2
+            ) * market_exchange_rate,
+            2
+        ) AS balance
+    FROM
+        FinancialEntry
+        INNER JOIN StockAccountEntry USING (account_id)
+        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountType USING (account_type_id)
+        INNER JOIN SECURITY USING(security_id)
+        INNER JOIN SecurityPriceAsOf USING(security_id)
+        INNER JOIN Currency USING (currency_id)
+    WHERE
+        account_subtype = 'STOCK'
+        AND date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
+    GROUP BY
+        person_id,
+        account_type_id,
+        security_id
+    HAVING
+        balance <> 0
+)
+SELECT
+    person_id,
+    asset_class_id,
+    SUM(ROUND(balance * rate, 2)) AS balance
+FROM
+    StockCount
+    INNER JOIN PerClassAllocationRate USING (person_id, security_id)
+GROUP BY
+    person_id,
+    asset_class_id;
+
+DROP VIEW NormalizedFixedIncomeBalance;
+
+-- return fixed income balanced, normalized to main currency
+CREATE VIEW NormalizedFixedIncomeBalance AS
+SELECT
+    person_id,
+    ROUND(
+        SUM(
+            ROUND(
+                ROUND(
+                    unit * (COALESCE(debit, 0) - COALESCE(credit, 0)),
+                    2
+                ) * market_exchange_rate,
+                2
+            )
+        ),
+        2
+    ) AS balance
+FROM
+    Account
+    INNER JOIN GicEntry USING (account_id)
+    INNER JOIN GicAccountHolder USING (gic_account_holder_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN FinancialEntry USING (account_id)
+    INNER JOIN Currency USING (currency_id)
+WHERE
+    account_kind = 'ASSET'
+    AND date <= (
+        SELECT
+            as_of
+        FROM
+            ValuationCutoff
+    )
+GROUP BY
+    person_id;
+
+DROP VIEW CashView;
+
+CREATE VIEW CashView AS
+SELECT
+    cash_account_holder_id,
+    person_id,
+    emergency_target,
+    MAX(
+        0,
+        MIN(
+            emergency_target,
+            ROUND(balance - min_balance_waiver, 2)
+        )
+    ) AS emergency_fund,
+    MAX(
+        0,
+        ROUND(
+            (
+                emergency_target + min_balance_waiver
+            ) - balance,
+            2
+        )
+    ) AS injection_needed,
+    MAX(
+        0,
+        ROUND(
+            balance - (
+                emergency_target + min_balance_waiver
+            ),
+            2
+        )
+    ) AS unallocated_fund,
+    currency_id
+FROM
+    CashAccountEntry
+    INNER JOIN CashAccountHolder USING (cash_account_holder_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN (
+        SELECT
+            account_id,
+            ROUND(
+                SUM (
+                    ROUND(
+                        unit * (COALESCE(debit, 0.0) - COALESCE(credit, 0.0)),
+                        2
+                    )
+                ),
+                2
+            ) AS balance
+        FROM
+            FinancialEntry
+            INNER JOIN CashAccountEntry USING (account_id)
+        WHERE
+            date <= (
+                SELECT
+                    as_of
+                FROM
+                    ValuationCutoff
+            )
+        GROUP BY
+            account_id
+    ) USING (account_id)
+WHERE
+    account_subtype = 'CASH';
+
+-- quick test
+SELECT
+    *
+FROM
+    AllocationView;
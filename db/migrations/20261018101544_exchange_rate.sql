@@ -0,0 +1,264 @@
+-- amount of the quote currency that one unit of the base currency buys, e.g. USD/CAD
+CREATE TABLE ExchangeRate (
+    base_currency_id INTEGER NOT NULL REFERENCES Currency(currency_id),
+    quote_currency_id INTEGER NOT NULL REFERENCES Currency(currency_id),
+    date INTEGER NOT NULL CHECK (date >= 0),
+    rate REAL NOT NULL CHECK (rate > 0),
+    PRIMARY KEY (base_currency_id, quote_currency_id, date),
+    CHECK (base_currency_id <> quote_currency_id)
+) STRICT;
+
+CREATE INDEX ExchangeRate_idx_QuoteCurrency ON ExchangeRate (quote_currency_id);
+
+-- keep the rates that are known today
+INSERT INTO
+    ExchangeRate (
+        base_currency_id,
+        quote_currency_id,
+        date,
+        rate
+    )
+SELECT
+    Currency.currency_id,
+    Home.currency_id,
+    CAST(
+        strftime('%s', 'now', 'localtime', 'start of day', 'utc') AS INTEGER
+    ),
+    Currency.market_exchange_rate
+FROM
+    Currency
+    INNER JOIN Currency Home ON Home.currency = 'CAD'
+WHERE
+    Currency.currency <> 'CAD'
+    AND Currency.market_exchange_rate > 0;
+
+-- rate that converts each currency into CAD (i.e. the currency of the reports) on the valuation date,
+-- falls back to the earliest known rate for dates before the history starts
+CREATE VIEW MarketExchangeRate AS WITH HomeRate AS (
+    SELECT
+        base_currency_id AS currency_id,
+        date,
+        rate
+    FROM
+        ExchangeRate
+        INNER JOIN Currency ON quote_currency_id = Currency.currency_id
+    WHERE
+        currency = 'CAD'
+    UNION
+    ALL
+    SELECT
+        quote_currency_id AS currency_id,
+        date,
+        1.0 / rate AS rate
+    FROM
+        ExchangeRate
+        INNER JOIN Currency ON base_currency_id = Currency.currency_id
+    WHERE
+        currency = 'CAD'
+)
+SELECT
+    currency_id,
+    CASE
+        WHEN currency = 'CAD' THEN 1.0
+        ELSE COALESCE(
+            (
+                SELECT
+                    rate
+                FROM
+                    HomeRate
+                WHERE
+                    HomeRate.currency_id = Currency.currency_id
+                    AND date <= (
+                        SELECT
+                            as_of
+                        FROM
+                            ValuationCutoff
+                    )
+                ORDER BY
+                    date DESC
+                LIMIT
+                    1
+            ), (
+                SELECT
+                    rate
+                FROM
+                    HomeRate
+                WHERE
+                    HomeRate.currency_id = Currency.currency_id
+                ORDER BY
+                    date
+                LIMIT
+                    1
+            ), 1.0
+        )
+    END AS market_exchange_rate
+FROM
+    Currency;
+
+-- quick test
+SELECT
+    *
+FROM
+    MarketExchangeRate;
+
+DROP VIEW NormalizedStockBalance;
+
+-- returns stock balance by asset class, normalized to currency
+CREATE VIEW NormalizedStockBalance AS WITH StockCount AS (
+    SELECT
+        person_id,
+        security_id,
+        ROUND(
+            ROUND(
+                ROUND(
+                    SUM(
+                        CASE
+                            WHEN debit IS NOT NULL THEN unit
+                            ELSE - unit
+                        END
+                    ),
+                    4
+                ) * SecurityPriceAsOf.price,
+                2
+            ) * market_exchange_rate,
+            2
+        ) AS balance
+    FROM
+        FinancialEntry
+        INNER JOIN StockAccountEntry USING (account_id)
+        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountType USING (account_type_id)
+        INNER JOIN SECURITY USING(security_id)
+        INNER JOIN SecurityPriceAsOf USING(security_id)
+        INNER JOIN MarketExchangeRate USING (currency_id)
+    WHERE
+        account_subtype = 'STOCK'
+        AND date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
+    GROUP BY
+        person_id,
+        account_type_id,
+        security_id
+    HAVING
+        balance <> 0
+)
+SELECT
+    person_id,
+    asset_class_id,
+    SUM(ROUND(balance * rate, 2)) AS balance
+FROM
+    StockCount
+    INNER JOIN PerClassAllocationRate USING (person_id, security_id)
+GROUP BY
+    person_id,
+    asset_class_id;
+
+DROP VIEW NormalizedFixedIncomeBalance;
+
+-- return fixed income balanced, normalized to main currency
+CREATE VIEW NormalizedFixedIncomeBalance AS
+SELECT
+    person_id,
+    ROUND(
+        SUM(
+            ROUND(
+                ROUND(
+                    unit * (COALESCE(debit, 0) - COALESCE(credit, 0)),
+                    2
+                ) * market_exchange_rate,
+                2
+            )
+        ),
+        2
+    ) AS balance
+FROM
+    Account
+    INNER JOIN GicEntry USING (account_id)
+    INNER JOIN GicAccountHolder USING (gic_account_holder_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN FinancialEntry USING (account_id)
+    INNER JOIN MarketExchangeRate USING (currency_id)
+WHERE
+    account_kind = 'ASSET'
+    AND date <= (
+        SELECT
+            as_of
+        FROM
+            ValuationCutoff
+    )
+GROUP BY
+    person_id;
+
+DROP VIEW BalanceByAssetClass;
+
+CREATE VIEW BalanceByAssetClass (
+    person_id,
+    asset_class_id,
+    liquid_balance,
+    reserve_balance,
+    total_balance
+) AS
+SELECT
+    person_id,
+    asset_class_id,
+    COALESCE(balance, 0),
+    0.0,
+    COALESCE(balance, 0)
+FROM
+    NormalizedStockBalance
+UNION
+ALL
+SELECT
+    person_id,
+    asset_class_id,
+    COALESCE(SUM(unallocated_fund), 0) * market_exchange_rate,
+    COALESCE(SUM(emergency_fund), 0) * market_exchange_rate,
+    COALESCE(SUM(unallocated_fund) + SUM(emergency_fund), 0) * market_exchange_rate
+FROM
+    CashView
+    INNER JOIN AssetClass USING (person_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+    INNER JOIN MarketExchangeRate USING (currency_id)
+WHERE
+    asset_class_name = 'Cash'
+    AND -- exclude prepaid accounts (i.e. Presto) from rebalancing
+    cash_account_holder_id NOT IN (
+        SELECT
+            cash_account_holder_id
+        FROM
+            PrepaidAccount
+            INNER JOIN CashAccountHolder USING (account_type_id)
+    )
+GROUP BY
+    person_id,
+    asset_class_id
+UNION
+ALL
+SELECT
+    person_id,
+    asset_class_id,
+    0.0,
+    COALESCE(balance, 0.0),
+    COALESCE(balance, 0.0)
+FROM
+    NormalizedFixedIncomeBalance
+    INNER JOIN AssetClass USING (person_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+WHERE
+    asset_class_name = 'Fixed Income';
+
+ALTER TABLE
+    Currency DROP COLUMN market_exchange_rate;
+
+-- quick test
+SELECT
+    *
+FROM
+    AllocationView;
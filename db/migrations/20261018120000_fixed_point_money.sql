@@ -0,0 +1,1081 @@
+-- Money is stored as integer minor units (cents), units and unit prices as integers with 4 decimal places
+-- (see `Money`, `Units` and `Price`),
+-- so that the balances are exact sums instead of re-rounded floats.
+-- Every view that reads an amount is recreated with integer arithmetic.
+DROP VIEW BalanceByAssetClass;
+
+DROP VIEW NormalizedFixedIncomeBalance;
+
+DROP VIEW NormalizedStockBalance;
+
+DROP VIEW AllocationView;
+
+DROP VIEW TotalAssetBalance;
+
+DROP VIEW CashView;
+
+DROP VIEW SecurityPriceAsOf;
+
+DROP VIEW JustifyAmex;
+
+DROP VIEW CashbackEstimate;
+
+DROP VIEW CashbackTransactionBalance;
+
+DROP VIEW CashbackTransaction;
+
+DROP VIEW PrepaidTransaction;
+
+DROP VIEW ExpenseTransaction;
+
+DROP VIEW RequiredPadInjection;
+
+DROP VIEW PadSourceBalance;
+
+DROP VIEW DebtByPadSource;
+
+DROP VIEW CreditCardView;
+
+DROP VIEW Acb;
+
+DROP VIEW AcbPrecomputation;
+
+DROP VIEW AcbInjectPrevRunningTotal;
+
+DROP VIEW AcbRunningShareTotal;
+
+DROP VIEW AcbBaseData;
+
+DROP TRIGGER Security_trigger_insert_RecordPrice;
+
+DROP TRIGGER Security_trigger_update_RecordPrice;
+
+DROP TRIGGER SecurityPrice_trigger_insert_UpdateCurrentPrice;
+
+DROP TRIGGER SecurityPrice_trigger_update_UpdateCurrentPrice;
+
+-- FinancialEntry has a table constraint on debit and credit, so it is rebuilt
+CREATE TABLE FinancialEntryFixedPoint (
+    date INTEGER NOT NULL CHECK (date >= 0),
+    transaction_id INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    account_id INTEGER NOT NULL REFERENCES Account(account_id),
+    -- can contain fractional shares, 10000 is one unit
+    unit INTEGER NOT NULL CHECK(unit > 0),
+    -- the price of one unit, 10000 is one dollar
+    debit INTEGER CHECK(
+        debit IS NULL
+        OR debit >= 0
+    ),
+    credit INTEGER CHECK(
+        credit IS NULL
+        OR credit >= 0
+    ),
+    description TEXT NOT NULL,
+    PRIMARY KEY (transaction_id, item_id),
+    -- one of debit or credit must be null
+    CHECK(
+        (
+            debit IS NOT NULL
+            OR credit IS NOT NULL
+        )
+        AND NOT (
+            debit IS NULL
+            AND credit IS NULL
+        )
+    )
+) STRICT;
+
+INSERT INTO
+    FinancialEntryFixedPoint (
+        date,
+        transaction_id,
+        item_id,
+        account_id,
+        unit,
+        debit,
+        credit,
+        description
+    )
+SELECT
+    date,
+    transaction_id,
+    item_id,
+    account_id,
+    CAST(ROUND(unit * 10000) AS INTEGER),
+    CAST(ROUND(debit * 10000) AS INTEGER),
+    CAST(ROUND(credit * 10000) AS INTEGER),
+    description
+FROM
+    FinancialEntry;
+
+DROP TABLE FinancialEntry;
+
+ALTER TABLE
+    FinancialEntryFixedPoint RENAME TO FinancialEntry;
+
+CREATE INDEX FinancialEntry_idx_TransactionCredit ON FinancialEntry(transaction_id, credit);
+
+CREATE INDEX FinancialEntry_idx_ByDate ON FinancialEntry(account_id, transaction_id, date);
+
+CREATE INDEX FinancialEntry_idx_ExpenseQuery ON FinancialEntry(account_id, transaction_id, credit);
+
+CREATE INDEX FinancialEntry_idx_TransactionAccount ON FinancialEntry(transaction_id, account_id);
+
+CREATE INDEX FinancialEntry_idx_Date ON FinancialEntry(date);
+
+CREATE INDEX FinancialEntry_idx_AccountId ON FinancialEntry(account_id, date);
+
+-- the other tables are referenced by foreign keys, so their columns are swapped in place
+ALTER TABLE
+    SECURITY
+ADD
+    COLUMN price_in_cents INTEGER NOT NULL DEFAULT 0 CHECK(price_in_cents >= 0);
+
+UPDATE
+    SECURITY
+SET
+    price_in_cents = CAST(ROUND(price * 100) AS INTEGER);
+
+ALTER TABLE
+    SECURITY DROP COLUMN price;
+
+ALTER TABLE
+    SECURITY RENAME COLUMN price_in_cents TO price;
+
+ALTER TABLE
+    SecurityPrice
+ADD
+    COLUMN price_in_cents INTEGER NOT NULL DEFAULT 0 CHECK(price_in_cents >= 0);
+
+UPDATE
+    SecurityPrice
+SET
+    price_in_cents = CAST(ROUND(price * 100) AS INTEGER);
+
+ALTER TABLE
+    SecurityPrice DROP COLUMN price;
+
+ALTER TABLE
+    SecurityPrice RENAME COLUMN price_in_cents TO price;
+
+ALTER TABLE
+    CashAccountProduct
+ADD
+    COLUMN min_balance_waiver_in_cents INTEGER NOT NULL DEFAULT 0 CHECK(min_balance_waiver_in_cents >= 0);
+
+UPDATE
+    CashAccountProduct
+SET
+    min_balance_waiver_in_cents = CAST(ROUND(min_balance_waiver * 100) AS INTEGER);
+
+ALTER TABLE
+    CashAccountProduct DROP COLUMN min_balance_waiver;
+
+ALTER TABLE
+    CashAccountProduct RENAME COLUMN min_balance_waiver_in_cents TO min_balance_waiver;
+
+ALTER TABLE
+    CashAccountHolder
+ADD
+    COLUMN emergency_target_in_cents INTEGER NOT NULL DEFAULT 0 CHECK(emergency_target_in_cents >= 0);
+
+UPDATE
+    CashAccountHolder
+SET
+    emergency_target_in_cents = CAST(ROUND(emergency_target * 100) AS INTEGER);
+
+ALTER TABLE
+    CashAccountHolder DROP COLUMN emergency_target;
+
+ALTER TABLE
+    CashAccountHolder RENAME COLUMN emergency_target_in_cents TO emergency_target;
+
+ALTER TABLE
+    CreditCardProduct
+ADD
+    COLUMN annual_fee_in_cents INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE
+    CreditCardProduct
+ADD
+    COLUMN credit_limit_in_cents INTEGER;
+
+UPDATE
+    CreditCardProduct
+SET
+    annual_fee_in_cents = CAST(ROUND(annual_fee * 100) AS INTEGER),
+    credit_limit_in_cents = CAST(ROUND(credit_limit * 100) AS INTEGER);
+
+ALTER TABLE
+    CreditCardProduct DROP COLUMN annual_fee;
+
+ALTER TABLE
+    CreditCardProduct DROP COLUMN credit_limit;
+
+ALTER TABLE
+    CreditCardProduct RENAME COLUMN annual_fee_in_cents TO annual_fee;
+
+-- null credit_limit represents prepaid cards
+ALTER TABLE
+    CreditCardProduct RENAME COLUMN credit_limit_in_cents TO credit_limit;
+
+-- SECURITY.price is the current quote, so record every change of it in the history
+CREATE TRIGGER Security_trigger_insert_RecordPrice
+AFTER
+INSERT
+    ON SECURITY
+    WHEN NEW.price > 0 BEGIN
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+VALUES
+    (
+        NEW.security_id,
+        CAST(
+            strftime('%s', 'now', 'localtime', 'start of day', 'utc') AS INTEGER
+        ),
+        NEW.price
+    ) ON CONFLICT (security_id, date) DO
+UPDATE
+SET
+    price = excluded.price;
+
+END;
+
+CREATE TRIGGER Security_trigger_update_RecordPrice
+AFTER
+UPDATE
+    OF price ON SECURITY
+    WHEN NEW.price <> OLD.price BEGIN
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+VALUES
+    (
+        NEW.security_id,
+        CAST(
+            strftime('%s', 'now', 'localtime', 'start of day', 'utc') AS INTEGER
+        ),
+        NEW.price
+    ) ON CONFLICT (security_id, date) DO
+UPDATE
+SET
+    price = excluded.price;
+
+END;
+
+-- the most recent record of the history is the current quote
+CREATE TRIGGER SecurityPrice_trigger_insert_UpdateCurrentPrice
+AFTER
+INSERT
+    ON SecurityPrice
+    WHEN NOT EXISTS (
+        SELECT
+            *
+        FROM
+            SecurityPrice
+        WHERE
+            security_id = NEW.security_id
+            AND date > NEW.date
+    ) BEGIN
+UPDATE
+    SECURITY
+SET
+    price = NEW.price
+WHERE
+    security_id = NEW.security_id
+    AND price <> NEW.price;
+
+END;
+
+CREATE TRIGGER SecurityPrice_trigger_update_UpdateCurrentPrice
+AFTER
+UPDATE
+    OF price ON SecurityPrice
+    WHEN NOT EXISTS (
+        SELECT
+            *
+        FROM
+            SecurityPrice
+        WHERE
+            security_id = NEW.security_id
+            AND date > NEW.date
+    ) BEGIN
+UPDATE
+    SECURITY
+SET
+    price = NEW.price
+WHERE
+    security_id = NEW.security_id
+    AND price <> NEW.price;
+
+END;
+
+-- The amounts of every entry in cents. This is the only place where units are multiplied by prices, rounded
+-- half up once per entry, so that every balance is an exact sum of these amounts.
+CREATE VIEW FinancialEntryAmount AS
+SELECT
+    *,
+    (unit * COALESCE(debit, 0) + 500000) / 1000000 AS debit_amount,
+    (unit * COALESCE(credit, 0) + 500000) / 1000000 AS credit_amount
+FROM
+    FinancialEntry;
+
+CREATE VIEW AcbBaseData AS WITH BaseData AS (
+    SELECT
+        transaction_id,
+        person_id,
+        date,
+        security_id,
+        SUM(
+            -- only the STOCK accounts can change number of units in a position
+            CASE
+                WHEN account_subtype = 'STOCK' THEN unit * CASE
+                    WHEN debit IS NOT NULL THEN 1 -- buying
+                    ELSE -1 -- selling
+                END
+                ELSE 0
+            END
+        ) AS unit,
+        COALESCE(exchange_rate, 1.0) AS exchange_rate,
+        SUM(
+            CASE
+                WHEN account_subtype = 'STOCK' THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS book_value,
+        SUM(
+            CASE
+                WHEN account_subtype = 'DISTRIBUTION' THEN CAST(
+                    ROUND(credit_amount * COALESCE(exchange_rate, 1.0)) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS distribution,
+        SUM(
+            CASE
+                WHEN account_subtype = 'COMMISSION' THEN debit
+                ELSE 0
+            END
+        ) AS commission,
+        MIN(
+            CASE
+                -- distributions come before stock purchases
+                WHEN account_subtype = 'DISTRIBUTION' THEN 1
+                WHEN account_subtype = 'STOCK' THEN 2
+                WHEN account_subtype = 'COMMISSION' THEN 3
+            END
+        ) AS sort_order
+    FROM
+        FinancialEntryAmount
+        INNER JOIN StockAccountEntry USING (account_id)
+        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+        INNER JOIN CashAccountProduct USING (account_type_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountKind USING (account_kind_id)
+        INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+        LEFT JOIN TransactionForex USING (transaction_id)
+    WHERE
+        account_subtype IN ('STOCK', 'DISTRIBUTION', 'COMMISSION')
+        AND tax_shelter_type = 'NON-REGISTERED'
+    GROUP BY
+        transaction_id
+)
+SELECT
+    transaction_id,
+    person_id,
+    date,
+    security_id,
+    exchange_rate,
+    unit,
+    book_value - commission AS book_value,
+    distribution,
+    sort_order
+FROM
+    BaseData;
+
+CREATE VIEW AcbRunningShareTotal AS
+SELECT
+    *,
+    SUM(unit) OVER(
+        PARTITION BY person_id,
+        security_id
+        ORDER BY
+            date,
+            sort_order
+    ) AS acc_units
+FROM
+    AcbBaseData;
+
+CREATE VIEW AcbInjectPrevRunningTotal AS
+SELECT
+    *,
+    LAG(acc_units, 1, 0) OVER (
+        PARTITION BY person_id,
+        security_id
+        ORDER BY
+            date,
+            sort_order
+    ) AS prev_acc_units
+FROM
+    AcbRunningShareTotal;
+
+CREATE VIEW AcbPrecomputation AS
+SELECT
+    *,
+    CASE
+        WHEN unit > 0 THEN book_value
+        ELSE 0
+    END AS acb_increase,
+    CASE
+        WHEN unit < 0 THEN -- COALESCE for handling an edge case where I'm exiting a position, i.e. acb_decrease_factor = 0 meaning ACB becomes 0
+        COALESCE(
+            CAST(prev_acc_units + unit AS REAL) / prev_acc_units,
+            0.0
+        )
+        ELSE 1.0
+    END AS acb_decrease_factor,
+    ROW_NUMBER() OVER(
+        PARTITION BY person_id,
+        security_id
+        ORDER BY
+            date,
+            sort_order
+    ) AS group_order
+FROM
+    AcbInjectPrevRunningTotal;
+
+CREATE VIEW Acb AS WITH RECURSIVE RecurseAcb (
+    sort_order,
+    person_id,
+    transaction_id,
+    date,
+    security_id,
+    unit,
+    book_value,
+    acc_units,
+    prev_acc_units,
+    acb_increase,
+    acb_decrease_factor,
+    distribution,
+    group_order,
+    acb,
+    capital_gl
+) AS (
+    SELECT
+        sort_order,
+        person_id,
+        transaction_id,
+        date,
+        security_id,
+        unit,
+        book_value,
+        acc_units,
+        prev_acc_units,
+        acb_increase,
+        acb_decrease_factor,
+        distribution,
+        group_order,
+        -- acb for the first buy is the book cost
+        book_value,
+        -- first entry should be a buy, so no capital gain/loss
+        0
+    FROM
+        AcbPrecomputation
+    WHERE
+        group_order = 1
+    UNION
+    ALL
+    SELECT
+        a.sort_order,
+        a.person_id,
+        a.transaction_id,
+        a.date,
+        a.security_id,
+        a.unit,
+        a.book_value,
+        a.acc_units,
+        a.prev_acc_units,
+        a.acb_increase,
+        a.acb_decrease_factor,
+        a.distribution,
+        a.group_order,
+        CAST(
+            ROUND(
+                (b.acb + a.acb_increase) * a.acb_decrease_factor
+            ) AS INTEGER
+        ),
+        -- the cost of the sold units is whatever the remaining ACB doesn't keep, so no cent is lost
+        CASE
+            WHEN a.unit < 0 THEN ABS(a.book_value) - (
+                (b.acb + a.acb_increase) - CAST(
+                    ROUND(
+                        (b.acb + a.acb_increase) * a.acb_decrease_factor
+                    ) AS INTEGER
+                )
+            )
+            ELSE 0
+        END
+    FROM
+        AcbPrecomputation a
+        INNER JOIN RecurseAcb b USING (person_id, security_id)
+    WHERE
+        a.group_order = b.group_order + 1
+)
+SELECT
+    *
+FROM
+    RecurseAcb;
+
+CREATE VIEW CreditCardView AS WITH LastPayment AS (
+    SELECT
+        credit_card_holder_id,
+        MAX(date) AS last_payment_date
+    FROM
+        FinancialEntry
+        INNER JOIN Account USING (account_id)
+        INNER JOIN CreditCardEntry USING (account_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+    WHERE
+        transaction_id IN (
+            SELECT
+                transaction_id
+            FROM
+                FinancialEntry
+                INNER JOIN CashAccountEntry USING (account_id)
+        )
+        AND account_subtype = 'DEBT'
+    GROUP BY
+        credit_card_holder_id
+)
+SELECT
+    first_name,
+    last_name,
+    -- i.e. the generic name of the account
+    CreditCardProduct.account_name,
+    last_payment_date,
+    SUM(credit_amount - debit_amount) AS balance,
+    CreditCardPadSource.cash_account_holder_id IS NOT NULL AS has_pad
+FROM
+    FinancialEntryAmount
+    INNER JOIN Account USING (account_id)
+    INNER JOIN CreditCardEntry USING (account_id)
+    INNER JOIN CreditCardHolder USING (credit_card_holder_id)
+    INNER JOIN CreditCardProduct USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN Person USING (person_id)
+    LEFT JOIN LastPayment USING (credit_card_holder_id)
+    LEFT JOIN CreditCardPadSource USING (credit_card_holder_id)
+WHERE
+    account_subtype = 'DEBT'
+GROUP BY
+    account_id
+HAVING
+    balance <> 0;
+
+CREATE VIEW DebtByPadSource AS
+SELECT
+    cash_account_holder_id,
+    SUM(credit_amount - debit_amount) AS total_debt
+FROM
+    CreditCardPadSource
+    INNER JOIN CreditCardHolder USING (credit_card_holder_id)
+    INNER JOIN CreditCardEntry USING (credit_card_holder_id)
+    INNER JOIN FinancialEntryAmount USING (account_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+WHERE
+    account_subtype = 'DEBT'
+GROUP BY
+    cash_account_holder_id;
+
+CREATE VIEW PadSourceBalance AS
+SELECT
+    cash_account_holder_id,
+    SUM(debit_amount - credit_amount) AS source_balance
+FROM
+    (
+        SELECT
+            DISTINCT cash_account_holder_id
+        FROM
+            CreditCardPadSource
+    )
+    INNER JOIN CashAccountEntry USING(cash_account_holder_id)
+    INNER JOIN FinancialEntryAmount USING (account_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+WHERE
+    account_subtype = 'CASH'
+GROUP BY
+    cash_account_holder_id;
+
+CREATE VIEW RequiredPadInjection AS
+SELECT
+    first_name || ' ' || last_name AS name,
+    account_name,
+    COALESCE(total_debt, 0) - source_balance AS min_injection
+FROM
+    PadSourceBalance
+    LEFT JOIN DebtByPadSource USING (cash_account_holder_id)
+    INNER JOIN CashAccountHolder USING (cash_account_holder_id)
+    INNER JOIN Person USING (person_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+WHERE
+    min_injection > 0;
+
+CREATE VIEW ExpenseTransaction AS
+SELECT
+    FinancialEntryAmount.*
+FROM
+    FinancialEntryAmount
+    INNER JOIN Account USING (account_id)
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+WHERE
+    account_kind IN ('ASSET', 'LIABILITIES')
+    AND (
+        transaction_id IN (
+            SELECT
+                transaction_id
+            FROM
+                FinancialEntry
+                INNER JOIN Account USING (account_id)
+                INNER JOIN AccountType USING (account_type_id)
+                INNER JOIN AccountSubtype USING (account_subtype_id)
+                INNER JOIN AccountKind USING (account_kind_id)
+            WHERE
+                account_kind = 'EXPENSE'
+                AND account_type_id NOT IN (
+                    -- this ensures we're not counting account fees
+                    SELECT
+                        account_type_id
+                    FROM
+                        CashAccountProduct
+                    UNION
+                    ALL -- this ensures we're not counting employment income
+                    SELECT
+                        account_type_id
+                    FROM
+                        IncomeAccount
+                )
+            EXCEPT
+            SELECT
+                -- exclude transactions related to prepaid credits (there's a separate query for PrepaidAccount)
+                transaction_id
+            FROM
+                FinancialEntry
+                INNER JOIN Account USING (account_id)
+                INNER JOIN PrepaidAccount USING (account_type_id)
+        )
+    );
+
+CREATE VIEW PrepaidTransaction AS
+SELECT
+    FinancialEntryAmount.*
+FROM
+    FinancialEntryAmount
+    INNER JOIN Account USING (account_id) -- INNER JOIN PrepaidAccount USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+WHERE
+    -- we're interested in cashback for *expense* (i.e. crediting asset accounts)
+    credit IS NOT NULL
+    AND -- is a prepaid account
+    transaction_id IN (
+        SELECT
+            transaction_id
+        FROM
+            FinancialEntry
+            INNER JOIN Account USING (account_id)
+            INNER JOIN PrepaidAccount USING (account_type_id)
+        EXCEPT
+        SELECT
+            -- include transactions that related to prepaid credit spendings
+            transaction_id
+        FROM
+            FinancialEntry
+            INNER JOIN Account USING (account_id)
+            INNER JOIN AccountSubtype USING (account_subtype_id)
+            INNER JOIN AccountKind USING (account_kind_id)
+        WHERE
+            account_kind <> 'ASSET'
+    );
+
+CREATE VIEW CashbackTransaction AS
+SELECT
+    *
+FROM
+    ExpenseTransaction
+UNION
+ALL
+SELECT
+    *
+FROM
+    PrepaidTransaction;
+
+CREATE VIEW CashbackTransactionBalance AS
+SELECT
+    transaction_id,
+    date,
+    credit_amount - debit_amount AS balance,
+    COALESCE(cashback_rate, 0.0) AS cashback_rate
+FROM
+    CashbackTransaction
+    INNER JOIN TransactionStore USING(transaction_id)
+    INNER JOIN OwnedAccount USING (account_id)
+    LEFT JOIN StoreCashbackMapping USING (store_id, account_type_id)
+    LEFT JOIN CashbackCategory USING (account_type_id, cashback_category_name_id);
+
+CREATE VIEW CashbackEstimate AS
+SELECT
+    transaction_id,
+    date,
+    balance,
+    Actual.cashback_rate AS actual_cashback_rate,
+    CAST(ROUND(Actual.balance * Actual.cashback_rate) AS INTEGER) AS actual_cashback,
+    CashbackWithAmex.cashback_rate AS with_amex_cashback_rate,
+    CAST(
+        ROUND(balance * CashbackWithAmex.cashback_rate) AS INTEGER
+    ) AS with_amex_cashback,
+    CashbackNoAmex.cashback_rate AS without_amex_cashback_rate,
+    CAST(
+        ROUND(balance * CashbackNoAmex.cashback_rate) AS INTEGER
+    ) AS without_amex_cashback
+FROM
+    CashbackTransactionBalance Actual
+    INNER JOIN CashbackWithAmex USING (transaction_id)
+    INNER JOIN CashbackNoAmex USING (transaction_id);
+
+CREATE VIEW JustifyAmex AS
+SELECT
+    CAST(
+        strftime(
+            '%Y',
+            DATE(date, 'unixepoch')
+        ) AS INTEGER
+    ) AS year,
+    CAST(
+        strftime(
+            '%m',
+            DATE(date, 'unixepoch')
+        ) AS INTEGER
+    ) AS MONTH,
+    -- Represents the total spending balance from 30 days prior to the current date.
+    SUM(balance) AS balance,
+    -- Calculate the average extra cashback rate for optimally using Amex.
+    SUM(
+        (
+            with_amex_cashback_rate - without_amex_cashback_rate
+        ) * balance
+    ) / SUM (balance) AS extra_cashback_rate,
+    -- Compute the theoretical cashback if my credit cards were used with Amex.
+    SUM(with_amex_cashback) AS with_amex_cashback,
+    -- Compute the theoretical cashback if my credit cards were used without Amex.
+    SUM(without_amex_cashback) AS without_amex_cashback,
+    -- Compute the theoretical extra cashback earned by using Amex compared to not using it.
+    SUM(with_amex_cashback - without_amex_cashback) AS extra_cashback,
+    -- Compute the theoretical extra cashback earned by using Amex compared to not using it, net of fees.
+    SUM(with_amex_cashback - without_amex_cashback) - (
+        SELECT
+            CAST(ROUND(annual_fee / 12.0) AS INTEGER)
+        FROM
+            CreditCardProduct
+            INNER JOIN Institution USING (institution_id)
+        WHERE
+            institution_name = 'American Express'
+    ) AS extra_cashback_after_fee,
+    -- Calculate the missed cashback opportunities where Amex was available, but not used.
+    SUM(actual_cashback - with_amex_cashback) AS missed_opportunities
+FROM
+    CashbackEstimate
+GROUP BY
+    strftime(
+        '%Y-%m',
+        DATE(date, 'unixepoch', 'localtime')
+    )
+ORDER BY
+    year DESC,
+    MONTH DESC
+LIMIT
+    12;
+
+CREATE VIEW SecurityPriceAsOf AS
+SELECT
+    security_id,
+    CASE
+        WHEN (
+            SELECT
+                is_historical
+            FROM
+                ValuationCutoff
+        ) THEN COALESCE(
+            (
+                SELECT
+                    SecurityPrice.price
+                FROM
+                    SecurityPrice
+                WHERE
+                    SecurityPrice.security_id = SECURITY.security_id
+                    AND SecurityPrice.date <= (
+                        SELECT
+                            as_of
+                        FROM
+                            ValuationCutoff
+                    )
+                ORDER BY
+                    SecurityPrice.date DESC
+                LIMIT
+                    1
+            ), price
+        )
+        ELSE price
+    END AS price
+FROM
+    SECURITY;
+
+CREATE VIEW CashView AS
+SELECT
+    cash_account_holder_id,
+    person_id,
+    emergency_target,
+    MAX(
+        0,
+        MIN(
+            emergency_target,
+            balance - min_balance_waiver
+        )
+    ) AS emergency_fund,
+    MAX(
+        0,
+        (emergency_target + min_balance_waiver) - balance
+    ) AS injection_needed,
+    MAX(
+        0,
+        balance - (emergency_target + min_balance_waiver)
+    ) AS unallocated_fund,
+    currency_id
+FROM
+    CashAccountEntry
+    INNER JOIN CashAccountHolder USING (cash_account_holder_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN (
+        SELECT
+            account_id,
+            SUM(debit_amount - credit_amount) AS balance
+        FROM
+            FinancialEntryAmount
+            INNER JOIN CashAccountEntry USING (account_id)
+        WHERE
+            date <= (
+                SELECT
+                    as_of
+                FROM
+                    ValuationCutoff
+            )
+        GROUP BY
+            account_id
+    ) USING (account_id)
+WHERE
+    account_subtype = 'CASH';
+
+CREATE VIEW NormalizedStockBalance AS WITH StockCount AS (
+    SELECT
+        person_id,
+        security_id,
+        CAST(
+            ROUND(
+                CAST(
+                    ROUND(
+                        SUM(
+                            CASE
+                                WHEN debit IS NOT NULL THEN unit
+                                ELSE - unit
+                            END
+                        ) * SecurityPriceAsOf.price / 10000.0
+                    ) AS INTEGER
+                ) * market_exchange_rate
+            ) AS INTEGER
+        ) AS balance
+    FROM
+        FinancialEntry
+        INNER JOIN StockAccountEntry USING (account_id)
+        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountType USING (account_type_id)
+        INNER JOIN SECURITY USING(security_id)
+        INNER JOIN SecurityPriceAsOf USING(security_id)
+        INNER JOIN MarketExchangeRate USING (currency_id)
+    WHERE
+        account_subtype = 'STOCK'
+        AND date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
+    GROUP BY
+        person_id,
+        account_type_id,
+        security_id
+    HAVING
+        balance <> 0
+)
+SELECT
+    person_id,
+    asset_class_id,
+    SUM(CAST(ROUND(balance * rate) AS INTEGER)) AS balance
+FROM
+    StockCount
+    INNER JOIN PerClassAllocationRate USING (person_id, security_id)
+GROUP BY
+    person_id,
+    asset_class_id;
+
+CREATE VIEW NormalizedFixedIncomeBalance AS
+SELECT
+    person_id,
+    SUM(
+        CAST(
+            ROUND(
+                (debit_amount - credit_amount) * market_exchange_rate
+            ) AS INTEGER
+        )
+    ) AS balance
+FROM
+    Account
+    INNER JOIN GicEntry USING (account_id)
+    INNER JOIN GicAccountHolder USING (gic_account_holder_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN FinancialEntryAmount USING (account_id)
+    INNER JOIN MarketExchangeRate USING (currency_id)
+WHERE
+    account_kind = 'ASSET'
+    AND date <= (
+        SELECT
+            as_of
+        FROM
+            ValuationCutoff
+    )
+GROUP BY
+    person_id;
+
+CREATE VIEW BalanceByAssetClass (
+    person_id,
+    asset_class_id,
+    liquid_balance,
+    reserve_balance,
+    total_balance
+) AS
+SELECT
+    person_id,
+    asset_class_id,
+    COALESCE(balance, 0),
+    0,
+    COALESCE(balance, 0)
+FROM
+    NormalizedStockBalance
+UNION
+ALL
+SELECT
+    person_id,
+    asset_class_id,
+    CAST(
+        ROUND(
+            COALESCE(SUM(unallocated_fund), 0) * market_exchange_rate
+        ) AS INTEGER
+    ),
+    CAST(
+        ROUND(
+            COALESCE(SUM(emergency_fund), 0) * market_exchange_rate
+        ) AS INTEGER
+    ),
+    CAST(
+        ROUND(
+            COALESCE(SUM(unallocated_fund) + SUM(emergency_fund), 0) * market_exchange_rate
+        ) AS INTEGER
+    )
+FROM
+    CashView
+    INNER JOIN AssetClass USING (person_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+    INNER JOIN MarketExchangeRate USING (currency_id)
+WHERE
+    asset_class_name = 'Cash'
+    AND -- exclude prepaid accounts (i.e. Presto) from rebalancing
+    cash_account_holder_id NOT IN (
+        SELECT
+            cash_account_holder_id
+        FROM
+            PrepaidAccount
+            INNER JOIN CashAccountHolder USING (account_type_id)
+    )
+GROUP BY
+    person_id,
+    asset_class_id
+UNION
+ALL
+SELECT
+    person_id,
+    asset_class_id,
+    0,
+    COALESCE(balance, 0),
+    COALESCE(balance, 0)
+FROM
+    NormalizedFixedIncomeBalance
+    INNER JOIN AssetClass USING (person_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+WHERE
+    asset_class_name = 'Fixed Income';
+
+CREATE VIEW TotalAssetBalance AS
+SELECT
+    person_id,
+    SUM(liquid_balance) AS liquid_sum,
+    SUM(total_balance) AS total_sum
+FROM
+    BalanceByAssetClass
+GROUP BY
+    person_id;
+
+CREATE VIEW AllocationView AS WITH RebalanceRate AS (
+    SELECT
+        CAST(COALESCE(liquid_balance, 0) AS REAL) / liquid_sum AS liquid_rate,
+        CAST(COALESCE(total_balance, 0) AS REAL) / total_sum AS total_rate,
+        asset_class_id,
+        person_id,
+        parent_id,
+        -- class,
+        class_rate,
+        real_rate,
+        COALESCE(liquid_balance, 0) AS liquid_balance,
+        COALESCE(reserve_balance, 0) AS reserve_balance,
+        COALESCE(total_balance, 0) AS total_balance,
+        liquid_sum,
+        total_sum
+    FROM
+        PortfolioAllocationRate
+        LEFT JOIN BalanceByAssetClass b USING (person_id, asset_class_id)
+        INNER JOIN TotalAssetBalance USING (person_id)
+)
+SELECT
+    person_id,
+    asset_class_id,
+    CAST(
+        ROUND((real_rate - liquid_rate) * liquid_sum) AS INTEGER
+    ) AS current_rebalance_amount,
+    CAST(
+        ROUND((real_rate - total_rate) * total_sum) AS INTEGER
+    ) AS potential_rebalance_amount
+FROM
+    RebalanceRate
+WHERE
+    current_rebalance_amount <> 0
+    OR potential_rebalance_amount <> 0;
+
+-- A price with more than 4 decimal places can leave its transaction off by a few cents. The migration doesn't fail
+-- on the data that was accepted before, the check command lists the unbalanced transactions with their ids.
@@ -0,0 +1,29 @@
+-- Templates of the transactions that repeat on a schedule (e.g. paycheques, rent), one row per item like
+-- transaction.csv. The schedule, the dates and the description are repeated on every item of a template.
+CREATE TABLE RecurringEntry (
+    recurring_key TEXT NOT NULL CHECK (recurring_key <> ''),
+    item_id INTEGER NOT NULL CHECK (item_id > 0),
+    schedule TEXT NOT NULL CHECK (
+        schedule IN (
+            'WEEKLY',
+            'BIWEEKLY',
+            'MONTHLY',
+            'QUARTERLY',
+            'YEARLY'
+        )
+    ),
+    -- date of the first occurrence, the following ones are counted from it
+    start_date INTEGER NOT NULL CHECK (start_date >= 0),
+    end_date INTEGER CHECK (end_date >= start_date),
+    account_id INTEGER NOT NULL REFERENCES Account(account_id),
+    -- An amount or a formula (e.g. 2500, #1 * 5.95%), where #N is the amount of the item N of the template.
+    -- The item with neither a debit nor a credit takes whatever balances the others.
+    debit TEXT CHECK (debit <> ''),
+    credit TEXT CHECK (credit <> ''),
+    description TEXT NOT NULL,
+    PRIMARY KEY (recurring_key, item_id),
+    CHECK (
+        debit IS NULL
+        OR credit IS NULL
+    )
+) STRICT;
@@ -0,0 +1,74 @@
+-- the statements that the entries of an account were reconciled against
+CREATE TABLE Reconciliation (
+    account_id INTEGER NOT NULL REFERENCES Account(account_id),
+    -- end of the day of the statement
+    statement_date INTEGER NOT NULL CHECK (statement_date >= 0),
+    -- in cents, in the sign of the account's kind, e.g. the amount owed for a credit card
+    closing_balance INTEGER NOT NULL,
+    PRIMARY KEY (account_id, statement_date)
+) STRICT;
+
+-- The values of the entries when they were reconciled, so that the entries that are changed or deleted later on
+-- can be flagged. There is no foreign key to FinancialEntry, since the entry can be removed from the CSV file.
+CREATE TABLE ReconciledEntry (
+    transaction_id INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    account_id INTEGER NOT NULL,
+    statement_date INTEGER NOT NULL,
+    date INTEGER NOT NULL,
+    unit INTEGER NOT NULL,
+    debit INTEGER,
+    credit INTEGER,
+    PRIMARY KEY (transaction_id, item_id),
+    FOREIGN KEY (account_id, statement_date) REFERENCES Reconciliation(account_id, statement_date)
+) STRICT;
+
+CREATE VIEW ChangedReconciledEntry AS
+SELECT
+    ReconciledEntry.transaction_id,
+    ReconciledEntry.item_id,
+    ReconciledEntry.account_id,
+    ReconciledEntry.statement_date,
+    CASE
+        WHEN FinancialEntry.transaction_id IS NULL THEN 'DELETED'
+        ELSE 'CHANGED'
+    END AS change
+FROM
+    ReconciledEntry
+    LEFT JOIN FinancialEntry USING (transaction_id, item_id)
+WHERE
+    FinancialEntry.transaction_id IS NULL
+    OR FinancialEntry.account_id IS NOT ReconciledEntry.account_id
+    OR FinancialEntry.date IS NOT ReconciledEntry.date
+    OR FinancialEntry.unit IS NOT ReconciledEntry.unit
+    OR FinancialEntry.debit IS NOT ReconciledEntry.debit
+    OR FinancialEntry.credit IS NOT ReconciledEntry.credit
+UNION
+ALL
+-- entries that are added to a period that is already reconciled
+SELECT
+    transaction_id,
+    item_id,
+    account_id,
+    statement_date,
+    'ADDED' AS change
+FROM
+    FinancialEntry
+    INNER JOIN (
+        SELECT
+            account_id,
+            MAX(statement_date) AS statement_date
+        FROM
+            Reconciliation
+        GROUP BY
+            account_id
+    ) USING (account_id)
+WHERE
+    date <= statement_date
+    AND (transaction_id, item_id) NOT IN (
+        SELECT
+            transaction_id,
+            item_id
+        FROM
+            ReconciledEntry
+    );
@@ -0,0 +1,9 @@
+-- The statement transactions (by their FITID) that were drafted by the OFX/QFX import, so that overlapping
+-- statements aren't drafted twice. There is no foreign key to FinancialEntry, since the drafts are reviewed in a
+-- CSV file before they are upserted.
+CREATE TABLE ImportedStatementTransaction (
+    account_id INTEGER NOT NULL REFERENCES Account(account_id),
+    fitid TEXT NOT NULL CHECK(LENGTH(fitid) > 0),
+    transaction_id INTEGER NOT NULL,
+    PRIMARY KEY (account_id, fitid)
+) STRICT;
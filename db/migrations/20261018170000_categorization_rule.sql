@@ -0,0 +1,28 @@
+-- Assigns the store and the expense account of a transaction from its description, see `rules test`. The rules are
+-- tried in the order of their id, and the first one that matches fires.
+CREATE TABLE CategorizationRule (
+    rule_id INTEGER NOT NULL PRIMARY KEY,
+    -- a case-insensitive substring of the description, or a regular expression
+    pattern TEXT NOT NULL CHECK(LENGTH(pattern) > 0),
+    is_regex INTEGER NOT NULL CHECK(
+        is_regex = 0
+        OR is_regex = 1
+    ),
+    -- in cents, the bounds of the amount of the transaction, inclusive
+    min_amount INTEGER CHECK(min_amount >= 0),
+    max_amount INTEGER CHECK(max_amount >= 0),
+    -- the account that pays (e.g. a credit card), any account when NULL
+    account_id INTEGER REFERENCES Account(account_id),
+    store_id INTEGER REFERENCES Store(store_id),
+    -- the expense account of the purchase, by its type (e.g. GROCERY)
+    account_type_id INTEGER REFERENCES AccountType(account_type_id),
+    CHECK(
+        min_amount IS NULL
+        OR max_amount IS NULL
+        OR min_amount <= max_amount
+    ),
+    CHECK(
+        store_id IS NOT NULL
+        OR account_type_id IS NOT NULL
+    )
+) STRICT;
@@ -0,0 +1,295 @@
+-- A loss is superficial when the person (in any of their accounts, registered or not) acquires the same security
+-- within 30 days before or after the sale, and still holds it 30 days after the sale. The loss is denied in
+-- proportion to the units that are replaced, and added to the ACB of the replacement units held outside of the
+-- registered accounts.
+DROP VIEW Acb;
+
+DROP VIEW AcbPrecomputation;
+
+-- the change in units of every account of the persons, including the registered ones
+CREATE VIEW StockUnitChange AS
+SELECT
+    transaction_id,
+    person_id,
+    security_id,
+    date,
+    SUM(
+        CASE
+            WHEN debit IS NOT NULL THEN unit
+            ELSE - unit
+        END
+    ) AS unit,
+    tax_shelter_type = 'NON-REGISTERED' AS is_non_registered
+FROM
+    FinancialEntry
+    INNER JOIN StockAccountEntry USING (account_id)
+    INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+WHERE
+    account_subtype = 'STOCK'
+GROUP BY
+    transaction_id,
+    person_id,
+    security_id,
+    is_non_registered;
+
+CREATE VIEW AcbSuperficialUnits AS WITH SellPeriod AS (
+    SELECT
+        transaction_id,
+        person_id,
+        security_id,
+        - unit AS sold_units,
+        DATE(date, 'unixepoch', 'localtime', '-30 days') AS period_start,
+        DATE(date, 'unixepoch', 'localtime', '+30 days') AS period_end
+    FROM
+        AcbBaseData
+    WHERE
+        unit < 0
+),
+UnitChange AS (
+    SELECT
+        *,
+        DATE(date, 'unixepoch', 'localtime') AS day
+    FROM
+        StockUnitChange
+),
+PeriodUnits AS (
+    SELECT
+        SellPeriod.transaction_id,
+        SellPeriod.person_id,
+        SellPeriod.security_id,
+        sold_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN UnitChange.unit > 0
+                    AND UnitChange.transaction_id <> SellPeriod.transaction_id
+                    AND day BETWEEN period_start
+                    AND period_end THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS acquired_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN day <= period_end THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS held_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN UnitChange.unit > 0
+                    AND UnitChange.transaction_id <> SellPeriod.transaction_id
+                    AND day BETWEEN period_start
+                    AND period_end
+                    AND is_non_registered THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS acquired_non_registered_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN day <= period_end
+                    AND is_non_registered THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS held_non_registered_units
+    FROM
+        SellPeriod
+        LEFT JOIN UnitChange USING (person_id, security_id)
+    GROUP BY
+        SellPeriod.transaction_id,
+        SellPeriod.person_id,
+        SellPeriod.security_id
+),
+ReplacedUnits AS (
+    SELECT
+        transaction_id,
+        person_id,
+        security_id,
+        sold_units,
+        MAX(MIN(sold_units, acquired_units, held_units), 0) AS replaced_units,
+        acquired_non_registered_units,
+        held_non_registered_units
+    FROM
+        PeriodUnits
+)
+SELECT
+    transaction_id,
+    person_id,
+    security_id,
+    CAST(replaced_units AS REAL) / sold_units AS superficial_fraction,
+    -- the replacement units in the registered accounts have no ACB, so their share of the loss is lost
+    CAST(
+        MAX(
+            MIN(
+                replaced_units,
+                acquired_non_registered_units,
+                held_non_registered_units
+            ),
+            0
+        ) AS REAL
+    ) / sold_units AS superficial_acb_fraction
+FROM
+    ReplacedUnits
+WHERE
+    replaced_units > 0;
+
+CREATE VIEW AcbPrecomputation AS
+SELECT
+    AcbInjectPrevRunningTotal.*,
+    CASE
+        WHEN unit > 0 THEN book_value
+        ELSE 0
+    END AS acb_increase,
+    CASE
+        WHEN unit < 0 THEN -- COALESCE for handling an edge case where I'm exiting a position, i.e. acb_decrease_factor = 0 meaning ACB becomes 0
+        COALESCE(
+            CAST(prev_acc_units + unit AS REAL) / prev_acc_units,
+            0.0
+        )
+        ELSE 1.0
+    END AS acb_decrease_factor,
+    COALESCE(superficial_fraction, 0.0) AS superficial_fraction,
+    COALESCE(superficial_acb_fraction, 0.0) AS superficial_acb_fraction,
+    ROW_NUMBER() OVER(
+        PARTITION BY AcbInjectPrevRunningTotal.person_id,
+        AcbInjectPrevRunningTotal.security_id
+        ORDER BY
+            date,
+            sort_order
+    ) AS group_order
+FROM
+    AcbInjectPrevRunningTotal
+    LEFT JOIN AcbSuperficialUnits USING (transaction_id, person_id, security_id);
+
+CREATE VIEW Acb AS WITH RECURSIVE RecurseAcb (
+    sort_order,
+    person_id,
+    transaction_id,
+    date,
+    security_id,
+    unit,
+    book_value,
+    acc_units,
+    prev_acc_units,
+    acb_increase,
+    acb_decrease_factor,
+    superficial_fraction,
+    superficial_acb_fraction,
+    distribution,
+    group_order,
+    acb,
+    capital_gl,
+    superficial_loss
+) AS (
+    SELECT
+        sort_order,
+        person_id,
+        transaction_id,
+        date,
+        security_id,
+        unit,
+        book_value,
+        acc_units,
+        prev_acc_units,
+        acb_increase,
+        acb_decrease_factor,
+        superficial_fraction,
+        superficial_acb_fraction,
+        distribution,
+        group_order,
+        -- acb for the first buy is the book cost
+        book_value,
+        -- first entry should be a buy, so no capital gain/loss
+        0,
+        0
+    FROM
+        AcbPrecomputation
+    WHERE
+        group_order = 1
+    UNION
+    ALL
+    -- The gross gain/loss of a sale is the book value less the cost of the sold units, which is whatever the
+    -- remaining ACB doesn't keep, so no cent is lost. MIN(gross, 0) is the loss that can be denied, since buys
+    -- always have a positive gross.
+    SELECT
+        a.sort_order,
+        a.person_id,
+        a.transaction_id,
+        a.date,
+        a.security_id,
+        a.unit,
+        a.book_value,
+        a.acc_units,
+        a.prev_acc_units,
+        a.acb_increase,
+        a.acb_decrease_factor,
+        a.superficial_fraction,
+        a.superficial_acb_fraction,
+        a.distribution,
+        a.group_order,
+        CAST(
+            ROUND(
+                (b.acb + a.acb_increase) * a.acb_decrease_factor
+            ) AS INTEGER
+        ) + CAST(
+            ROUND(
+                - MIN(
+                    ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                        ROUND(
+                            (b.acb + a.acb_increase) * a.acb_decrease_factor
+                        ) AS INTEGER
+                    ),
+                    0
+                ) * a.superficial_acb_fraction
+            ) AS INTEGER
+        ),
+        CASE
+            WHEN a.unit < 0 THEN ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                ROUND(
+                    (b.acb + a.acb_increase) * a.acb_decrease_factor
+                ) AS INTEGER
+            ) + CAST(
+                ROUND(
+                    - MIN(
+                        ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                            ROUND(
+                                (b.acb + a.acb_increase) * a.acb_decrease_factor
+                            ) AS INTEGER
+                        ),
+                        0
+                    ) * a.superficial_fraction
+                ) AS INTEGER
+            )
+            ELSE 0
+        END,
+        CAST(
+            ROUND(
+                - MIN(
+                    ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                        ROUND(
+                            (b.acb + a.acb_increase) * a.acb_decrease_factor
+                        ) AS INTEGER
+                    ),
+                    0
+                ) * a.superficial_fraction
+            ) AS INTEGER
+        )
+    FROM
+        AcbPrecomputation a
+        INNER JOIN RecurseAcb b USING (person_id, security_id)
+    WHERE
+        a.group_order = b.group_order + 1
+)
+SELECT
+    *
+FROM
+    RecurseAcb;
@@ -0,0 +1,358 @@
+-- Return of capital lowers the ACB of the security, while reinvested (phantom) distributions raise it without
+-- changing the units, e.g. a return of capital debits the cash and credits RETURN-OF-CAPITAL, while a reinvested
+-- distribution debits REINVESTED-DISTRIBUTION and credits DISTRIBUTION. Both only adjust the cost of the STOCK account,
+-- so they are equity (like OPEN-BALANCE) that the net worth leaves out, since the stock is valued at its market price.
+INSERT
+    OR IGNORE INTO AccountSubtype (account_subtype, account_kind_id)
+SELECT
+    account_subtype,
+    account_kind_id
+FROM
+    (
+        SELECT
+            'RETURN-OF-CAPITAL' AS account_subtype
+        UNION
+        ALL
+        SELECT
+            'REINVESTED-DISTRIBUTION'
+    )
+    CROSS JOIN AccountKind
+WHERE
+    account_kind = 'EQUITY';
+
+INSERT INTO
+    StockAccountEntryType (account_subtype_id)
+SELECT
+    account_subtype_id
+FROM
+    AccountSubtype
+WHERE
+    account_subtype IN ('RETURN-OF-CAPITAL', 'REINVESTED-DISTRIBUTION');
+
+-- the existing holders get the accounts of the new subtypes, like upsert_stock_account_holder does
+INSERT
+    OR IGNORE INTO Account (
+        account_key,
+        account_type_id,
+        account_subtype_id,
+        account_name
+    )
+SELECT
+    person_key || '-' || account_type || '-' || ticker || '-' || account_subtype,
+    account_type_id,
+    account_subtype_id,
+    account_name || ', ' || ticker || ', ' || account_subtype
+FROM
+    StockAccountHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN SECURITY USING (security_id)
+    CROSS JOIN AccountSubtype
+WHERE
+    account_subtype IN ('RETURN-OF-CAPITAL', 'REINVESTED-DISTRIBUTION');
+
+INSERT
+    OR IGNORE INTO StockAccountEntry (
+        stock_account_holder_id,
+        account_subtype_id,
+        account_id
+    )
+SELECT
+    stock_account_holder_id,
+    AccountSubtype.account_subtype_id,
+    account_id
+FROM
+    StockAccountHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN SECURITY USING (security_id)
+    CROSS JOIN AccountSubtype
+    INNER JOIN Account ON Account.account_key = person_key || '-' || account_type || '-' || ticker || '-' || account_subtype
+WHERE
+    account_subtype IN ('RETURN-OF-CAPITAL', 'REINVESTED-DISTRIBUTION');
+
+DROP VIEW Acb;
+
+DROP VIEW AcbPrecomputation;
+
+DROP VIEW AcbBaseData;
+
+CREATE VIEW AcbBaseData AS WITH BaseData AS (
+    SELECT
+        transaction_id,
+        person_id,
+        date,
+        security_id,
+        SUM(
+            -- only the STOCK accounts can change number of units in a position
+            CASE
+                WHEN account_subtype = 'STOCK' THEN unit * CASE
+                    WHEN debit IS NOT NULL THEN 1 -- buying
+                    ELSE -1 -- selling
+                END
+                ELSE 0
+            END
+        ) AS unit,
+        COALESCE(exchange_rate, 1.0) AS exchange_rate,
+        SUM(
+            CASE
+                WHEN account_subtype = 'STOCK' THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS book_value,
+        SUM(
+            CASE
+                WHEN account_subtype = 'DISTRIBUTION' THEN CAST(
+                    ROUND(credit_amount * COALESCE(exchange_rate, 1.0)) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS distribution,
+        SUM(
+            CASE
+                WHEN account_subtype = 'COMMISSION' THEN debit
+                ELSE 0
+            END
+        ) AS commission,
+        -- a return of capital (credit) lowers the ACB, a reinvested distribution (debit) raises it
+        SUM(
+            CASE
+                WHEN account_subtype IN ('RETURN-OF-CAPITAL', 'REINVESTED-DISTRIBUTION') THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS acb_adjustment,
+        MIN(
+            CASE
+                -- distributions come before stock purchases
+                WHEN account_subtype IN (
+                    'DISTRIBUTION',
+                    'RETURN-OF-CAPITAL',
+                    'REINVESTED-DISTRIBUTION'
+                ) THEN 1
+                WHEN account_subtype = 'STOCK' THEN 2
+                WHEN account_subtype = 'COMMISSION' THEN 3
+            END
+        ) AS sort_order
+    FROM
+        FinancialEntryAmount
+        INNER JOIN StockAccountEntry USING (account_id)
+        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+        INNER JOIN CashAccountProduct USING (account_type_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountKind USING (account_kind_id)
+        INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+        LEFT JOIN TransactionForex USING (transaction_id)
+    WHERE
+        account_subtype IN (
+            'STOCK',
+            'DISTRIBUTION',
+            'COMMISSION',
+            'RETURN-OF-CAPITAL',
+            'REINVESTED-DISTRIBUTION'
+        )
+        AND tax_shelter_type = 'NON-REGISTERED'
+        AND transaction_id NOT IN (
+            SELECT
+                transaction_id
+            FROM
+                ClosingTransaction
+        )
+    GROUP BY
+        transaction_id
+)
+SELECT
+    transaction_id,
+    person_id,
+    date,
+    security_id,
+    exchange_rate,
+    unit,
+    book_value - commission AS book_value,
+    distribution,
+    acb_adjustment,
+    sort_order
+FROM
+    BaseData;
+
+CREATE VIEW AcbPrecomputation AS
+SELECT
+    AcbInjectPrevRunningTotal.*,
+    CASE
+        WHEN unit > 0 THEN book_value
+        ELSE 0
+    END + acb_adjustment AS acb_increase,
+    CASE
+        WHEN unit < 0 THEN -- COALESCE for handling an edge case where I'm exiting a position, i.e. acb_decrease_factor = 0 meaning ACB becomes 0
+        COALESCE(
+            CAST(prev_acc_units + unit AS REAL) / prev_acc_units,
+            0.0
+        )
+        ELSE 1.0
+    END AS acb_decrease_factor,
+    COALESCE(superficial_fraction, 0.0) AS superficial_fraction,
+    COALESCE(superficial_acb_fraction, 0.0) AS superficial_acb_fraction,
+    ROW_NUMBER() OVER(
+        PARTITION BY AcbInjectPrevRunningTotal.person_id,
+        AcbInjectPrevRunningTotal.security_id
+        ORDER BY
+            date,
+            sort_order
+    ) AS group_order
+FROM
+    AcbInjectPrevRunningTotal
+    LEFT JOIN AcbSuperficialUnits USING (transaction_id, person_id, security_id);
+
+-- When a return of capital takes the ACB below zero, the negative part is a deemed capital gain and the ACB is reset
+-- to zero.
+CREATE VIEW Acb AS WITH RECURSIVE RecurseAcb (
+    sort_order,
+    person_id,
+    transaction_id,
+    date,
+    security_id,
+    unit,
+    book_value,
+    acc_units,
+    prev_acc_units,
+    acb_increase,
+    acb_decrease_factor,
+    superficial_fraction,
+    superficial_acb_fraction,
+    distribution,
+    group_order,
+    acb,
+    capital_gl,
+    superficial_loss,
+    deemed_gain
+) AS (
+    SELECT
+        sort_order,
+        person_id,
+        transaction_id,
+        date,
+        security_id,
+        unit,
+        book_value,
+        acc_units,
+        prev_acc_units,
+        acb_increase,
+        acb_decrease_factor,
+        superficial_fraction,
+        superficial_acb_fraction,
+        distribution,
+        group_order,
+        -- acb for the first buy is the book cost
+        MAX(acb_increase, 0),
+        -- first entry should be a buy, so no capital gain/loss other than a deemed gain
+        MAX(- acb_increase, 0),
+        0,
+        MAX(- acb_increase, 0)
+    FROM
+        AcbPrecomputation
+    WHERE
+        group_order = 1
+    UNION
+    ALL
+    -- The gross gain/loss of a sale is the book value less the cost of the sold units, which is whatever the
+    -- remaining ACB doesn't keep, so no cent is lost. MIN(gross, 0) is the loss that can be denied, since buys
+    -- always have a positive gross.
+    SELECT
+        a.sort_order,
+        a.person_id,
+        a.transaction_id,
+        a.date,
+        a.security_id,
+        a.unit,
+        a.book_value,
+        a.acc_units,
+        a.prev_acc_units,
+        a.acb_increase,
+        a.acb_decrease_factor,
+        a.superficial_fraction,
+        a.superficial_acb_fraction,
+        a.distribution,
+        a.group_order,
+        MAX(
+            CAST(
+                ROUND(
+                    (b.acb + a.acb_increase) * a.acb_decrease_factor
+                ) AS INTEGER
+            ),
+            0
+        ) + CAST(
+            ROUND(
+                - MIN(
+                    ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                        ROUND(
+                            (b.acb + a.acb_increase) * a.acb_decrease_factor
+                        ) AS INTEGER
+                    ),
+                    0
+                ) * a.superficial_acb_fraction
+            ) AS INTEGER
+        ),
+        CASE
+            WHEN a.unit < 0 THEN ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                ROUND(
+                    (b.acb + a.acb_increase) * a.acb_decrease_factor
+                ) AS INTEGER
+            ) + CAST(
+                ROUND(
+                    - MIN(
+                        ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                            ROUND(
+                                (b.acb + a.acb_increase) * a.acb_decrease_factor
+                            ) AS INTEGER
+                        ),
+                        0
+                    ) * a.superficial_fraction
+                ) AS INTEGER
+            )
+            ELSE 0
+        END + MAX(
+            - CAST(
+                ROUND(
+                    (b.acb + a.acb_increase) * a.acb_decrease_factor
+                ) AS INTEGER
+            ),
+            0
+        ),
+        CAST(
+            ROUND(
+                - MIN(
+                    ABS(a.book_value) - (b.acb + a.acb_increase) + CAST(
+                        ROUND(
+                            (b.acb + a.acb_increase) * a.acb_decrease_factor
+                        ) AS INTEGER
+                    ),
+                    0
+                ) * a.superficial_fraction
+            ) AS INTEGER
+        ),
+        MAX(
+            - CAST(
+                ROUND(
+                    (b.acb + a.acb_increase) * a.acb_decrease_factor
+                ) AS INTEGER
+            ),
+            0
+        )
+    FROM
+        AcbPrecomputation a
+        INNER JOIN RecurseAcb b USING (person_id, security_id)
+    WHERE
+        a.group_order = b.group_order + 1
+)
+SELECT
+    *
+FROM
+    RecurseAcb;
@@ -0,0 +1,572 @@
+-- Splits, consolidations, ticker changes and mergers convert the units held before the effective date, e.g. a
+-- 4-for-1 split has 1 old unit and 4 new units. A ticker change or a merger moves the units into another security.
+CREATE TABLE CorporateAction (
+    security_id INTEGER NOT NULL REFERENCES SECURITY(security_id),
+    date INTEGER NOT NULL CHECK (date >= 0),
+    action TEXT NOT NULL CHECK (
+        action IN (
+            'SPLIT',
+            'CONSOLIDATION',
+            'TICKER-CHANGE',
+            'MERGER'
+        )
+    ),
+    old_units INTEGER NOT NULL CHECK (old_units > 0),
+    new_units INTEGER NOT NULL CHECK (new_units > 0),
+    new_security_id INTEGER REFERENCES SECURITY(security_id) CHECK (new_security_id <> security_id),
+    PRIMARY KEY (security_id, date),
+    CHECK (
+        CASE
+            action
+            WHEN 'SPLIT' THEN new_units > old_units
+            AND new_security_id IS NULL
+            WHEN 'CONSOLIDATION' THEN new_units < old_units
+            AND new_security_id IS NULL
+            WHEN 'TICKER-CHANGE' THEN new_units = old_units
+            AND new_security_id IS NOT NULL
+            ELSE new_security_id IS NOT NULL
+        END
+    )
+) STRICT;
+
+-- a security can only become another security once
+CREATE UNIQUE INDEX CorporateAction_idx_NewSecurity ON CorporateAction (security_id)
+WHERE
+    new_security_id IS NOT NULL;
+
+-- the security that the units end up in after the ticker changes and mergers, e.g. the ACB of an old ticker is carried
+-- over to the new one
+CREATE VIEW SecurityLineage AS WITH RECURSIVE Lineage (security_id, successor_security_id) AS (
+    SELECT
+        security_id,
+        security_id
+    FROM
+        SECURITY
+    UNION
+    SELECT
+        Lineage.security_id,
+        new_security_id
+    FROM
+        Lineage
+        INNER JOIN CorporateAction ON CorporateAction.security_id = Lineage.successor_security_id
+    WHERE
+        new_security_id IS NOT NULL
+)
+SELECT
+    security_id,
+    successor_security_id
+FROM
+    Lineage
+WHERE
+    successor_security_id NOT IN (
+        SELECT
+            security_id
+        FROM
+            CorporateAction
+        WHERE
+            new_security_id IS NOT NULL
+    );
+
+-- the units of the STOCK entries, positive when buying
+CREATE VIEW StockEntryUnit AS
+SELECT
+    transaction_id,
+    person_id,
+    account_type_id,
+    security_id,
+    date,
+    CASE
+        WHEN debit IS NOT NULL THEN unit
+        ELSE - unit
+    END AS unit
+FROM
+    FinancialEntry
+    INNER JOIN StockAccountEntry USING (account_id)
+    INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+WHERE
+    account_subtype = 'STOCK';
+
+-- The change of units of every account on the effective date of the corporate actions. The units of an entry are
+-- followed through the actions that come after it, in the units of the security at the time.
+CREATE VIEW CorporateActionUnitChange AS WITH RECURSIVE HeldUnits (
+    person_id,
+    account_type_id,
+    security_id,
+    date,
+    unit
+) AS (
+    SELECT
+        person_id,
+        account_type_id,
+        security_id,
+        (
+            SELECT
+                MIN(CorporateAction.date)
+            FROM
+                CorporateAction
+            WHERE
+                CorporateAction.security_id = StockEntryUnit.security_id
+                AND CorporateAction.date > StockEntryUnit.date
+        ),
+        CAST(unit AS REAL)
+    FROM
+        StockEntryUnit
+    UNION
+    ALL
+    SELECT
+        person_id,
+        account_type_id,
+        COALESCE(new_security_id, CorporateAction.security_id),
+        (
+            SELECT
+                MIN(NextAction.date)
+            FROM
+                CorporateAction NextAction
+            WHERE
+                NextAction.security_id = COALESCE(
+                    CorporateAction.new_security_id,
+                    CorporateAction.security_id
+                )
+                AND NextAction.date > CorporateAction.date
+        ),
+        unit * new_units / old_units
+    FROM
+        HeldUnits
+        INNER JOIN CorporateAction USING (security_id, date)
+),
+ActionUnits AS (
+    SELECT
+        person_id,
+        account_type_id,
+        security_id,
+        date,
+        action,
+        new_security_id,
+        CAST(ROUND(SUM(unit)) AS INTEGER) AS held_units,
+        CAST(ROUND(SUM(unit) * new_units / old_units) AS INTEGER) AS new_held_units
+    FROM
+        HeldUnits
+        INNER JOIN CorporateAction USING (security_id, date)
+    GROUP BY
+        person_id,
+        account_type_id,
+        security_id,
+        date
+    HAVING
+        held_units <> 0
+)
+SELECT
+    person_id,
+    account_type_id,
+    security_id,
+    date,
+    action,
+    new_held_units - held_units AS unit
+FROM
+    ActionUnits
+WHERE
+    new_security_id IS NULL
+UNION
+ALL
+SELECT
+    person_id,
+    account_type_id,
+    security_id,
+    date,
+    action,
+    - held_units
+FROM
+    ActionUnits
+WHERE
+    new_security_id IS NOT NULL
+UNION
+ALL
+SELECT
+    person_id,
+    account_type_id,
+    new_security_id,
+    date,
+    action,
+    new_held_units
+FROM
+    ActionUnits
+WHERE
+    new_security_id IS NOT NULL;
+
+-- the units of the accounts change with the STOCK entries and the corporate actions
+CREATE VIEW StockUnitHistory AS
+SELECT
+    transaction_id,
+    person_id,
+    account_type_id,
+    security_id,
+    date,
+    unit
+FROM
+    StockEntryUnit
+UNION
+ALL
+SELECT
+    NULL,
+    person_id,
+    account_type_id,
+    security_id,
+    date,
+    unit
+FROM
+    CorporateActionUnitChange;
+
+DROP VIEW NormalizedStockBalance;
+
+CREATE VIEW NormalizedStockBalance AS WITH StockCount AS (
+    SELECT
+        person_id,
+        security_id,
+        CAST(
+            ROUND(
+                CAST(
+                    ROUND(SUM(unit) * SecurityPriceAsOf.price / 10000.0) AS INTEGER
+                ) * market_exchange_rate
+            ) AS INTEGER
+        ) AS balance
+    FROM
+        StockUnitHistory
+        INNER JOIN SECURITY USING(security_id)
+        INNER JOIN SecurityPriceAsOf USING(security_id)
+        INNER JOIN MarketExchangeRate USING (currency_id)
+    WHERE
+        date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
+    GROUP BY
+        person_id,
+        account_type_id,
+        security_id
+    HAVING
+        balance <> 0
+)
+SELECT
+    person_id,
+    asset_class_id,
+    SUM(CAST(ROUND(balance * rate) AS INTEGER)) AS balance
+FROM
+    StockCount
+    INNER JOIN PerClassAllocationRate USING (person_id, security_id)
+GROUP BY
+    person_id,
+    asset_class_id;
+
+DROP VIEW AcbPrecomputation;
+
+DROP VIEW AcbSuperficialUnits;
+
+DROP VIEW StockUnitChange;
+
+DROP VIEW AcbBaseData;
+
+CREATE VIEW AcbBaseData AS WITH BaseData AS (
+    SELECT
+        transaction_id,
+        person_id,
+        date,
+        successor_security_id AS security_id,
+        SUM(
+            -- only the STOCK accounts can change number of units in a position
+            CASE
+                WHEN account_subtype = 'STOCK' THEN unit * CASE
+                    WHEN debit IS NOT NULL THEN 1 -- buying
+                    ELSE -1 -- selling
+                END
+                ELSE 0
+            END
+        ) AS unit,
+        COALESCE(exchange_rate, 1.0) AS exchange_rate,
+        SUM(
+            CASE
+                WHEN account_subtype = 'STOCK' THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS book_value,
+        SUM(
+            CASE
+                WHEN account_subtype = 'DISTRIBUTION' THEN CAST(
+                    ROUND(credit_amount * COALESCE(exchange_rate, 1.0)) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS distribution,
+        SUM(
+            CASE
+                WHEN account_subtype = 'COMMISSION' THEN debit
+                ELSE 0
+            END
+        ) AS commission,
+        -- a return of capital (credit) lowers the ACB, a reinvested distribution (debit) raises it
+        SUM(
+            CASE
+                WHEN account_subtype IN ('RETURN-OF-CAPITAL', 'REINVESTED-DISTRIBUTION') THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS acb_adjustment,
+        MIN(
+            CASE
+                -- distributions come before stock purchases
+                WHEN account_subtype IN (
+                    'DISTRIBUTION',
+                    'RETURN-OF-CAPITAL',
+                    'REINVESTED-DISTRIBUTION'
+                ) THEN 1
+                WHEN account_subtype = 'STOCK' THEN 2
+                WHEN account_subtype = 'COMMISSION' THEN 3
+            END
+        ) AS sort_order
+    FROM
+        FinancialEntryAmount
+        INNER JOIN StockAccountEntry USING (account_id)
+        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+        INNER JOIN CashAccountProduct USING (account_type_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountKind USING (account_kind_id)
+        INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+        INNER JOIN SecurityLineage USING (security_id)
+        LEFT JOIN TransactionForex USING (transaction_id)
+    WHERE
+        account_subtype IN (
+            'STOCK',
+            'DISTRIBUTION',
+            'COMMISSION',
+            'RETURN-OF-CAPITAL',
+            'REINVESTED-DISTRIBUTION'
+        )
+        AND tax_shelter_type = 'NON-REGISTERED'
+        AND transaction_id NOT IN (
+            SELECT
+                transaction_id
+            FROM
+                ClosingTransaction
+        )
+    GROUP BY
+        transaction_id
+),
+-- the corporate actions change the units without changing the ACB
+CorporateActionData AS (
+    SELECT
+        person_id,
+        date,
+        successor_security_id AS security_id,
+        SUM(unit) AS unit
+    FROM
+        CorporateActionUnitChange
+        INNER JOIN CashAccountProduct USING (account_type_id)
+        INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+        INNER JOIN SecurityLineage USING (security_id)
+    WHERE
+        tax_shelter_type = 'NON-REGISTERED'
+    GROUP BY
+        person_id,
+        date,
+        successor_security_id
+    HAVING
+        SUM(unit) <> 0
+)
+SELECT
+    transaction_id,
+    person_id,
+    date,
+    security_id,
+    exchange_rate,
+    unit,
+    book_value - commission AS book_value,
+    distribution,
+    acb_adjustment,
+    sort_order
+FROM
+    BaseData
+UNION
+ALL
+-- the action takes effect at the start of the day, before the trades
+SELECT
+    NULL,
+    person_id,
+    date,
+    security_id,
+    1.0,
+    unit,
+    0,
+    0,
+    0,
+    0
+FROM
+    CorporateActionData;
+
+-- the change in units of every account of the persons, including the registered ones
+CREATE VIEW StockUnitChange AS
+SELECT
+    transaction_id,
+    person_id,
+    successor_security_id AS security_id,
+    date,
+    SUM(unit) AS unit,
+    tax_shelter_type = 'NON-REGISTERED' AS is_non_registered
+FROM
+    StockUnitHistory
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+    INNER JOIN SecurityLineage USING (security_id)
+GROUP BY
+    transaction_id,
+    date,
+    person_id,
+    successor_security_id,
+    is_non_registered;
+
+CREATE VIEW AcbSuperficialUnits AS WITH SellPeriod AS (
+    SELECT
+        transaction_id,
+        person_id,
+        security_id,
+        - unit AS sold_units,
+        DATE(date, 'unixepoch', 'localtime', '-30 days') AS period_start,
+        DATE(date, 'unixepoch', 'localtime', '+30 days') AS period_end
+    FROM
+        AcbBaseData
+    WHERE
+        unit < 0
+        -- the corporate actions aren't sales
+        AND transaction_id IS NOT NULL
+),
+UnitChange AS (
+    SELECT
+        *,
+        DATE(date, 'unixepoch', 'localtime') AS day
+    FROM
+        StockUnitChange
+),
+PeriodUnits AS (
+    SELECT
+        SellPeriod.transaction_id,
+        SellPeriod.person_id,
+        SellPeriod.security_id,
+        sold_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN UnitChange.unit > 0
+                    AND UnitChange.transaction_id <> SellPeriod.transaction_id
+                    AND day BETWEEN period_start
+                    AND period_end THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS acquired_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN day <= period_end THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS held_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN UnitChange.unit > 0
+                    AND UnitChange.transaction_id <> SellPeriod.transaction_id
+                    AND day BETWEEN period_start
+                    AND period_end
+                    AND is_non_registered THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS acquired_non_registered_units,
+        COALESCE(
+            SUM(
+                CASE
+                    WHEN day <= period_end
+                    AND is_non_registered THEN UnitChange.unit
+                END
+            ),
+            0
+        ) AS held_non_registered_units
+    FROM
+        SellPeriod
+        LEFT JOIN UnitChange USING (person_id, security_id)
+    GROUP BY
+        SellPeriod.transaction_id,
+        SellPeriod.person_id,
+        SellPeriod.security_id
+),
+ReplacedUnits AS (
+    SELECT
+        transaction_id,
+        person_id,
+        security_id,
+        sold_units,
+        MAX(MIN(sold_units, acquired_units, held_units), 0) AS replaced_units,
+        acquired_non_registered_units,
+        held_non_registered_units
+    FROM
+        PeriodUnits
+)
+SELECT
+    transaction_id,
+    person_id,
+    security_id,
+    CAST(replaced_units AS REAL) / sold_units AS superficial_fraction,
+    -- the replacement units in the registered accounts have no ACB, so their share of the loss is lost
+    CAST(
+        MAX(
+            MIN(
+                replaced_units,
+                acquired_non_registered_units,
+                held_non_registered_units
+            ),
+            0
+        ) AS REAL
+    ) / sold_units AS superficial_acb_fraction
+FROM
+    ReplacedUnits
+WHERE
+    replaced_units > 0;
+
+CREATE VIEW AcbPrecomputation AS
+SELECT
+    AcbInjectPrevRunningTotal.*,
+    CASE
+        WHEN unit > 0 THEN book_value
+        ELSE 0
+    END + acb_adjustment AS acb_increase,
+    CASE
+        -- the units of the corporate actions don't change the ACB
+        WHEN unit < 0
+        AND transaction_id IS NOT NULL THEN -- COALESCE for handling an edge case where I'm exiting a position, i.e. acb_decrease_factor = 0 meaning ACB becomes 0
+        COALESCE(
+            CAST(prev_acc_units + unit AS REAL) / prev_acc_units,
+            0.0
+        )
+        ELSE 1.0
+    END AS acb_decrease_factor,
+    COALESCE(superficial_fraction, 0.0) AS superficial_fraction,
+    COALESCE(superficial_acb_fraction, 0.0) AS superficial_acb_fraction,
+    ROW_NUMBER() OVER(
+        PARTITION BY AcbInjectPrevRunningTotal.person_id,
+        AcbInjectPrevRunningTotal.security_id
+        ORDER BY
+            date,
+            sort_order
+    ) AS group_order
+FROM
+    AcbInjectPrevRunningTotal
+    LEFT JOIN AcbSuperficialUnits USING (transaction_id, person_id, security_id);
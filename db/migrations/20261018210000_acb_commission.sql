@@ -0,0 +1,155 @@
+-- The commission now adds to the cost of a purchase and comes off the proceeds of a sale, where it used to be taken off
+-- the book value of both. It is converted with the exchange rate of the trade instead of using the raw debit, and is
+-- also kept on its own for the outlays of the dispositions.
+DROP VIEW AcbBaseData;
+
+CREATE VIEW AcbBaseData AS WITH BaseData AS (
+    SELECT
+        transaction_id,
+        person_id,
+        date,
+        successor_security_id AS security_id,
+        SUM(
+            -- only the STOCK accounts can change number of units in a position
+            CASE
+                WHEN account_subtype = 'STOCK' THEN unit * CASE
+                    WHEN debit IS NOT NULL THEN 1 -- buying
+                    ELSE -1 -- selling
+                END
+                ELSE 0
+            END
+        ) AS unit,
+        COALESCE(exchange_rate, 1.0) AS exchange_rate,
+        SUM(
+            CASE
+                WHEN account_subtype = 'STOCK' THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS book_value,
+        SUM(
+            CASE
+                WHEN account_subtype = 'DISTRIBUTION' THEN CAST(
+                    ROUND(credit_amount * COALESCE(exchange_rate, 1.0)) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS distribution,
+        SUM(
+            CASE
+                WHEN account_subtype = 'COMMISSION' THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS commission,
+        -- a return of capital (credit) lowers the ACB, a reinvested distribution (debit) raises it
+        SUM(
+            CASE
+                WHEN account_subtype IN ('RETURN-OF-CAPITAL', 'REINVESTED-DISTRIBUTION') THEN CAST(
+                    ROUND(
+                        (debit_amount - credit_amount) * COALESCE(exchange_rate, 1.0)
+                    ) AS INTEGER
+                )
+                ELSE 0
+            END
+        ) AS acb_adjustment,
+        MIN(
+            CASE
+                -- distributions come before stock purchases
+                WHEN account_subtype IN (
+                    'DISTRIBUTION',
+                    'RETURN-OF-CAPITAL',
+                    'REINVESTED-DISTRIBUTION'
+                ) THEN 1
+                WHEN account_subtype = 'STOCK' THEN 2
+                WHEN account_subtype = 'COMMISSION' THEN 3
+            END
+        ) AS sort_order
+    FROM
+        FinancialEntryAmount
+        INNER JOIN StockAccountEntry USING (account_id)
+        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+        INNER JOIN CashAccountProduct USING (account_type_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountKind USING (account_kind_id)
+        INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+        INNER JOIN SecurityLineage USING (security_id)
+        LEFT JOIN TransactionForex USING (transaction_id)
+    WHERE
+        account_subtype IN (
+            'STOCK',
+            'DISTRIBUTION',
+            'COMMISSION',
+            'RETURN-OF-CAPITAL',
+            'REINVESTED-DISTRIBUTION'
+        )
+        AND tax_shelter_type = 'NON-REGISTERED'
+        AND transaction_id NOT IN (
+            SELECT
+                transaction_id
+            FROM
+                ClosingTransaction
+        )
+    GROUP BY
+        transaction_id
+),
+-- the corporate actions change the units without changing the ACB
+CorporateActionData AS (
+    SELECT
+        person_id,
+        date,
+        successor_security_id AS security_id,
+        SUM(unit) AS unit
+    FROM
+        CorporateActionUnitChange
+        INNER JOIN CashAccountProduct USING (account_type_id)
+        INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+        INNER JOIN SecurityLineage USING (security_id)
+    WHERE
+        tax_shelter_type = 'NON-REGISTERED'
+    GROUP BY
+        person_id,
+        date,
+        successor_security_id
+    HAVING
+        SUM(unit) <> 0
+)
+SELECT
+    transaction_id,
+    person_id,
+    date,
+    security_id,
+    exchange_rate,
+    unit,
+    -- the commission adds to the cost of a purchase, and comes off the proceeds of a sale
+    book_value + commission AS book_value,
+    distribution,
+    acb_adjustment,
+    sort_order,
+    commission
+FROM
+    BaseData
+UNION
+ALL
+-- the action takes effect at the start of the day, before the trades
+SELECT
+    NULL,
+    person_id,
+    date,
+    security_id,
+    1.0,
+    unit,
+    0,
+    0,
+    0,
+    0,
+    0
+FROM
+    CorporateActionData;
+
@@ -0,0 +1,65 @@
+-- The history owns the price: SECURITY.price only seeds the history of a security that has none, so an old price from
+-- the CSV files can't be recorded as today's quote.
+DROP TRIGGER Security_trigger_update_RecordPrice;
+
+CREATE TRIGGER Security_trigger_update_RecordPrice
+AFTER
+UPDATE
+    OF price ON SECURITY
+    WHEN NEW.price <> OLD.price
+    AND NEW.price > 0
+    AND NOT EXISTS (
+        SELECT
+            *
+        FROM
+            SecurityPrice
+        WHERE
+            security_id = NEW.security_id
+    ) BEGIN
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+VALUES
+    (
+        NEW.security_id,
+        CAST(
+            strftime('%s', 'now', 'localtime', 'start of day', 'utc') AS INTEGER
+        ),
+        NEW.price
+    );
+
+END;
+
+-- ValuationDate stays NULL in the ledger, a past valuation only sets it inside its transaction (see
+-- `set_valuation_date`)
+DROP VIEW SecurityPriceAsOf;
+
+-- the price of each security on the valuation date, NULL when there is no quote on or before it
+CREATE VIEW SecurityPriceAsOf AS
+SELECT
+    security_id,
+    (
+        SELECT
+            SecurityPrice.price
+        FROM
+            SecurityPrice
+        WHERE
+            SecurityPrice.security_id = SECURITY.security_id
+            AND SecurityPrice.date <= (
+                SELECT
+                    as_of
+                FROM
+                    ValuationCutoff
+            )
+        ORDER BY
+            SecurityPrice.date DESC
+        LIMIT
+            1
+    ) AS price
+FROM
+    SECURITY;
+
+-- quick test
+SELECT
+    *
+FROM
+    AllocationView;
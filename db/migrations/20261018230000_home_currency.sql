@@ -0,0 +1,87 @@
+-- the currency of the reports, every other currency is converted into it
+ALTER TABLE
+    Currency
+ADD
+    COLUMN is_home INTEGER NOT NULL DEFAULT 0 CHECK (is_home IN (0, 1));
+
+-- the views used to convert into CAD
+UPDATE
+    Currency
+SET
+    is_home = 1
+WHERE
+    currency = 'CAD';
+
+CREATE UNIQUE INDEX Currency_idx_Home ON Currency (is_home)
+WHERE
+    is_home = 1;
+
+CREATE VIEW HomeCurrency AS
+SELECT
+    currency_id,
+    currency
+FROM
+    Currency
+WHERE
+    is_home = 1;
+
+-- the dated rates that convert a currency into the home currency, in either direction of the quotes
+CREATE VIEW HomeExchangeRate AS
+SELECT
+    base_currency_id AS currency_id,
+    date,
+    rate
+FROM
+    ExchangeRate
+    INNER JOIN HomeCurrency ON quote_currency_id = HomeCurrency.currency_id
+UNION
+ALL
+SELECT
+    quote_currency_id AS currency_id,
+    date,
+    1.0 / rate AS rate
+FROM
+    ExchangeRate
+    INNER JOIN HomeCurrency ON base_currency_id = HomeCurrency.currency_id;
+
+DROP VIEW MarketExchangeRate;
+
+-- rate that converts each currency into the home currency on the valuation date, NULL when there is no rate on or
+-- before it (or no home currency), so the holding is left out instead of being valued at another date's rate
+CREATE VIEW MarketExchangeRate AS
+SELECT
+    currency_id,
+    CASE
+        WHEN currency_id IN (
+            SELECT
+                currency_id
+            FROM
+                HomeCurrency
+        ) THEN 1.0
+        ELSE (
+            SELECT
+                rate
+            FROM
+                HomeExchangeRate
+            WHERE
+                HomeExchangeRate.currency_id = Currency.currency_id
+                AND date <= (
+                    SELECT
+                        as_of
+                    FROM
+                        ValuationCutoff
+                )
+            ORDER BY
+                date DESC
+            LIMIT
+                1
+        )
+    END AS market_exchange_rate
+FROM
+    Currency;
+
+-- quick test
+SELECT
+    *
+FROM
+    AllocationView;
@@ -0,0 +1,21 @@
+-- The valuation date and the views that value holdings move to the TEMP schema of each connection, see
+-- db/src/valuation_date.sql. Changes to these views go there rather than in a migration.
+DROP VIEW AllocationView;
+
+DROP VIEW TotalAssetBalance;
+
+DROP VIEW BalanceByAssetClass;
+
+DROP VIEW NormalizedStockBalance;
+
+DROP VIEW SecurityPriceAsOf;
+
+DROP VIEW NormalizedFixedIncomeBalance;
+
+DROP VIEW MarketExchangeRate;
+
+DROP VIEW CashView;
+
+DROP VIEW ValuationCutoff;
+
+DROP TABLE ValuationDate;
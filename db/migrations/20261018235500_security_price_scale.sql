@@ -0,0 +1,22 @@
+-- The quotes keep 4 decimal places like the prices of the entries (10000 is one dollar) instead of cents, so a sub-cent
+-- quote (e.g. 0.0425) isn't rounded. The valuation views divide by 1000000 accordingly, see db/src/valuation_date.sql.
+-- The trigger of SecurityPrice carries the latest quote of each security into SECURITY.price.
+UPDATE
+    SecurityPrice
+SET
+    price = price * 100;
+
+-- a security without a history has no price, or the price the CSV file seeded it with
+UPDATE
+    SECURITY
+SET
+    price = price * 100
+WHERE
+    NOT EXISTS (
+        SELECT
+            *
+        FROM
+            SecurityPrice
+        WHERE
+            SecurityPrice.security_id = SECURITY.security_id
+    );
@@ -0,0 +1,194 @@
+use std::{collections::BTreeSet, path::Path};
+
+use crate::{
+    Error, FinancialEntry, Money, Price, Query, Transaction, TransactionForex, TransactionStore,
+};
+
+/// The rows written by `add_transaction`, in the shape of transaction.csv, transaction_store.csv and
+/// transaction_forex.csv.
+pub struct AddedTransaction {
+    pub entries: Vec<FinancialEntry>,
+    pub store: Option<TransactionStore>,
+    pub forex: Option<TransactionForex>,
+}
+
+/// Checks that the entries make a transaction: a debit and a credit on different accounts, no zero amount, and the
+/// debits equal to the credits.
+fn check_entries(entries: &[FinancialEntry]) -> Result<(), Error> {
+    let amount = |price: Option<Price>, entry: &FinancialEntry| {
+        price
+            .map(|price| entry.unit.times(price))
+            .unwrap_or(Money::ZERO)
+    };
+
+    if !entries.iter().any(|entry| entry.debit.is_some())
+        || !entries.iter().any(|entry| entry.credit.is_some())
+    {
+        return Err(Error::invalid(
+            "a transaction needs at least one debit and one credit",
+        ));
+    }
+
+    let accounts: BTreeSet<_> = entries.iter().map(|entry| &entry.account_key).collect();
+    if accounts.len() < 2 {
+        return Err(Error::invalid(
+            "the debits and the credits are on the same account",
+        ));
+    }
+
+    if let Some(entry) = entries
+        .iter()
+        .find(|entry| amount(entry.debit.or(entry.credit), entry).is_zero())
+    {
+        return Err(Error::invalid(format!(
+            "the entry of {} has a zero amount",
+            entry.account_key
+        )));
+    }
+
+    let debit: Money = entries.iter().map(|entry| amount(entry.debit, entry)).sum();
+    let credit: Money = entries
+        .iter()
+        .map(|entry| amount(entry.credit, entry))
+        .sum();
+
+    if debit != credit {
+        return Err(Error::invalid(format!(
+            "the debits (${debit}) don't equal the credits (${credit}), off by ${}",
+            (debit - credit).abs()
+        )));
+    }
+
+    Ok(())
+}
+
+impl Transaction<'_> {
+    /// Inserts a new transaction, numbered after the last one of the database and of `transaction_csv` (when the
+    /// transaction is appended to it). The transaction_id and the item_id of the entries are assigned here. Nothing
+    /// is written unless the debits equal the credits.
+    pub async fn add_transaction(
+        &mut self,
+        mut entries: Vec<FinancialEntry>,
+        store_key: Option<&str>,
+        exchange_rate: Option<f64>,
+        transaction_csv: Option<&Path>,
+    ) -> Result<AddedTransaction, Error> {
+        check_entries(&entries)?;
+
+        let transaction_id = self
+            .get_next_transaction_id_after_csv(transaction_csv)
+            .await?;
+
+        for (index, entry) in entries.iter_mut().enumerate() {
+            entry.transaction_id = transaction_id;
+            entry.item_id = index as i64 + 1;
+
+            if let Err(err) = self.execute(entry.query()).await {
+                return Err(Error::from_record(err, entry).of_entry(entry.entry_id()));
+            }
+        }
+
+        let store = store_key.map(|store_key| TransactionStore {
+            transaction_id,
+            store_key: store_key.to_string(),
+        });
+        if let Some(store) = &store {
+            if let Err(err) = self.execute(store.query()).await {
+                return Err(Error::from_record(err, store));
+            }
+        }
+
+        let forex = exchange_rate.map(|exchange_rate| TransactionForex {
+            transaction_id,
+            exchange_rate,
+        });
+        if let Some(forex) = &forex {
+            if let Err(err) = self.execute(forex.query()).await {
+                return Err(Error::from_record(err, forex));
+            }
+        }
+
+        Ok(AddedTransaction {
+            entries,
+            store,
+            forex,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{write_csv, Db, Units};
+
+    fn entry(account_key: &str, debit: Option<&str>, credit: Option<&str>) -> FinancialEntry {
+        FinancialEntry {
+            transaction_id: 0,
+            item_id: 0,
+            date: 0,
+            account_key: account_key.into(),
+            unit: Units::from_raw(Units::SCALE),
+            debit: debit.map(|debit| debit.parse().unwrap()),
+            credit: credit.map(|credit| credit.parse().unwrap()),
+            description: "".into(),
+        }
+    }
+
+    #[test]
+    fn balanced_entries() {
+        assert!(check_entries(&[
+            entry("ALICE-CASH", Some("12.34"), None),
+            entry("ALICE-GROCERY", None, Some("12.34")),
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn entries_on_the_same_account() {
+        assert!(check_entries(&[
+            entry("ALICE-CASH", Some("12.34"), None),
+            entry("ALICE-CASH", None, Some("12.34")),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn entries_with_zero_amount() {
+        assert!(check_entries(&[
+            entry("ALICE-CASH", Some("0"), None),
+            entry("ALICE-GROCERY", None, Some("0")),
+        ])
+        .is_err());
+        assert!(check_entries(&[
+            entry("ALICE-CASH", Some("12.34"), None),
+            entry("ALICE-GROCERY", None, Some("12.34")),
+            entry("ALICE-GIFT", None, Some("0")),
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn next_transaction_id_after_csv() {
+        let csv_path =
+            std::env::temp_dir().join(format!("pit-transaction-{}.csv", std::process::id()));
+        let mut staged = entry("ALICE-CASH", Some("12.34"), None);
+        staged.transaction_id = 7;
+        write_csv(&csv_path, &[staged]).unwrap();
+
+        let mut db = Db::from_path("sqlite::memory:".into()).await.unwrap();
+        let mut transaction = db.begin_wrapped_transaction().await.unwrap();
+        let next_transaction_id = transaction
+            .get_next_transaction_id_after_csv(Some(&csv_path))
+            .await;
+        std::fs::remove_file(&csv_path).unwrap();
+
+        assert_eq!(next_transaction_id.unwrap(), 8);
+        assert_eq!(
+            transaction
+                .get_next_transaction_id_after_csv(None)
+                .await
+                .unwrap(),
+            1
+        );
+    }
+}
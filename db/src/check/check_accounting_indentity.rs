@@ -1,8 +1,8 @@
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction};
 
 pub struct AccountingIdentityResult {
-    pub asset_balance: f64,
-    pub equity_liabilities_balance: f64,
+    pub asset_balance: Money,
+    pub equity_liabilities_balance: Money,
     pub is_balance: bool,
 }
 
@@ -13,19 +13,9 @@ impl Transaction<'_> {
             r#"
 WITH Lhs AS (
     SELECT
-        ROUND(
-            SUM(
-                ROUND(
-                    unit * (
-                        COALESCE(debit, 0) - COALESCE(credit, 0)
-                    ),
-                    2
-                )
-            ),
-            2
-        ) AS balance
+        SUM(debit_amount - credit_amount) AS balance
     FROM
-        FinancialEntry
+        FinancialEntryAmount
         INNER JOIN Account USING(account_id)
         INNER JOIN AccountSubtype USING(account_subtype_id)
         INNER JOIN AccountKind USING(account_kind_id)
@@ -34,19 +24,9 @@ WITH Lhs AS (
 ),
 Rhs AS (
     SELECT
-        ROUND(
-            SUM(
-                ROUND(
-                    unit * (
-                        COALESCE(credit, 0) - COALESCE(debit, 0)
-                    ),
-                    2
-                )
-            ),
-            2
-        ) AS balance
+        SUM(credit_amount - debit_amount) AS balance
     FROM
-        FinancialEntry
+        FinancialEntryAmount
         INNER JOIN Account USING(account_id)
         INNER JOIN AccountSubtype USING(account_subtype_id)
         INNER JOIN AccountKind USING(account_kind_id)
@@ -56,24 +36,24 @@ Rhs AS (
 SELECT
     (
         SELECT
-            COALESCE(balance, 0.0)
+            COALESCE(balance, 0)
         FROM
             Lhs
-    ) AS "asset_balance!:f64",
+    ) AS "asset_balance!:Money",
     (
         SELECT
-            COALESCE(balance, 0.0)
+            COALESCE(balance, 0)
         FROM
             Rhs
-    ) AS "equity_liabilities_balance!:f64",
+    ) AS "equity_liabilities_balance!:Money",
     (
         SELECT
-            COALESCE(balance, 0.0)
+            COALESCE(balance, 0)
         FROM
             Lhs
     ) = (
         SELECT
-            COALESCE(balance, 0.0)
+            COALESCE(balance, 0)
         FROM
             Rhs
     ) AS "is_balance!:bool"
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Local};
+use sqlx::Row;
+
+use crate::{SqlResult, Transaction};
+
+pub struct ChangedReconciledEntry {
+    pub transaction_id: i64,
+    pub item_id: i64,
+    pub account_key: String,
+    pub statement_date: DateTime<Local>,
+    /// ADDED, CHANGED or DELETED
+    pub change: String,
+}
+
+impl Transaction<'_> {
+    /// The reconciled entries that were changed or deleted afterward, and the entries added to reconciled periods.
+    pub async fn check_reconciled_entries(&mut self) -> SqlResult<Vec<ChangedReconciledEntry>> {
+        let records = sqlx::query(
+            r#"
+SELECT
+    transaction_id,
+    item_id,
+    account_key,
+    statement_date,
+    change
+FROM
+    ChangedReconciledEntry
+    INNER JOIN Account USING (account_id)
+ORDER BY
+    transaction_id,
+    item_id
+"#,
+        )
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|row| ChangedReconciledEntry {
+            transaction_id: row.get(0),
+            item_id: row.get(1),
+            account_key: row.get(2),
+            statement_date: row.get(3),
+            change: row.get(4),
+        })
+        .collect();
+
+        Ok(records)
+    }
+}
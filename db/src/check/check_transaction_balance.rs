@@ -1,10 +1,10 @@
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction};
 
 pub struct AssertTransactionBalance {
     pub transaction_id: i64,
-    pub debit: f64,
-    pub credit: f64,
-    pub balance: f64,
+    pub debit: Money,
+    pub credit: Money,
+    pub balance: Money,
 }
 
 impl Transaction<'_> {
@@ -13,16 +13,16 @@ impl Transaction<'_> {
             AssertTransactionBalance,
             r#"
 SELECT
-    transaction_id,
-    SUM(ROUND(unit * COALESCE(debit, 0), 2)) AS "debit!:f64",
-    SUM(ROUND(unit * COALESCE(credit, 0), 2)) AS "credit!:f64",
-    ROUND(SUM(ROUND((unit * COALESCE(debit, 0)), 2)) - SUM(ROUND((unit * COALESCE(credit, 0)), 2)), 2) AS "balance!:f64"
+    transaction_id AS "transaction_id!",
+    SUM(debit_amount) AS "debit!:Money",
+    SUM(credit_amount) AS "credit!:Money",
+    SUM(debit_amount) - SUM(credit_amount) AS "balance!:Money"
 FROM
-    FinancialEntry
+    FinancialEntryAmount
 GROUP BY
     transaction_id
 HAVING
-    "balance!:f64" <> 0
+    "balance!:Money" <> 0
 ORDER BY
     date
     "#
@@ -1,7 +1,9 @@
 mod check_accounting_indentity;
+mod check_reconciled_entry;
 mod check_transaction_balance;
 mod check_transaction_store;
 
 pub use check_accounting_indentity::AccountingIdentityResult;
+pub use check_reconciled_entry::ChangedReconciledEntry;
 pub use check_transaction_balance::AssertTransactionBalance;
 pub use check_transaction_store::CheckTransactionStore;
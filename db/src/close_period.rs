@@ -0,0 +1,331 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use common::end_of_day;
+use sqlx::Row;
+
+use crate::{Error, Money, Price, SqlResult, Transaction, Units};
+
+pub struct ClosingEntry {
+    pub account_key: String,
+    pub debit: Option<Money>,
+    pub credit: Option<Money>,
+}
+
+pub struct ClosedPeriod {
+    pub year: i32,
+    pub closed_until: DateTime<Local>,
+    /// None when there was no revenue or expense to close.
+    pub transaction_id: Option<i64>,
+    pub entries: Vec<ClosingEntry>,
+}
+
+impl Transaction<'_> {
+    /// Entries dated on or before the returned date cannot be inserted, changed or deleted.
+    pub async fn get_closed_until(&mut self) -> SqlResult<Option<DateTime<Local>>> {
+        let closed_until: Option<i64> = sqlx::query("SELECT MAX(closed_until) FROM ClosedPeriod")
+            .fetch_one(&mut *self.0)
+            .await?
+            .get(0);
+
+        Ok(closed_until.and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single()))
+    }
+
+    /// The end of the last closed period on or before the given date.
+    pub async fn get_closed_until_as_of(
+        &mut self,
+        as_of: &DateTime<Local>,
+    ) -> SqlResult<Option<DateTime<Local>>> {
+        let timestamp = as_of.timestamp();
+
+        let closed_until: Option<i64> =
+            sqlx::query("SELECT MAX(closed_until) FROM ClosedPeriod WHERE closed_until <= ?")
+                .bind(timestamp)
+                .fetch_one(&mut *self.0)
+                .await?
+                .get(0);
+
+        Ok(closed_until.and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single()))
+    }
+
+    /// Posts the closing entries that move the balance of every revenue and expense account, up to the end of
+    /// the year, into the retained earnings of the person who owns the account. Then locks the entries of the year.
+    pub async fn close_period(&mut self, year: i32) -> Result<ClosedPeriod, Error> {
+        let last_day = NaiveDate::from_ymd_opt(year, 12, 31)
+            .ok_or_else(|| Error::invalid(format!("{year} is not a valid year")))?;
+        let closed_until = end_of_day(&last_day);
+
+        if closed_until >= Local::now() {
+            return Err(Error::invalid(format!("{year} hasn't ended yet")));
+        }
+
+        if let Some(prev_closed_until) = self.get_closed_until().await? {
+            if prev_closed_until >= closed_until {
+                return Err(Error::invalid(format!(
+                    "the entries are already closed until {}",
+                    prev_closed_until.date_naive()
+                )));
+            }
+        }
+
+        let timestamp = closed_until.timestamp();
+
+        struct Balance {
+            account_id: i64,
+            account_key: String,
+            person_id: Option<i64>,
+            balance: Money,
+        }
+
+        let balances: Vec<Balance> = sqlx::query(
+            r#"
+SELECT
+    account_id,
+    Account.account_key,
+    person_id,
+    SUM(debit_amount - credit_amount) AS balance
+FROM
+    FinancialEntryAmount
+    INNER JOIN Account USING (account_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+    LEFT JOIN OwnedAccount USING (account_id)
+WHERE
+    account_kind IN ('REVENUE', 'EXPENSE')
+    AND date <= ?
+GROUP BY
+    account_id
+HAVING
+    balance <> 0
+ORDER BY
+    person_id,
+    Account.account_key
+"#,
+        )
+        .bind(timestamp)
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|row| Balance {
+            account_id: row.get(0),
+            account_key: row.get(1),
+            person_id: row.get(2),
+            balance: row.get(3),
+        })
+        .collect();
+
+        // the closing transactions are numbered below zero, so they never take the id of a transaction in
+        // transaction.csv that isn't upserted yet
+        let transaction_id = if balances.is_empty() {
+            None
+        } else {
+            Some(-i64::from(year))
+        };
+
+        let description = format!("Closing entry of {year}");
+        let mut entries = Vec::new();
+        let mut item_id = 0;
+
+        // the balances are ordered by person, so each person's retained earnings follow their accounts
+        let mut index = 0;
+        while index < balances.len() {
+            let person_id = balances[index].person_id;
+            let mut net_debit = Money::ZERO;
+
+            while index < balances.len() && balances[index].person_id == person_id {
+                let balance = &balances[index];
+
+                // zeroes the account out, e.g. a revenue with a credit balance is debited
+                let (debit, credit) = if balance.balance.is_negative() {
+                    (Some(balance.balance.abs()), None)
+                } else {
+                    (None, Some(balance.balance))
+                };
+
+                item_id += 1;
+                self.insert_closing_entry(
+                    timestamp,
+                    transaction_id,
+                    item_id,
+                    balance.account_id,
+                    debit,
+                    credit,
+                    &description,
+                )
+                .await?;
+                entries.push(ClosingEntry {
+                    account_key: balance.account_key.clone(),
+                    debit,
+                    credit,
+                });
+
+                net_debit += balance.balance;
+                index += 1;
+            }
+
+            if net_debit.is_zero() {
+                continue;
+            }
+
+            let (account_id, account_key) = self.get_retained_earnings_account(person_id).await?;
+            let (debit, credit) = if net_debit.is_negative() {
+                (None, Some(net_debit.abs()))
+            } else {
+                (Some(net_debit), None)
+            };
+
+            item_id += 1;
+            self.insert_closing_entry(
+                timestamp,
+                transaction_id,
+                item_id,
+                account_id,
+                debit,
+                credit,
+                &description,
+            )
+            .await?;
+            entries.push(ClosingEntry {
+                account_key,
+                debit,
+                credit,
+            });
+        }
+
+        sqlx::query!(
+            "INSERT INTO ClosedPeriod (year, closed_until, transaction_id) VALUES (?, ?, ?)",
+            year,
+            timestamp,
+            transaction_id
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        Ok(ClosedPeriod {
+            year,
+            closed_until,
+            transaction_id,
+            entries,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_closing_entry(
+        &mut self,
+        timestamp: i64,
+        transaction_id: Option<i64>,
+        item_id: i64,
+        account_id: i64,
+        debit: Option<Money>,
+        credit: Option<Money>,
+        description: &str,
+    ) -> SqlResult<()> {
+        let unit = Units::from_raw(Units::SCALE);
+        let debit = debit.map(Price::from);
+        let credit = credit.map(Price::from);
+
+        sqlx::query!(
+            r#"
+INSERT INTO
+    FinancialEntry (
+        date,
+        transaction_id,
+        item_id,
+        account_id,
+        unit,
+        debit,
+        credit,
+        description
+    )
+VALUES
+    (?, ?, ?, ?, ?, ?, ?, ?)
+"#,
+            timestamp,
+            transaction_id,
+            item_id,
+            account_id,
+            unit,
+            debit,
+            credit,
+            description
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Creates the retained earnings account of the person on first use, `None` is for the accounts nobody owns.
+    async fn get_retained_earnings_account(
+        &mut self,
+        person_id: Option<i64>,
+    ) -> Result<(i64, String), Error> {
+        let (account_key, account_name) = match person_id {
+            Some(person_id) => {
+                let row = sqlx::query(
+                    "SELECT person_key, first_name || ' ' || last_name FROM Person WHERE person_id = ?",
+                )
+                .bind(person_id)
+                .fetch_one(&mut *self.0)
+                .await?;
+                let person_key: String = row.get(0);
+                let name: String = row.get(1);
+
+                (
+                    format!("{person_key}-RETAINED-EARNINGS"),
+                    format!("Retained Earnings of {name}"),
+                )
+            }
+            None => (
+                "RETAINED-EARNINGS".to_string(),
+                "Retained Earnings".to_string(),
+            ),
+        };
+
+        sqlx::query!(
+            r#"
+INSERT INTO
+    Account (
+        account_key,
+        account_subtype_id,
+        account_type_id,
+        account_name
+    )
+SELECT
+    ?,
+    account_subtype_id,
+    account_type_id,
+    ?
+FROM
+    AccountSubtype
+    INNER JOIN AccountType
+WHERE
+    account_subtype = 'RETAINED-EARNINGS'
+    AND account_type = 'RETAINED-EARNINGS' ON CONFLICT (account_key) DO NOTHING
+"#,
+            account_key,
+            account_name
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        let account_id: i64 = sqlx::query("SELECT account_id FROM Account WHERE account_key = ?")
+            .bind(&account_key)
+            .fetch_one(&mut *self.0)
+            .await?
+            .get(0);
+
+        sqlx::query!(
+            r#"
+INSERT INTO
+    RetainedEarningsAccount (account_id, person_id)
+VALUES
+    (?, ?) ON CONFLICT (account_id) DO NOTHING
+"#,
+            account_id,
+            person_id
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        Ok((account_id, account_key))
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use common::{struct_field_names, Id};
+use serde::{de, Serialize};
+
+use crate::Error;
+
+/// Reads the CSV file into a map keyed by the id, along with the line of each record for reporting errors.
+pub fn read_csv_into_map<T>(csv_path: &Path) -> Result<BTreeMap<T::IdType, (u64, T)>, Error>
+where
+    T: Id + de::DeserializeOwned,
+{
+    let mut errors = Vec::new();
+    let parsed_records = read_csv_collecting_errors(csv_path, &mut errors);
+
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(parsed_records),
+    }
+}
+
+/// Reads every row of the CSV file, the rows that cannot be parsed and the duplicate ids are pushed into `errors`
+/// so that all the problems of the file are reported at once.
+pub fn read_csv_collecting_errors<T>(
+    csv_path: &Path,
+    errors: &mut Vec<Error>,
+) -> BTreeMap<T::IdType, (u64, T)>
+where
+    T: Id + de::DeserializeOwned,
+{
+    let csv_error = |line: Option<u64>| {
+        move |source: csv::Error| Error::Csv {
+            path: csv_path.to_path_buf(),
+            line,
+            source,
+        }
+    };
+
+    let mut parsed_records = BTreeMap::new();
+
+    let mut reader = match csv::Reader::from_path(csv_path) {
+        Ok(reader) => reader,
+        Err(err) => {
+            errors.push(csv_error(None)(err));
+            return parsed_records;
+        }
+    };
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => {
+            errors.push(csv_error(Some(1))(err));
+            return parsed_records;
+        }
+    };
+
+    let mut row = csv::StringRecord::new();
+
+    loop {
+        match reader.read_record(&mut row) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => {
+                let line = err.position().map(|position| position.line());
+                let is_io_error = matches!(err.kind(), csv::ErrorKind::Io(_));
+                errors.push(csv_error(line)(err));
+
+                // the reader cannot recover from I/O errors, but it can skip a malformed row
+                if is_io_error {
+                    break;
+                }
+                continue;
+            }
+        }
+
+        let line = row.position().map(|position| position.line()).unwrap_or(0);
+        let record: T = match row.deserialize(Some(&headers)) {
+            Ok(record) => record,
+            Err(err) => {
+                errors.push(csv_error(Some(line))(err));
+                continue;
+            }
+        };
+        let id = record.id();
+
+        if let Some((first_line, _)) = parsed_records.get(&id) {
+            errors.push(Error::DuplicateId {
+                path: csv_path.to_path_buf(),
+                line,
+                first_line: *first_line,
+                id: format!("{id:?}"),
+            });
+            continue;
+        }
+
+        parsed_records.insert(id, (line, record));
+    }
+
+    parsed_records
+}
+
+/// Appends the records to the end of the CSV file, the header is written when the file is new.
+pub fn append_csv<T>(csv_path: &Path, records: &[T]) -> Result<(), Error>
+where
+    T: Serialize + de::DeserializeOwned,
+{
+    stage_append_csv(csv_path, records)?.commit()
+}
+
+/// A CSV file with appended records, written into a temporary file next to it until `commit` renames it over the
+/// file, so that several files can be changed together. The file is left untouched when this is dropped instead.
+pub struct StagedCsv {
+    csv_path: PathBuf,
+    staged_path: PathBuf,
+}
+
+impl StagedCsv {
+    pub fn commit(self) -> Result<(), Error> {
+        fs::rename(&self.staged_path, &self.csv_path).map_err(Error::io(&self.csv_path))
+    }
+
+    /// Commits the files once all of them are staged, so that a file that cannot be written leaves every file as is.
+    pub fn commit_all(staged: Vec<StagedCsv>) -> Result<(), Error> {
+        staged.into_iter().try_for_each(StagedCsv::commit)
+    }
+}
+
+impl Drop for StagedCsv {
+    fn drop(&mut self) {
+        // already gone when committed
+        let _ = fs::remove_file(&self.staged_path);
+    }
+}
+
+/// Copies the CSV file with the records appended into a temporary file, see `StagedCsv`. The records are written in
+/// the column order of the existing header, since the columns may have been moved around in a spreadsheet.
+pub fn stage_append_csv<T>(csv_path: &Path, records: &[T]) -> Result<StagedCsv, Error>
+where
+    T: Serialize + de::DeserializeOwned,
+{
+    let csv_error = |line| {
+        move |source| Error::Csv {
+            path: csv_path.to_path_buf(),
+            line,
+            source,
+        }
+    };
+
+    let mut content = match fs::read(csv_path) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+        Err(err) => return Err(Error::io(csv_path)(err)),
+    };
+
+    let field_names = struct_field_names::<T>();
+    let header = if content.is_empty() {
+        None
+    } else {
+        let header = csv::Reader::from_reader(content.as_slice())
+            .headers()
+            .map_err(csv_error(Some(1)))?
+            .clone();
+        Some(header)
+    };
+
+    // the position of each column in the serialized records, None for the columns that aren't read back
+    let columns: Vec<Option<usize>> = match &header {
+        Some(header) => {
+            let columns: Vec<_> = header
+                .iter()
+                .map(|column| field_names.iter().position(|name| *name == column.trim()))
+                .collect();
+
+            let missing: Vec<_> = field_names
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !columns.contains(&Some(*index)))
+                .map(|(_, name)| *name)
+                .collect();
+            if !missing.is_empty() {
+                return Err(Error::invalid(format!(
+                    "the header has no column for {}, the records cannot be appended",
+                    missing.join(", ")
+                ))
+                .at(csv_path, 1));
+            }
+
+            columns
+        }
+        None => (0..field_names.len()).map(Some).collect(),
+    };
+
+    // spreadsheets don't always end the last row with a newline
+    if content.last().is_some_and(|byte| *byte != b'\n') {
+        content.push(b'\n');
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(content);
+
+    if header.is_none() {
+        writer.write_record(field_names).map_err(csv_error(None))?;
+    }
+
+    for record in records {
+        let fields = serialize_fields(record).map_err(csv_error(None))?;
+        let row = columns
+            .iter()
+            .map(|column| column.and_then(|index| fields.get(index)).unwrap_or(""));
+        writer.write_record(row).map_err(csv_error(None))?;
+    }
+
+    let content = writer
+        .into_inner()
+        .map_err(|err| Error::io(csv_path)(err.into_error()))?;
+
+    let staged_path = csv_path.with_extension("csv.tmp");
+    fs::write(&staged_path, content).map_err(Error::io(&staged_path))?;
+
+    Ok(StagedCsv {
+        csv_path: csv_path.to_path_buf(),
+        staged_path,
+    })
+}
+
+/// The fields of the record in the order of the struct.
+fn serialize_fields<T: Serialize>(record: &T) -> Result<csv::StringRecord, csv::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(vec![]);
+    writer.serialize(record)?;
+    let serialized = writer
+        .into_inner()
+        .map_err(|err| csv::Error::from(err.into_error()))?;
+
+    let mut row = csv::StringRecord::new();
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(serialized.as_slice())
+        .read_record(&mut row)?;
+
+    Ok(row)
+}
+
+/// Writes the records into the CSV file, replacing its content.
+pub fn write_csv<T>(csv_path: &Path, records: &[T]) -> Result<(), Error>
+where
+    T: Serialize + de::DeserializeOwned,
+{
+    let csv_error = |source| Error::Csv {
+        path: csv_path.to_path_buf(),
+        line: None,
+        source,
+    };
+
+    // the header is written explicitly, so that an empty file still has one
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_path(csv_path)
+        .map_err(csv_error)?;
+
+    writer
+        .write_record(struct_field_names::<T>())
+        .map_err(csv_error)?;
+
+    for record in records {
+        writer.serialize(record).map_err(csv_error)?;
+    }
+
+    writer.flush().map_err(Error::io(csv_path))?;
+
+    Ok(())
+}
@@ -6,21 +6,28 @@ use sqlx::{
     ConnectOptions, Connection, SqliteConnection,
 };
 
-use crate::{Db, SqlResult, Transaction};
+use crate::{Db, Error, SqlResult, Transaction};
 
 impl Db {
-    pub async fn new() -> SqlResult<Self> {
-        let dir = home::home_dir()
-            .expect("cannot find home directory")
-            .join(".pit");
-        fs::create_dir_all(dir.clone()).expect("cannot create directory for storing data");
-
-        Self::from_path(dir.join("data.sqlite3")).await
+    pub async fn new() -> Result<Self, Error> {
+        Self::from_path(Self::default_path()).await
     }
 
-    pub async fn from_path(path: PathBuf) -> SqlResult<Self> {
-        let path = path.to_str().unwrap();
+    pub async fn from_path(path: PathBuf) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(Error::io(parent))?;
+            }
+        }
+
+        let path = path.to_str().ok_or_else(|| {
+            Error::invalid(format!(
+                "{} is not a valid UTF-8 path",
+                path.to_string_lossy()
+            ))
+        })?;
         let conn = SqliteConnectOptions::from_str(path)?
+            .create_if_missing(true)
             .journal_mode(SqliteJournalMode::Wal)
             .foreign_keys(true)
             .connect()
@@ -28,10 +35,55 @@ impl Db {
 
         let mut db = Self(conn);
         db.migrate_to_latest().await?;
+        db.create_valuation_views().await?;
 
         Ok(db)
     }
 
+    /// The directory that stores the default ledger and all named profiles, i.e. `~/.pit`.
+    pub fn data_dir() -> PathBuf {
+        home::home_dir()
+            .expect("cannot find home directory")
+            .join(".pit")
+    }
+
+    pub fn default_path() -> PathBuf {
+        Self::data_dir().join("data.sqlite3")
+    }
+
+    pub fn profile_dir() -> PathBuf {
+        Self::data_dir().join("profiles")
+    }
+
+    /// Each named profile is a separate ledger, stored as `~/.pit/profiles/<profile>.sqlite3`.
+    pub fn profile_path(profile: &str) -> PathBuf {
+        Self::profile_dir().join(format!("{profile}.sqlite3"))
+    }
+
+    pub fn list_profiles() -> std::io::Result<Vec<String>> {
+        let dir = Self::profile_dir();
+
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut profiles = Vec::new();
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) == Some("sqlite3") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    profiles.push(stem.to_string());
+                }
+            }
+        }
+
+        profiles.sort();
+
+        Ok(profiles)
+    }
+
     pub async fn migrate_to_latest(&mut self) -> Result<(), MigrateError> {
         sqlx::migrate!("./migrations").run(&mut self.0).await
     }
@@ -0,0 +1,112 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use common::{struct_field_names, Id};
+use serde::{de, Serialize};
+
+use crate::{read_csv_into_map, Error, Export, Transaction};
+
+pub struct ChangedField {
+    pub column: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+pub struct ChangedRecord {
+    pub id: String,
+    pub changes: Vec<ChangedField>,
+}
+
+/// Difference between a CSV file and the rows currently in the database.
+pub struct TableDiff {
+    pub csv_path: PathBuf,
+    pub columns: &'static [&'static str],
+    pub new: Vec<Vec<String>>,
+    pub changed: Vec<ChangedRecord>,
+    pub num_unchanged: usize,
+}
+
+/// Serializes the record the same way it is exported, so that both sides of the diff are normalized.
+fn to_fields<T: Serialize>(record: &T) -> Result<Vec<String>, csv::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(vec![]);
+    writer.serialize(record)?;
+
+    let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(bytes.as_slice());
+
+    match reader.records().next() {
+        Some(record) => Ok(record?.iter().map(|field| field.to_string()).collect()),
+        None => Ok(vec![]),
+    }
+}
+
+impl Transaction<'_> {
+    /// Compares the CSV file with the database without modifying anything.
+    pub async fn diff_all<T>(&mut self, csv_path: &Path) -> Result<TableDiff, Error>
+    where
+        T: Export + Id + de::DeserializeOwned,
+        T::IdType: std::fmt::Debug,
+    {
+        let parsed_records = read_csv_into_map::<T>(csv_path)?;
+
+        let existing_records: BTreeMap<T::IdType, T> = sqlx::query_as::<_, T>(T::DIFF_QUERY)
+            .fetch_all(&mut *self.0)
+            .await?
+            .into_iter()
+            .map(|record| (record.id(), record))
+            .collect();
+
+        let columns = struct_field_names::<T>();
+        let mut diff = TableDiff {
+            csv_path: csv_path.to_path_buf(),
+            columns,
+            new: vec![],
+            changed: vec![],
+            num_unchanged: 0,
+        };
+
+        for (id, (line, mut record)) in parsed_records {
+            let csv_error = |source| Error::Csv {
+                path: csv_path.to_path_buf(),
+                line: Some(line),
+                source,
+            };
+
+            let old_fields = match existing_records.get(&id) {
+                Some(existing_record) => {
+                    record.keep_existing(existing_record);
+                    to_fields(existing_record).map_err(csv_error)?
+                }
+                None => {
+                    diff.new.push(to_fields(&record).map_err(csv_error)?);
+                    continue;
+                }
+            };
+            let new_fields = to_fields(&record).map_err(csv_error)?;
+
+            let changes: Vec<_> = columns
+                .iter()
+                .zip(old_fields.into_iter().zip(new_fields))
+                .filter(|(_, (old, new))| old != new)
+                .map(|(column, (old, new))| ChangedField { column, old, new })
+                .collect();
+
+            if changes.is_empty() {
+                diff.num_unchanged += 1;
+            } else {
+                diff.changed.push(ChangedRecord {
+                    id: format!("{:?}", id),
+                    changes,
+                });
+            }
+        }
+
+        Ok(diff)
+    }
+}
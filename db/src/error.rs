@@ -0,0 +1,302 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use sqlx::migrate::MigrateError;
+
+/// Errors of the ledger. Errors that come from a CSV file carry the path and the line of the offending record.
+#[derive(Debug)]
+pub enum Error {
+    /// The CSV file cannot be read or a row cannot be parsed.
+    Csv {
+        path: PathBuf,
+        line: Option<u64>,
+        source: csv::Error,
+    },
+    /// Two rows of the CSV file have the same id.
+    DuplicateId {
+        path: PathBuf,
+        line: u64,
+        first_line: u64,
+        id: String,
+    },
+    /// The record refers to a key (e.g. person_key, account_key, ticker) that doesn't exist.
+    UnknownKey {
+        path: Option<PathBuf>,
+        line: Option<u64>,
+        key: String,
+        record: String,
+    },
+    /// The field refers to a key that isn't defined in the CSV folder, found while validating the folder.
+    UnresolvedKey {
+        path: PathBuf,
+        line: u64,
+        column: &'static str,
+        value: String,
+        /// The closest key that is defined, if any is close enough to be a typo.
+        suggestion: Option<String>,
+    },
+    /// The record violates a constraint of the database, e.g. a check or a foreign key.
+    Constraint {
+        path: Option<PathBuf>,
+        line: Option<u64>,
+        record: String,
+        source: sqlx::Error,
+    },
+    /// The record inserts, changes or deletes an entry dated on or before the lock date of a closed period.
+    ClosedPeriod {
+        path: Option<PathBuf>,
+        line: Option<u64>,
+        /// The transaction_id and item_id of the entry, which the triggers cannot put in their message.
+        entry: Option<(i64, i64)>,
+        record: String,
+    },
+    /// The database cannot be migrated to the latest schema.
+    Migration(MigrateError),
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Sql(sqlx::Error),
+    /// The request doesn't make sense for the ledger, e.g. a stock account that isn't a cash account.
+    Invalid {
+        path: Option<PathBuf>,
+        line: Option<u64>,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Classifies the failure of writing the record, so that a missing key is reported as such.
+    pub fn from_record(source: sqlx::Error, record: &impl fmt::Debug) -> Self {
+        let record = format!("{record:?}");
+
+        let database_error = match source.as_database_error() {
+            Some(database_error) => database_error,
+            None => return Self::Sql(source),
+        };
+
+        // the upserts look up the keys with subqueries, so an unknown key ends up as a NULL id
+        let not_null_column = database_error
+            .message()
+            .strip_prefix("NOT NULL constraint failed: ")
+            .filter(|column| column.ends_with("_id"))
+            .map(|column| column.to_string());
+
+        // raised by the triggers of FinancialEntry, see close-period
+        if database_error.message().contains("closed period") {
+            return Self::ClosedPeriod {
+                path: None,
+                line: None,
+                entry: None,
+                record,
+            };
+        }
+
+        match not_null_column {
+            Some(key) => Self::UnknownKey {
+                path: None,
+                line: None,
+                key,
+                record,
+            },
+            None => Self::Constraint {
+                path: None,
+                line: None,
+                record,
+                source,
+            },
+        }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::Invalid {
+            path: None,
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    /// Attaches the location of the record that caused the error, unless it already has one.
+    pub fn at(self, csv_path: &Path, record_line: u64) -> Self {
+        match self {
+            Self::UnknownKey {
+                path: None,
+                line: None,
+                key,
+                record,
+            } => Self::UnknownKey {
+                path: Some(csv_path.to_path_buf()),
+                line: Some(record_line),
+                key,
+                record,
+            },
+            Self::Constraint {
+                path: None,
+                line: None,
+                record,
+                source,
+            } => Self::Constraint {
+                path: Some(csv_path.to_path_buf()),
+                line: Some(record_line),
+                record,
+                source,
+            },
+            Self::ClosedPeriod {
+                path: None,
+                line: None,
+                entry,
+                record,
+            } => Self::ClosedPeriod {
+                path: Some(csv_path.to_path_buf()),
+                line: Some(record_line),
+                entry,
+                record,
+            },
+            Self::Invalid {
+                path: None,
+                line: None,
+                message,
+            } => Self::Invalid {
+                path: Some(csv_path.to_path_buf()),
+                line: Some(record_line),
+                message,
+            },
+            error => error,
+        }
+    }
+
+    /// Names the entry that is dated in a closed period, see `Query::entry_id`.
+    pub fn of_entry(self, entry_id: Option<(i64, i64)>) -> Self {
+        match self {
+            Self::ClosedPeriod {
+                path,
+                line,
+                entry: None,
+                record,
+            } => Self::ClosedPeriod {
+                path,
+                line,
+                entry: entry_id,
+                record,
+            },
+            error => error,
+        }
+    }
+
+    pub fn io(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        |source| Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// The path and line of the offending record, if the error came from a CSV file.
+    pub fn location(&self) -> Option<(&PathBuf, Option<u64>)> {
+        match self {
+            Self::Csv { path, line, .. } => Some((path, *line)),
+            Self::DuplicateId { path, line, .. } | Self::UnresolvedKey { path, line, .. } => {
+                Some((path, Some(*line)))
+            }
+            Self::UnknownKey {
+                path: Some(path),
+                line,
+                ..
+            }
+            | Self::Constraint {
+                path: Some(path),
+                line,
+                ..
+            }
+            | Self::ClosedPeriod {
+                path: Some(path),
+                line,
+                ..
+            }
+            | Self::Invalid {
+                path: Some(path),
+                line,
+                ..
+            } => Some((path, *line)),
+            Self::Io { path, .. } => Some((path, None)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location() {
+            Some((path, Some(line))) => write!(f, "{}:{}: ", path.to_string_lossy(), line)?,
+            Some((path, None)) => write!(f, "{}: ", path.to_string_lossy())?,
+            None => {}
+        }
+
+        match self {
+            Self::Csv { source, .. } => write!(f, "{source}"),
+            Self::DuplicateId { first_line, id, .. } => {
+                write!(f, "duplicate id {id}, first seen on line {first_line}")
+            }
+            Self::UnknownKey { key, record, .. } => {
+                write!(f, "unknown key for {key}, record: {record}")
+            }
+            Self::UnresolvedKey {
+                column,
+                value,
+                suggestion,
+                ..
+            } => match suggestion {
+                Some(suggestion) => {
+                    write!(
+                        f,
+                        "unknown {column} {value:?}, did you mean {suggestion:?}?"
+                    )
+                }
+                None => write!(f, "unknown {column} {value:?}"),
+            },
+            Self::Constraint { record, source, .. } => write!(f, "{source}, record: {record}"),
+            Self::ClosedPeriod {
+                entry: Some((transaction_id, item_id)),
+                record,
+                ..
+            } => write!(
+                f,
+                "the entry (transaction_id {transaction_id}, item_id {item_id}) is dated in a closed period and cannot be inserted, changed or deleted, record: {record}"
+            ),
+            Self::ClosedPeriod { record, .. } => write!(
+                f,
+                "the entry is dated in a closed period and cannot be inserted, changed or deleted, record: {record}"
+            ),
+            Self::Migration(source) => write!(f, "cannot migrate the database, {source}"),
+            Self::Io { source, .. } => write!(f, "{source}"),
+            Self::Sql(source) => write!(f, "{source}"),
+            Self::Invalid { message, .. } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Csv { source, .. } => Some(source),
+            Self::Constraint { source, .. } => Some(source),
+            Self::Migration(source) => Some(source),
+            Self::Io { source, .. } => Some(source),
+            Self::Sql(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(source: sqlx::Error) -> Self {
+        Self::Sql(source)
+    }
+}
+
+impl From<MigrateError> for Error {
+    fn from(source: MigrateError) -> Self {
+        Self::Migration(source)
+    }
+}
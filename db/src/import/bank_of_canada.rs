@@ -0,0 +1,138 @@
+use std::{collections::BTreeSet, path::Path};
+
+use chrono::NaiveDate;
+use common::start_of_day;
+use sqlx::Row;
+
+use crate::{Error, Transaction};
+
+pub struct ExchangeRateImport {
+    pub num_imported: usize,
+    /// Series (e.g. FXAUDCAD) whose currencies aren't in the database.
+    pub skipped_series: Vec<String>,
+}
+
+impl Transaction<'_> {
+    /// Loads the daily exchange rates exported by the Bank of Canada (i.e. Valet's FX_RATES_DAILY CSV),
+    /// where each `FX<BASE><QUOTE>` column is a series of rates.
+    pub async fn import_bank_of_canada_exchange_rate(
+        &mut self,
+        csv_path: &Path,
+    ) -> Result<ExchangeRateImport, Error> {
+        println!(
+            "importing exchange rates from {}",
+            csv_path.to_string_lossy()
+        );
+
+        let content = std::fs::read_to_string(csv_path).map_err(Error::io(csv_path))?;
+
+        // the observations come after the terms and conditions and the description of each series
+        const OBSERVATIONS_MARKER: &str = "\"OBSERVATIONS\"";
+        let observations = match content.find(OBSERVATIONS_MARKER) {
+            Some(index) => content[index + OBSERVATIONS_MARKER.len()..].trim_start(),
+            None => content.as_str(),
+        };
+
+        // lines of the reader are relative to the observations
+        let line_offset = content[..content.len() - observations.len()]
+            .matches('\n')
+            .count() as u64;
+
+        let csv_error = |line: Option<u64>| {
+            move |source: csv::Error| Error::Csv {
+                path: csv_path.to_path_buf(),
+                line,
+                source,
+            }
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(observations.as_bytes());
+
+        let headers = reader
+            .headers()
+            .map_err(csv_error(Some(line_offset + 1)))?
+            .clone();
+
+        let date_index = headers
+            .iter()
+            .position(|header| header == "date")
+            .ok_or_else(|| Error::invalid("missing date column").at(csv_path, line_offset + 1))?;
+
+        let series: Vec<_> = headers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, header)| {
+                let pair = header.strip_prefix("FX")?;
+
+                if pair.len() == 6 && pair.chars().all(|c| c.is_ascii_uppercase()) {
+                    Some((index, header, &pair[..3], &pair[3..]))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if series.is_empty() {
+            return Err(Error::invalid("no exchange rate series (e.g. FXUSDCAD)")
+                .at(csv_path, line_offset + 1));
+        }
+
+        let currencies: BTreeSet<String> = sqlx::query("SELECT currency FROM Currency")
+            .fetch_all(&mut *self.0)
+            .await?
+            .into_iter()
+            .map(|record| record.get(0))
+            .collect();
+
+        let (series, skipped_series): (Vec<_>, Vec<_>) =
+            series.into_iter().partition(|(_, _, base, quote)| {
+                currencies.contains(*base) && currencies.contains(*quote)
+            });
+
+        let mut num_imported = 0;
+
+        for record in reader.records() {
+            let record = record.map_err(csv_error(None))?;
+            let line = line_offset
+                + record
+                    .position()
+                    .map(|position| position.line())
+                    .unwrap_or(0);
+
+            let date = match record.get(date_index) {
+                Some(date) if !date.trim().is_empty() => {
+                    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|err| {
+                        Error::invalid(format!("{err}, record: {record:?}")).at(csv_path, line)
+                    })?
+                }
+                _ => continue,
+            };
+            let date = start_of_day(&date);
+
+            for (index, _, base, quote) in &series {
+                // a rate is missing on holidays
+                let rate = match record.get(*index).map(str::trim) {
+                    Some(rate) if !rate.is_empty() => rate.parse::<f64>().map_err(|err| {
+                        Error::invalid(format!("{err}, record: {record:?}")).at(csv_path, line)
+                    })?,
+                    _ => continue,
+                };
+
+                self.set_exchange_rate(base, quote, &date, rate)
+                    .await
+                    .map_err(|err| err.at(csv_path, line))?;
+                num_imported += 1;
+            }
+        }
+
+        Ok(ExchangeRateImport {
+            num_imported,
+            skipped_series: skipped_series
+                .into_iter()
+                .map(|(_, header, _, _)| header.to_string())
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,7 @@
+mod bank_of_canada;
+mod ofx;
+mod quote_file;
+
+pub use bank_of_canada::ExchangeRateImport;
+pub use ofx::{read_ofx, OfxAccountKind, OfxImport, OfxStatement, OfxTransaction};
+pub use quote_file::{PriceImport, QuoteFileFormat};
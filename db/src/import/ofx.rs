@@ -0,0 +1,444 @@
+use std::{collections::HashSet, path::Path};
+
+use chrono::NaiveDate;
+use common::start_of_day;
+use sqlx::Row;
+
+use crate::{Error, FinancialEntry, Money, Transaction, TransactionStore, Units};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OfxAccountKind {
+    /// A chequing or savings account, i.e. `<STMTRS>`.
+    Bank,
+    /// A credit card, i.e. `<CCSTMTRS>`.
+    CreditCard,
+}
+
+/// A `<STMTTRN>` of the statement. The amount is signed from the holder's side, i.e. deposits and card payments are
+/// positive, while withdrawals and purchases are negative.
+#[derive(Clone, Debug)]
+pub struct OfxTransaction {
+    pub line: u64,
+    pub fitid: String,
+    pub date: NaiveDate,
+    pub amount: Money,
+    pub transaction_type: String,
+    pub name: String,
+    pub memo: String,
+}
+
+impl OfxTransaction {
+    /// The payee, or the memo when the bank leaves the payee out.
+    pub fn description(&self) -> String {
+        match (self.name.is_empty(), self.memo.is_empty()) {
+            (false, false) if self.name != self.memo => format!("{} - {}", self.name, self.memo),
+            (false, _) => self.name.clone(),
+            (true, _) => self.memo.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OfxStatement {
+    pub kind: OfxAccountKind,
+    /// The `<ACCTID>` of the statement, usually a masked account number.
+    pub account_number: Option<String>,
+    pub transactions: Vec<OfxTransaction>,
+}
+
+pub struct OfxImport {
+    /// The draft entries, two per statement transaction, in the shape of transaction.csv.
+    pub entries: Vec<FinancialEntry>,
+    /// The stores assigned by the categorization rules, in the shape of transaction_store.csv.
+    pub stores: Vec<TransactionStore>,
+    /// Statement transactions whose FITID was imported before.
+    pub num_duplicates: usize,
+}
+
+/// The value of the first `<TAG>` in the text, up to the next tag. This works for both the SGML flavour (OFX 1.x and
+/// QFX), where the closing tags of the values are left out, and the XML flavour (OFX 2.x).
+fn tag_value<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}>");
+    let start = text.find(&open)? + open.len();
+    let rest = &text[start..];
+    let end = rest.find('<').unwrap_or(rest.len());
+
+    Some(rest[..end].trim())
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Reads the transactions of a bank or credit card statement in the OFX or QFX format.
+pub fn read_ofx(ofx_path: &Path) -> Result<OfxStatement, Error> {
+    let content = std::fs::read(ofx_path).map_err(Error::io(ofx_path))?;
+    // the SGML flavour is often encoded in Windows-1252, which only matters for the descriptions
+    let content = String::from_utf8_lossy(&content);
+
+    parse_ofx(&content, ofx_path)
+}
+
+/// Parses the content of the statement, `ofx_path` only locates the errors.
+fn parse_ofx(content: &str, ofx_path: &Path) -> Result<OfxStatement, Error> {
+    let kind = if content.contains("<CCSTMTRS>") {
+        OfxAccountKind::CreditCard
+    } else if content.contains("<STMTRS>") {
+        OfxAccountKind::Bank
+    } else {
+        return Err(Error::invalid(format!(
+            "{} has neither a bank nor a credit card statement",
+            ofx_path.to_string_lossy()
+        )));
+    };
+
+    let account_number = tag_value(content, "ACCTID")
+        .filter(|value| !value.is_empty())
+        .map(unescape);
+
+    const OPEN: &str = "<STMTTRN>";
+    let mut transactions = vec![];
+    let mut offset = 0;
+
+    while let Some(index) = content[offset..].find(OPEN) {
+        let start = offset + index;
+        let line = content[..start].matches('\n').count() as u64 + 1;
+
+        let rest = &content[start + OPEN.len()..];
+        let end = rest
+            .find("</STMTTRN>")
+            .or_else(|| rest.find(OPEN))
+            .unwrap_or(rest.len());
+        let block = &rest[..end];
+        offset = start + OPEN.len() + end;
+
+        let invalid = |message: String| Error::invalid(message).at(ofx_path, line);
+        let required = |tag: &str| {
+            tag_value(block, tag)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| invalid(format!("the transaction has no <{tag}>")))
+        };
+
+        let fitid = unescape(required("FITID")?);
+
+        // e.g. 20240131, 20240131120000 or 20240131120000.000[-5:EST]
+        let posted = required("DTPOSTED")?;
+        let date = posted
+            .get(..8)
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+            .ok_or_else(|| invalid(format!("{posted:?} is not a date")))?;
+
+        // some banks use a decimal comma
+        let amount = required("TRNAMT")?;
+        let amount = amount
+            .replace(',', ".")
+            .parse()
+            .map_err(|_| invalid(format!("{amount:?} is not an amount")))?;
+
+        transactions.push(OfxTransaction {
+            line,
+            fitid,
+            date,
+            amount,
+            transaction_type: tag_value(block, "TRNTYPE").unwrap_or_default().to_string(),
+            name: unescape(tag_value(block, "NAME").unwrap_or_default()),
+            memo: unescape(tag_value(block, "MEMO").unwrap_or_default()),
+        });
+    }
+
+    transactions.sort_by(|a, b| a.date.cmp(&b.date).then(a.line.cmp(&b.line)));
+
+    Ok(OfxStatement {
+        kind,
+        account_number,
+        transactions,
+    })
+}
+
+impl Transaction<'_> {
+    /// Drafts a transaction for every statement transaction that wasn't imported before, between the account of the
+    /// statement and the counter account, unless a categorization rule assigns an expense account. The FITIDs are
+    /// recorded, so the drafts have to be reviewed and upserted (e.g. `upsert transaction`) before the next import.
+    /// `reimport` drafts the recorded FITIDs again, e.g. when the review CSV was discarded.
+    pub async fn import_ofx(
+        &mut self,
+        ofx_path: &Path,
+        account_key: &str,
+        counter_account_key: &str,
+        reimport: bool,
+    ) -> Result<OfxImport, Error> {
+        println!("importing statement from {}", ofx_path.to_string_lossy());
+
+        let statement = read_ofx(ofx_path)?;
+
+        // the statement of a bank account goes to a cash account, the one of a credit card to a card
+        let query = match statement.kind {
+            OfxAccountKind::Bank => {
+                "SELECT account_id FROM CashAccountEntry INNER JOIN Account USING (account_id) WHERE account_key = ?"
+            }
+            OfxAccountKind::CreditCard => {
+                "SELECT account_id FROM CreditCardEntry INNER JOIN Account USING (account_id) WHERE account_key = ?"
+            }
+        };
+        let account_id: i64 = sqlx::query(query)
+            .bind(account_key)
+            .fetch_optional(&mut *self.0)
+            .await?
+            .map(|row| row.get(0))
+            .ok_or_else(|| {
+                let key = match statement.kind {
+                    OfxAccountKind::Bank => "CashAccountEntry.account_key",
+                    OfxAccountKind::CreditCard => "CreditCardEntry.account_key",
+                };
+
+                Error::UnknownKey {
+                    path: Some(ofx_path.to_path_buf()),
+                    line: None,
+                    key: key.into(),
+                    record: account_key.to_string(),
+                }
+            })?;
+
+        let is_counter_account = sqlx::query("SELECT 1 FROM Account WHERE account_key = ?")
+            .bind(counter_account_key)
+            .fetch_optional(&mut *self.0)
+            .await?
+            .is_some();
+        if !is_counter_account {
+            return Err(Error::UnknownKey {
+                path: None,
+                line: None,
+                key: "Account.account_key".into(),
+                record: counter_account_key.to_string(),
+            });
+        }
+
+        // the FITIDs are still unique within the statement when they are drafted again
+        let mut imported: HashSet<String> = if reimport {
+            HashSet::new()
+        } else {
+            sqlx::query("SELECT fitid FROM ImportedStatementTransaction WHERE account_id = ?")
+                .bind(account_id)
+                .fetch_all(&mut *self.0)
+                .await?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect()
+        };
+
+        let rules = self.get_rules().await?;
+
+        let mut transaction_id = self.get_next_transaction_id().await?;
+        let mut entries = vec![];
+        let mut stores = vec![];
+        let mut num_duplicates = 0;
+
+        for ofx_transaction in statement.transactions {
+            if !imported.insert(ofx_transaction.fitid.clone()) {
+                num_duplicates += 1;
+                continue;
+            }
+
+            if ofx_transaction.amount.is_zero() {
+                continue;
+            }
+
+            let date = start_of_day(&ofx_transaction.date).timestamp();
+            let description = ofx_transaction.description();
+            let amount = ofx_transaction.amount.abs();
+
+            let rule = rules.find(&description, amount, Some(account_key));
+            let counter_account_key = rule
+                .and_then(|rule| rule.expense_account_key.as_deref())
+                .unwrap_or(counter_account_key);
+            if let Some(store_key) = rule.and_then(|rule| rule.record.store_key.clone()) {
+                stores.push(TransactionStore {
+                    transaction_id,
+                    store_key,
+                });
+            }
+
+            // money coming in (a deposit, or a payment of the card) is a debit of the statement account
+            let is_inflow = !ofx_transaction.amount.is_negative();
+            let entry = |item_id: i64, account_key: &str, is_debit: bool| FinancialEntry {
+                transaction_id,
+                item_id,
+                date,
+                account_key: account_key.to_string(),
+                unit: Units::from_raw(Units::SCALE),
+                debit: is_debit.then_some(amount.into()),
+                credit: (!is_debit).then_some(amount.into()),
+                description: description.clone(),
+            };
+            entries.push(entry(1, account_key, is_inflow));
+            entries.push(entry(2, counter_account_key, !is_inflow));
+
+            sqlx::query!(
+                r#"
+INSERT INTO
+    ImportedStatementTransaction (account_id, fitid, transaction_id)
+VALUES
+    (?, ?, ?) ON CONFLICT(account_id, fitid) DO
+UPDATE
+SET
+    transaction_id = excluded.transaction_id
+"#,
+                account_id,
+                ofx_transaction.fitid,
+                transaction_id
+            )
+            .execute(&mut *self.0)
+            .await?;
+
+            transaction_id += 1;
+        }
+
+        Ok(OfxImport {
+            entries,
+            stores,
+            num_duplicates,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<OfxStatement, Error> {
+        parse_ofx(content, Path::new("statement.ofx"))
+    }
+
+    #[test]
+    fn sgml_without_closing_tags() {
+        let statement = parse(
+            "OFXHEADER:100
+DATA:OFXSGML
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<BANKACCTFROM><ACCTID>****1234</BANKACCTFROM>
+<BANKTRANLIST>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20240205120000.000[-5:EST]
+<TRNAMT>-12.34
+<FITID>2
+<NAME>GROCER &amp; CO
+<MEMO>POS PURCHASE
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20240131
+<TRNAMT>1000.00
+<FITID>1
+<NAME>PAYROLL
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+",
+        )
+        .unwrap();
+
+        assert_eq!(statement.kind, OfxAccountKind::Bank);
+        assert_eq!(statement.account_number.as_deref(), Some("****1234"));
+
+        // sorted by date
+        let [payroll, purchase] = statement.transactions.as_slice() else {
+            panic!("expected 2 transactions, got {:?}", statement.transactions);
+        };
+
+        assert_eq!(payroll.fitid, "1");
+        assert_eq!(payroll.date, NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+        assert_eq!(payroll.amount, Money::from_cents(100000));
+        assert_eq!(payroll.description(), "PAYROLL");
+
+        assert_eq!(purchase.fitid, "2");
+        assert_eq!(purchase.line, 8);
+        assert_eq!(purchase.date, NaiveDate::from_ymd_opt(2024, 2, 5).unwrap());
+        assert_eq!(purchase.amount, Money::from_cents(-1234));
+        assert_eq!(purchase.transaction_type, "DEBIT");
+        assert_eq!(purchase.description(), "GROCER & CO - POS PURCHASE");
+    }
+
+    #[test]
+    fn xml() {
+        let statement = parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<?OFX OFXHEADER="200" VERSION="220"?>
+<OFX>
+  <CREDITCARDMSGSRSV1>
+    <CCSTMTTRNRS>
+      <CCSTMTRS>
+        <CCACCTFROM><ACCTID>5555</ACCTID></CCACCTFROM>
+        <BANKTRANLIST>
+          <STMTTRN>
+            <TRNTYPE>DEBIT</TRNTYPE>
+            <DTPOSTED>20240301</DTPOSTED>
+            <TRNAMT>-45.67</TRNAMT>
+            <FITID>abc</FITID>
+            <MEMO>COFFEE</MEMO>
+          </STMTTRN>
+        </BANKTRANLIST>
+      </CCSTMTRS>
+    </CCSTMTTRNRS>
+  </CREDITCARDMSGSRSV1>
+</OFX>
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(statement.kind, OfxAccountKind::CreditCard);
+        assert_eq!(statement.account_number.as_deref(), Some("5555"));
+
+        let [coffee] = statement.transactions.as_slice() else {
+            panic!("expected 1 transaction, got {:?}", statement.transactions);
+        };
+
+        assert_eq!(coffee.fitid, "abc");
+        assert_eq!(coffee.date, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(coffee.amount, Money::from_cents(-4567));
+        assert_eq!(coffee.description(), "COFFEE");
+    }
+
+    #[test]
+    fn decimal_comma() {
+        let statement = parse(
+            "<OFX><STMTRS>
+<STMTTRN><DTPOSTED>20240101<TRNAMT>-1234,5<FITID>1
+</STMTRS></OFX>",
+        )
+        .unwrap();
+
+        assert_eq!(statement.transactions[0].amount, Money::from_cents(-123450));
+    }
+
+    #[test]
+    fn missing_fitid() {
+        let error = parse(
+            "<OFX><STMTRS>
+<STMTTRN><DTPOSTED>20240101<TRNAMT>1.00<FITID>1
+<STMTTRN><DTPOSTED>20240102<TRNAMT>2.00<NAME>NO ID
+</STMTRS></OFX>",
+        )
+        .unwrap_err();
+
+        match error {
+            Error::Invalid { line, message, .. } => {
+                assert_eq!(line, Some(3));
+                assert_eq!(message, "the transaction has no <FITID>");
+            }
+            error => panic!("unexpected error {error:?}"),
+        }
+    }
+
+    #[test]
+    fn neither_bank_nor_credit_card() {
+        assert!(parse("<OFX><INVSTMTRS></INVSTMTRS></OFX>").is_err());
+    }
+}
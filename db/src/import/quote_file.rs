@@ -0,0 +1,207 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use chrono::NaiveDate;
+use common::{start_of_day, SymbolList, EXCHANGES};
+use sqlx::Row;
+
+use crate::{Error, Price, Transaction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteFileFormat {
+    /// Date,Open,High,Low,Close,Adj Close,Volume of a single symbol
+    Yahoo,
+    /// Either Date,Open,High,Low,Close,Volume of a single symbol, or the bulk <TICKER>,<PER>,<DATE>,... layout
+    Stooq,
+    /// ticker,date,close
+    Simple,
+}
+
+pub struct PriceImport {
+    pub format: QuoteFileFormat,
+    pub num_imported: usize,
+    /// Symbols in the file that don't map to any security.
+    pub unknown_symbols: BTreeSet<String>,
+}
+
+fn parse_quote_date(date: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%Y%m%d", "%m/%d/%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date.trim(), format).ok())
+}
+
+/// Maps a symbol of a quote provider (e.g. XEQT.TO, VT.US, BRK-B) to a ticker in `SECURITY`.
+fn resolve_symbol(symbol: &str, securities: &SymbolList) -> Option<String> {
+    let symbol = symbol.trim().to_uppercase();
+
+    if securities.values().any(|tickers| tickers.contains(&symbol)) {
+        return Some(symbol);
+    }
+
+    let is_listed = |exchange_key: &str, ticker: &str| {
+        securities
+            .get(exchange_key)
+            .is_some_and(|tickers| tickers.contains(ticker))
+    };
+
+    EXCHANGES.iter().find_map(|exchange| {
+        [exchange.yahoo_suffix, exchange.stooq_suffix]
+            .into_iter()
+            .flatten()
+            .find_map(|suffix| {
+                let ticker = symbol.strip_suffix(suffix)?;
+
+                // share classes are written with a dash on Yahoo Finance, e.g. BRK-B
+                [ticker.to_string(), ticker.replace('-', ".")]
+                    .into_iter()
+                    .find(|ticker| is_listed(exchange.short, ticker))
+            })
+    })
+}
+
+impl Transaction<'_> {
+    async fn get_symbol_list(&mut self) -> sqlx::Result<SymbolList> {
+        let mut securities = SymbolList::new();
+
+        for record in sqlx::query(
+            r#"
+SELECT
+    exchange_key,
+    ticker
+FROM
+    SECURITY
+    INNER JOIN Exchange USING (exchange_id)
+"#,
+        )
+        .fetch_all(&mut *self.0)
+        .await?
+        {
+            securities
+                .entry(record.get(0))
+                .or_default()
+                .insert(record.get(1));
+        }
+
+        Ok(securities)
+    }
+
+    /// Records the closing prices of a downloaded quote file. Files without a ticker column hold
+    /// the quotes of a single symbol, which is either `symbol` or the file name (e.g. XEQT.TO.csv).
+    pub async fn import_security_price(
+        &mut self,
+        csv_path: &Path,
+        symbol: Option<&str>,
+    ) -> Result<PriceImport, Error> {
+        println!("importing prices from {}", csv_path.to_string_lossy());
+
+        let csv_error = |line: Option<u64>| {
+            move |source: csv::Error| Error::Csv {
+                path: csv_path.to_path_buf(),
+                line,
+                source,
+            }
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_path(csv_path)
+            .map_err(csv_error(None))?;
+
+        let headers: Vec<_> = reader
+            .headers()
+            .map_err(csv_error(Some(1)))?
+            .iter()
+            .map(|header| header.trim().to_lowercase())
+            .collect();
+        let find = |name: &str| headers.iter().position(|header| header == name);
+
+        let (format, ticker_index, date_index, close_index) =
+            match (find("<ticker>"), find("<date>"), find("<close>")) {
+                (Some(ticker), Some(date), Some(close)) => {
+                    (QuoteFileFormat::Stooq, Some(ticker), date, close)
+                }
+                _ => match (find("ticker"), find("date"), find("close")) {
+                    (Some(ticker), Some(date), Some(close)) => {
+                        (QuoteFileFormat::Simple, Some(ticker), date, close)
+                    }
+                    (None, Some(date), Some(close)) if find("adj close").is_some() => {
+                        (QuoteFileFormat::Yahoo, None, date, close)
+                    }
+                    (None, Some(date), Some(close)) => (QuoteFileFormat::Stooq, None, date, close),
+                    _ => {
+                        return Err(Error::invalid(
+                            "unrecognized quote file, expecting a Yahoo Finance, Stooq or ticker,date,close CSV",
+                        )
+                        .at(csv_path, 1))
+                    }
+                },
+            };
+
+        let file_symbol = match symbol {
+            Some(symbol) => symbol.to_string(),
+            None => csv_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+
+        let securities = self.get_symbol_list().await?;
+        let mut resolved = BTreeMap::new();
+        let mut unknown_symbols = BTreeSet::new();
+        let mut num_imported = 0;
+
+        for record in reader.records() {
+            let record = record.map_err(csv_error(None))?;
+            let line = record
+                .position()
+                .map(|position| position.line())
+                .unwrap_or(0);
+
+            let symbol = match ticker_index {
+                Some(index) => record.get(index).unwrap_or_default().trim().to_string(),
+                None => file_symbol.clone(),
+            };
+
+            let ticker = resolved
+                .entry(symbol.clone())
+                .or_insert_with(|| resolve_symbol(&symbol, &securities));
+
+            let ticker = match ticker {
+                Some(ticker) => ticker.clone(),
+                None => {
+                    unknown_symbols.insert(symbol);
+                    continue;
+                }
+            };
+
+            // Yahoo Finance writes "null" on days without trading
+            let close = match record.get(close_index).map(str::trim) {
+                Some(close) if !close.is_empty() && close != "null" => Price::parse_rounded(close)
+                    .map_err(|err| {
+                        Error::invalid(format!("{err}, record: {record:?}")).at(csv_path, line)
+                    })?,
+                _ => continue,
+            };
+
+            let date = record
+                .get(date_index)
+                .and_then(parse_quote_date)
+                .ok_or_else(|| {
+                    Error::invalid(format!("invalid date, record: {record:?}")).at(csv_path, line)
+                })?;
+
+            self.set_security_price(&ticker, &start_of_day(&date), close)
+                .await
+                .map_err(|err| err.at(csv_path, line))?;
+            num_imported += 1;
+        }
+
+        Ok(PriceImport {
+            format,
+            num_imported,
+            unknown_symbols,
+        })
+    }
+}
@@ -1,12 +1,34 @@
+mod add_transaction;
 mod check;
+mod close_period;
+mod csv_file;
 mod db;
+mod diff;
+mod error;
+mod import;
+mod money;
 mod one_shot;
+mod reconcile;
+mod recurring;
+mod rules;
+mod snapshot;
 mod transaction;
 mod update_price;
 mod upsert;
+mod validate;
+mod valuation_date;
 
+pub use add_transaction::AddedTransaction;
 pub use check::*;
+pub use close_period::{ClosedPeriod, ClosingEntry};
+pub use csv_file::{append_csv, read_csv_into_map, stage_append_csv, write_csv, StagedCsv};
+pub use diff::*;
+pub use error::Error;
+pub use import::*;
+pub use money::{Money, ParseAmountError, Price, Units};
 pub use one_shot::*;
+pub use reconcile::{read_statement, LedgerLine, StatementLine, StatementMatch};
+pub use rules::{AppliedRule, Rule, Rules};
 use sqlx::SqliteConnection;
 pub use upsert::*;
 
@@ -19,5 +41,30 @@ pub struct Transaction<'c>(sqlx::Transaction<'c, sqlx::Sqlite>);
 pub type SqlQuery<'q> = sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>;
 
 pub trait Query {
-    fn query(&self) -> SqlQuery;
+    fn query(&self) -> SqlQuery<'_>;
+
+    /// The transaction_id and item_id of an entry, for the errors of the entries dated in a closed period.
+    fn entry_id(&self) -> Option<(i64, i64)> {
+        None
+    }
+}
+
+/// Deletes the record, used to prune the rows that are removed from the CSV file.
+pub trait Prune {
+    fn delete_query(&self) -> SqlQuery<'_>;
+}
+
+pub trait Export:
+    serde::Serialize + for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin
+{
+    /// Selects the records in the same shape as the CSV file they are upserted from, ordered by their `Id` so that
+    /// exporting again doesn't reorder the file.
+    const EXPORT_QUERY: &'static str;
+
+    /// Selects every record that the upsert can match, including the ones the export leaves out (e.g. the rows
+    /// created by other upserts), so that the dry-run doesn't show them as new.
+    const DIFF_QUERY: &'static str = Self::EXPORT_QUERY;
+
+    /// Copies the fields that the CSV file leaves out, and that the upsert keeps, from the existing record.
+    fn keep_existing(&mut self, _existing: &Self) {}
 }
@@ -0,0 +1,587 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An amount of money in minor units (i.e. cents), which is how the ledger stores every amount.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct Money(i64);
+
+/// A number of units (e.g. shares) with 4 decimal places, stored as an integer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct Units(i64);
+
+/// The price of one unit with 4 decimal places, stored as an integer.
+/// This is what the debit and credit of an entry hold, e.g. a stock bought at $26.4567 per share.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct Price(i64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAmountError {
+    NotDecimal(String),
+    TooPrecise { input: String, decimal_places: u32 },
+}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotDecimal(input) => write!(f, "{input:?} is not a decimal number"),
+            Self::TooPrecise {
+                input,
+                decimal_places,
+            } => write!(f, "{input:?} has more than {decimal_places} decimal places"),
+        }
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+/// How the decimal places beyond the precision of the type are handled.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExtraDecimals {
+    /// Extra decimal places must be zeros, otherwise parsing would silently lose data.
+    Reject,
+    /// Rounded half away from zero, e.g. quotes often come with more precision than cents.
+    Round,
+}
+
+/// Parses a decimal number into an integer with the given number of decimal places.
+fn parse_fixed(
+    input: &str,
+    decimal_places: u32,
+    extra_decimals: ExtraDecimals,
+) -> Result<i64, ParseAmountError> {
+    let error = || ParseAmountError::NotDecimal(input.to_string());
+
+    let trimmed = input.trim();
+    let (is_negative, digits) = match trimmed.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(error());
+    }
+
+    let (kept, dropped) = fraction.split_at(fraction.len().min(decimal_places as usize));
+    if extra_decimals == ExtraDecimals::Reject && dropped.chars().any(|c| c != '0') {
+        return Err(ParseAmountError::TooPrecise {
+            input: input.to_string(),
+            decimal_places,
+        });
+    }
+
+    let mut value: i64 = 0;
+    for digit in whole.chars().chain(kept.chars()) {
+        value = value
+            .checked_mul(10)
+            .and_then(|value| value.checked_add(digit.to_digit(10).unwrap_or(0) as i64))
+            .ok_or_else(error)?;
+    }
+    for _ in kept.len()..decimal_places as usize {
+        value = value.checked_mul(10).ok_or_else(error)?;
+    }
+    if dropped.starts_with(['5', '6', '7', '8', '9']) {
+        value = value.checked_add(1).ok_or_else(error)?;
+    }
+
+    Ok(if is_negative { -value } else { value })
+}
+
+/// Formats the integer with the given number of decimal places, the trailing zeros are removed when `trim` is set.
+fn format_fixed(value: i64, decimal_places: u32, trim: bool) -> String {
+    let scale = 10_u64.pow(decimal_places);
+    let sign = if value < 0 { "-" } else { "" };
+    let whole = value.unsigned_abs() / scale;
+    let fraction = value.unsigned_abs() % scale;
+
+    let fraction = format!("{:0width$}", fraction, width = decimal_places as usize);
+    let fraction = if trim {
+        fraction.trim_end_matches('0')
+    } else {
+        &fraction
+    };
+
+    if fraction.is_empty() {
+        format!("{sign}{whole}")
+    } else {
+        format!("{sign}{whole}.{fraction}")
+    }
+}
+
+impl Money {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Self(cents)
+    }
+
+    pub const fn cents(self) -> i64 {
+        self.0
+    }
+
+    /// Only for ratios and for display, amounts are never added up as floats.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl Units {
+    pub const ZERO: Self = Self(0);
+    /// The stored integer of one unit.
+    pub const SCALE: i64 = 10_000;
+
+    pub const fn from_raw(raw: i64) -> Self {
+        Self(raw)
+    }
+
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::SCALE as f64
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// The amount of `units` at `price` per unit, rounded half away from zero to the cent.
+    /// This is the same rounding as the `FinancialEntryAmount` view.
+    pub fn times(self, price: Price) -> Money {
+        // units and prices both have 4 decimal places, cents have 2
+        let divisor = (Self::SCALE * Price::SCALE / 100) as i128;
+        let product = self.0 as i128 * price.0 as i128;
+        let half = divisor / 2;
+        let rounded = if product < 0 {
+            (product - half) / divisor
+        } else {
+            (product + half) / divisor
+        };
+
+        Money(rounded as i64)
+    }
+}
+
+impl Price {
+    pub const ZERO: Self = Self(0);
+    /// The stored integer of one dollar.
+    pub const SCALE: i64 = 10_000;
+
+    pub const fn from_raw(raw: i64) -> Self {
+        Self(raw)
+    }
+
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Parses a price that may have more than 4 decimal places and rounds it half away from zero.
+    /// Only for market data (e.g. quotes), prices that are posted to the ledger are never rounded.
+    pub fn parse_rounded(s: &str) -> Result<Self, ParseAmountError> {
+        parse_fixed(s, 4, ExtraDecimals::Round).map(Self)
+    }
+}
+
+/// An amount is the price of a single unit.
+impl From<Money> for Price {
+    fn from(money: Money) -> Self {
+        Self(money.0 * (Self::SCALE / 100))
+    }
+}
+
+impl FromStr for Money {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_fixed(s, 2, ExtraDecimals::Reject).map(Self)
+    }
+}
+
+impl FromStr for Units {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_fixed(s, 4, ExtraDecimals::Reject).map(Self)
+    }
+}
+
+impl FromStr for Price {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_fixed(s, 4, ExtraDecimals::Reject).map(Self)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the precision is ignored, amounts always have 2 decimal places
+        f.write_str(&format_fixed(self.0, 2, false))
+    }
+}
+
+/// Shows the units without the trailing zeros, unless a precision is given (e.g. `{:.4}`).
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = match f.precision() {
+            Some(precision) if precision >= 4 => format!(
+                "{}{}",
+                format_fixed(self.0, 4, false),
+                "0".repeat(precision - 4)
+            ),
+            Some(_) | None => format_fixed(self.0, 4, true),
+        };
+
+        f.write_str(&formatted)
+    }
+}
+
+/// Shows the price with 2 decimal places like an amount, or up to 4 when the cents aren't enough.
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = format_fixed(self.0, 4, false);
+        let (whole, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+
+        write!(f, "{whole}.{:0<2}", fraction.trim_end_matches('0'))
+    }
+}
+
+impl Add for Money {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self(iter.map(|money| money.0).sum())
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        Self(iter.map(|money| money.0).sum())
+    }
+}
+
+impl Add for Units {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Units {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Units {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl AddAssign for Units {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sum for Units {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self(iter.map(|units| units.0).sum())
+    }
+}
+
+/// The CSV files have the amounts as decimal numbers, e.g. 12.34
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Units {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_fixed(self.0, 2, true))
+    }
+}
+
+impl<'de> Deserialize<'de> for Price {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Units {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_fixed(self.0, 4, true))
+    }
+}
+
+impl Serialize for Price {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_fixed(self.0, 4, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_money() {
+        assert_eq!("12.34".parse(), Ok(Money::from_cents(1234)));
+        assert_eq!("12.3".parse(), Ok(Money::from_cents(1230)));
+        assert_eq!("12".parse(), Ok(Money::from_cents(1200)));
+        assert_eq!(".5".parse(), Ok(Money::from_cents(50)));
+        assert_eq!(" -0.07 ".parse(), Ok(Money::from_cents(-7)));
+        assert_eq!("+1.00".parse(), Ok(Money::from_cents(100)));
+        assert_eq!("12.3400".parse(), Ok(Money::from_cents(1234)));
+    }
+
+    #[test]
+    fn parse_money_rejects_extra_decimals() {
+        assert_eq!(
+            "12.345".parse::<Money>(),
+            Err(ParseAmountError::TooPrecise {
+                input: "12.345".into(),
+                decimal_places: 2
+            })
+        );
+        assert!("26.4567".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn parse_money_rejects_non_decimals() {
+        for input in [
+            "",
+            ".",
+            "-",
+            "1,00",
+            "1.2.3",
+            "$1",
+            "1e3",
+            "abc",
+            "99999999999999999999",
+        ] {
+            assert_eq!(
+                input.parse::<Money>(),
+                Err(ParseAmountError::NotDecimal(input.into())),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_price_rounded() {
+        assert_eq!(Price::parse_rounded("0.0425"), Ok(Price::from_raw(425)));
+        assert_eq!(
+            Price::parse_rounded("26.456699371337891"),
+            Ok(Price::from_raw(264_567))
+        );
+        assert_eq!(
+            Price::parse_rounded("26.44445"),
+            Ok(Price::from_raw(264_445))
+        );
+        assert_eq!(
+            Price::parse_rounded("26.44444"),
+            Ok(Price::from_raw(264_444))
+        );
+        assert_eq!(Price::parse_rounded("3"), Ok(Price::from_raw(30_000)));
+    }
+
+    #[test]
+    fn parse_units_and_price() {
+        assert_eq!("37.1234".parse(), Ok(Units::from_raw(371_234)));
+        assert_eq!("1".parse(), Ok(Units::from_raw(Units::SCALE)));
+        assert!("0.00001".parse::<Units>().is_err());
+        assert_eq!("0.00010".parse(), Ok(Units::from_raw(1)));
+
+        assert_eq!("26.4567".parse(), Ok(Price::from_raw(264_567)));
+        assert_eq!("982.16".parse(), Ok(Price::from_raw(9_821_600)));
+        assert!("26.45671".parse::<Price>().is_err());
+    }
+
+    #[test]
+    fn display_money() {
+        assert_eq!(Money::from_cents(1234).to_string(), "12.34");
+        assert_eq!(Money::from_cents(1200).to_string(), "12.00");
+        assert_eq!(Money::from_cents(5).to_string(), "0.05");
+        assert_eq!(Money::from_cents(-5).to_string(), "-0.05");
+        assert_eq!(Money::from_cents(-123_456).to_string(), "-1234.56");
+        assert_eq!(format!("{:.2}", Money::from_cents(5)), "0.05");
+    }
+
+    #[test]
+    fn display_units() {
+        assert_eq!(Units::from_raw(371_234).to_string(), "37.1234");
+        assert_eq!(Units::from_raw(15_000).to_string(), "1.5");
+        assert_eq!(Units::from_raw(20_000).to_string(), "2");
+        assert_eq!(Units::from_raw(-5).to_string(), "-0.0005");
+        assert_eq!(format!("{:.4}", Units::from_raw(15_000)), "1.5000");
+        assert_eq!(format!("{:.6}", Units::from_raw(15_000)), "1.500000");
+    }
+
+    #[test]
+    fn display_price() {
+        assert_eq!(Price::from_raw(264_567).to_string(), "26.4567");
+        assert_eq!(Price::from_raw(264_500).to_string(), "26.45");
+        assert_eq!(Price::from_raw(264_560).to_string(), "26.456");
+        assert_eq!(Price::from_raw(260_000).to_string(), "26.00");
+        assert_eq!(Price::from_raw(0).to_string(), "0.00");
+    }
+
+    #[test]
+    fn serialize_round_trips() {
+        fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(vec![]);
+            writer.serialize((value,)).unwrap();
+            let written = writer.into_inner().unwrap();
+
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(written.as_slice());
+            let (value,): (T,) = reader.deserialize().next().unwrap().unwrap();
+            value
+        }
+
+        for cents in [0, 5, 1230, -1234, 98_216] {
+            let money = Money::from_cents(cents);
+            assert_eq!(round_trip(&money), money);
+        }
+        for raw in [1, 371_234, 10_000] {
+            let units = Units::from_raw(raw);
+            assert_eq!(round_trip(&units), units);
+        }
+        for raw in [264_567, 9_821_600, 1] {
+            let price = Price::from_raw(raw);
+            assert_eq!(round_trip(&price), price);
+        }
+    }
+
+    #[test]
+    fn price_from_money() {
+        assert_eq!(
+            Price::from(Money::from_cents(98_216)),
+            "982.16".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn units_times_price() {
+        let units: Units = "37.1234".parse().unwrap();
+        let price: Price = "26.4567".parse().unwrap();
+        // 982.1586... is rounded once, to the cent
+        assert_eq!(units.times(price), Money::from_cents(98_216));
+
+        let one = Units::from_raw(Units::SCALE);
+        assert_eq!(
+            one.times("982.16".parse().unwrap()),
+            Money::from_cents(98_216)
+        );
+
+        // half a cent is rounded away from zero on both sides
+        let half: Units = "0.5".parse().unwrap();
+        assert_eq!(half.times("0.01".parse().unwrap()), Money::from_cents(1));
+        assert_eq!(
+            (-half).times("0.01".parse().unwrap()),
+            Money::from_cents(-1)
+        );
+        assert_eq!(half.times("0.0099".parse().unwrap()), Money::ZERO);
+    }
+
+    #[test]
+    fn money_arithmetic() {
+        let amounts = [
+            Money::from_cents(10),
+            Money::from_cents(-25),
+            Money::from_cents(7),
+        ];
+        assert_eq!(amounts.iter().sum::<Money>(), Money::from_cents(-8));
+        assert_eq!(-Money::from_cents(3), Money::from_cents(-3));
+        assert_eq!(Money::from_cents(-3).abs(), Money::from_cents(3));
+    }
+}
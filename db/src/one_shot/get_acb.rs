@@ -4,19 +4,21 @@ use chrono::{DateTime, Local};
 use serde::Deserialize;
 use sqlx::{sqlite::SqliteRow, Row};
 
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction, Units};
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Acb {
     pub first_name: String,
     pub last_name: String,
     pub ticker: String,
-    pub acc_units: f64,
-    pub avg_price: f64,
-    pub acb: f64,
-    pub total_distribution: f64,
-    pub total_capital_gl: f64,
+    pub acc_units: Units,
+    pub avg_price: Money,
+    pub acb: Money,
+    pub total_distribution: Money,
+    pub total_capital_gl: Money,
     pub person_id: i64,
+    /// The superficial losses that were denied, i.e. left out of `total_capital_gl` and added to the ACB.
+    pub total_superficial_loss: Money,
 }
 
 impl From<SqliteRow> for Acb {
@@ -31,6 +33,7 @@ impl From<SqliteRow> for Acb {
             total_distribution: row.get(6),
             total_capital_gl: row.get(7),
             person_id: row.get(8),
+            total_superficial_loss: row.get(9),
         }
     }
 }
@@ -47,7 +50,8 @@ WITH CapitalGl AS (
         person_id,
         security_id,
         SUM(distribution) AS total_distribution,
-        SUM(capital_gl) AS total_capital_gl
+        SUM(capital_gl) AS total_capital_gl,
+        SUM(superficial_loss) AS total_superficial_loss
     FROM
         Acb
     WHERE
@@ -62,11 +66,15 @@ SELECT
     last_name,
     ticker,
     acc_units,
-    COALESCE(ROUND(acb / acc_units, 2), 0.0) AS avg_price,
-    COALESCE(acb, 0.0),
+    COALESCE(
+        CAST(ROUND(acb * 10000.0 / acc_units) AS INTEGER),
+        0
+    ) AS avg_price,
+    COALESCE(acb, 0),
     total_distribution,
     total_capital_gl,
-    person_id
+    person_id,
+    COALESCE(total_superficial_loss, 0)
 FROM
     Acb
     INNER JOIN SECURITY USING (security_id)
@@ -1,15 +1,15 @@
 use serde::Deserialize;
 use sqlx::{sqlite::SqliteRow, Row};
 
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction};
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct AssetRebalance {
     pub first_name: String,
     pub last_name: String,
     pub asset_class_name: String,
-    pub current_rebalance_amount: f64,
-    pub potential_rebalance_amount: f64,
+    pub current_rebalance_amount: Money,
+    pub potential_rebalance_amount: Money,
 }
 
 impl From<SqliteRow> for AssetRebalance {
@@ -5,14 +5,14 @@ use common::all_time_until_now;
 use serde::Deserialize;
 use sqlx::Row;
 
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction};
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct BalanceRecord {
     pub name: String,
     pub account_type: String,
     pub account_name: String,
-    pub balance: f64,
+    pub balance: Money,
 }
 
 impl Transaction<'_> {
@@ -34,23 +34,14 @@ WITH BaseData AS (
         account_kind_id,
         account_type_id,
         account_id,
-        ROUND(
-            SUM(
-                ROUND(
-                    unit * ROUND(
-                        CASE
-                            WHEN account_kind IN ('ASSET', 'EXPENSE') THEN COALESCE(debit, 0) - COALESCE(credit, 0)
-                            ELSE COALESCE(credit, 0) - COALESCE(debit, 0)
-                        END,
-                        2
-                    ),
-                    2
-                )
-            ),
-            2
+        SUM(
+            CASE
+                WHEN account_kind IN ('ASSET', 'EXPENSE') THEN debit_amount - credit_amount
+                ELSE credit_amount - debit_amount
+            END
         ) AS balance
     FROM
-        FinancialEntry
+        FinancialEntryAmount
         INNER JOIN Account USING (account_id)
         INNER JOIN AccountSubtype USING (account_subtype_id)
         INNER JOIN AccountType USING (account_type_id)
@@ -60,6 +51,16 @@ WITH BaseData AS (
         account_kind = ?
         AND date BETWEEN ?
         AND ?
+        -- the closing entries would zero out the revenue and expense of closed periods
+        AND (
+            account_kind NOT IN ('REVENUE', 'EXPENSE')
+            OR transaction_id NOT IN (
+                SELECT
+                    transaction_id
+                FROM
+                    ClosingTransaction
+            )
+        )
     GROUP BY
         person_id,
         account_kind_id,
@@ -70,7 +71,7 @@ SELECT
     first_name || ' ' || last_name as name,
     account_type,
     account_name,
-    balance AS "balance!:f64"
+    balance AS "balance!:Money"
 FROM
     BaseData
     INNER JOIN Account USING (account_id)
@@ -84,7 +85,8 @@ ORDER BY
     name,
     account_kind,
     account_type
-"#)
+"#,
+        )
         .bind(kind)
         .bind(start_ts)
         .bind(end_ts)
@@ -96,11 +98,19 @@ ORDER BY
             account_type: row.get(1),
             account_name: row.get(2),
             balance: row.get(3),
-        }).collect();
+        })
+        .collect();
 
         Ok(rows)
     }
 
+    pub async fn get_asset_balance(
+        &mut self,
+        range: Option<Range<DateTime<Local>>>,
+    ) -> SqlResult<Vec<BalanceRecord>> {
+        self.get_balance("ASSET", range).await
+    }
+
     pub async fn get_revenue_balance(
         &mut self,
         range: Option<Range<DateTime<Local>>>,
@@ -132,23 +142,14 @@ WITH BaseData AS (
         account_kind_id,
         account_type_id,
         account_id,
-        ROUND(
-            SUM(
-                ROUND(
-                    unit * ROUND(
-                        CASE
-                            WHEN account_kind IN ('ASSET', 'EXPENSE') THEN COALESCE(debit, 0) - COALESCE(credit, 0)
-                            ELSE COALESCE(credit, 0) - COALESCE(debit, 0)
-                        END,
-                        2
-                    ),
-                    2
-                )
-            ),
-            2
+        SUM(
+            CASE
+                WHEN account_kind IN ('ASSET', 'EXPENSE') THEN debit_amount - credit_amount
+                ELSE credit_amount - debit_amount
+            END
         ) AS balance
     FROM
-        FinancialEntry
+        FinancialEntryAmount
         INNER JOIN Account USING (account_id)
         INNER JOIN AccountSubtype USING (account_subtype_id)
         INNER JOIN AccountType USING (account_type_id)
@@ -171,7 +172,7 @@ SELECT
     first_name || ' ' || last_name AS name,
     account_type,
     account_name,
-    balance AS "balance!:f64"
+    balance AS "balance!:Money"
 FROM
     BaseData
     INNER JOIN Account USING (account_id)
@@ -183,7 +184,8 @@ ORDER BY
     name,
     account_kind,
     account_type;
-"#)
+"#,
+        )
         .bind(start_ts)
         .bind(end_ts)
         .fetch_all(&mut *self.0)
@@ -194,7 +196,8 @@ ORDER BY
             account_type: row.get(1),
             account_name: row.get(2),
             balance: row.get(3),
-        }).collect();
+        })
+        .collect();
 
         Ok(rows)
     }
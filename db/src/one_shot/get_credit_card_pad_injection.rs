@@ -1,12 +1,12 @@
 use serde::Serialize;
 
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction};
 
 #[derive(Serialize, Debug)]
 pub struct CreditCardPadInjection {
     pub name: String,
     pub account_name: String,
-    pub min_injection: f64,
+    pub min_injection: Money,
 }
 
 impl Transaction<'_> {
@@ -19,7 +19,7 @@ impl Transaction<'_> {
 SELECT
     name,
     account_name,
-    min_injection AS "min_injection!:f64"
+    min_injection AS "min_injection!:Money"
 FROM
     RequiredPadInjection
 "#
@@ -1,7 +1,7 @@
 use serde::Serialize;
 use sqlx::types::chrono::{DateTime, Local};
 
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction};
 
 #[derive(Serialize, Debug)]
 pub struct CurrentCredit {
@@ -9,7 +9,7 @@ pub struct CurrentCredit {
     pub last_name: String,
     pub account_name: String,
     pub last_payment_date: Option<DateTime<Local>>,
-    pub balance: f64,
+    pub balance: Money,
     pub has_pad: bool,
 }
 
@@ -23,7 +23,7 @@ SELECT
     last_name,
     account_name AS "account_name",
     last_payment_date AS "last_payment_date:DateTime<Local>",
-    balance AS "balance!:f64",
+    balance AS "balance!:Money",
     has_pad AS "has_pad:bool"
 FROM
     CreditCardView;
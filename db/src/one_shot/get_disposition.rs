@@ -0,0 +1,96 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Local};
+use common::{excel_date_format, serialize_excel_date};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row};
+
+use crate::{Money, SqlResult, Transaction, Units};
+
+/// A sale of a security held outside of the registered accounts, in the shape of the Schedule 3 (capital gains), i.e.
+/// the gain or loss is the proceeds less the ACB and the outlays, except for the superficial loss that is denied.
+/// A deemed gain, from a return of capital that takes the ACB below zero, is a disposition without units.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Disposition {
+    pub name: String,
+    pub ticker: String,
+    #[serde(
+        deserialize_with = "excel_date_format",
+        serialize_with = "serialize_excel_date"
+    )]
+    /// The date of the sale's entries, i.e. the trade date, which also places the sale in its year and in the 30-day
+    /// window of the superficial loss. The Schedule 3 goes by the settlement date (T+1, or T+2 before May 27, 2024),
+    /// which the ledger doesn't record.
+    pub trade_date: i64,
+    pub units: Units,
+    pub proceeds: Money,
+    pub acb: Money,
+    /// The commission of the sale.
+    pub outlays: Money,
+    pub capital_gl: Money,
+    pub superficial_loss: Money,
+}
+
+impl From<SqliteRow> for Disposition {
+    fn from(row: SqliteRow) -> Self {
+        Self {
+            name: row.get(0),
+            ticker: row.get(1),
+            trade_date: row.get(2),
+            units: row.get(3),
+            proceeds: row.get(4),
+            acb: row.get(5),
+            outlays: row.get(6),
+            capital_gl: row.get(7),
+            superficial_loss: row.get(8),
+        }
+    }
+}
+
+impl Transaction<'_> {
+    pub async fn get_disposition(
+        &mut self,
+        range: Range<DateTime<Local>>,
+    ) -> SqlResult<Vec<Disposition>> {
+        let start_ts = range.start.timestamp();
+        let end_ts = range.end.timestamp();
+
+        // the ACB of the sold units is whatever the gain (before the denied loss) doesn't take from the net proceeds
+        let rows = sqlx::query(
+            r#"
+SELECT
+    first_name || ' ' || last_name,
+    ticker,
+    Acb.date,
+    - Acb.unit,
+    ABS(Acb.book_value) + commission + deemed_gain,
+    ABS(Acb.book_value) - (capital_gl - superficial_loss - deemed_gain),
+    commission,
+    capital_gl,
+    superficial_loss
+FROM
+    Acb
+    INNER JOIN AcbBaseData USING (transaction_id, person_id, security_id)
+    INNER JOIN SECURITY USING (security_id)
+    INNER JOIN Person USING (person_id)
+WHERE
+    (
+        Acb.unit < 0
+        OR deemed_gain > 0
+    )
+    AND Acb.date BETWEEN ?
+    AND ?
+ORDER BY
+    person_id,
+    Acb.date,
+    ticker
+"#,
+        )
+        .bind(start_ts)
+        .bind(end_ts)
+        .fetch_all(&mut *self.0)
+        .await?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
@@ -1,31 +1,31 @@
 use serde::Deserialize;
 
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, sqlx::FromRow)]
 pub struct EmergencyRebalance {
     pub first_name: String,
     pub last_name: String,
     pub account_name: String,
     pub currency: String,
     pub currency_symbol: String,
-    pub unallocated_fund: f64,
-    pub injection_needed: f64,
+    pub unallocated_fund: Money,
+    pub injection_needed: Money,
 }
 
 impl Transaction<'_> {
     pub async fn get_emergency_rebalance(&mut self) -> SqlResult<Vec<EmergencyRebalance>> {
-        let result = sqlx::query_as!(
-            EmergencyRebalance,
+        // CashView is a TEMP view (see valuation_date.sql), which the checked queries can't see
+        let result = sqlx::query_as::<_, EmergencyRebalance>(
             r#"
 SELECT
     first_name,
     last_name,
     account_name,
-    currency AS "currency!:String",
-    currency_symbol AS "currency_symbol!:String",
-    unallocated_fund AS "unallocated_fund!:f64",
-    injection_needed AS "injection_needed!:f64"
+    currency,
+    currency_symbol,
+    unallocated_fund,
+    injection_needed
 FROM
     CashView
     INNER JOIN CashAccountHolder USING (cash_account_holder_id)
@@ -42,7 +42,7 @@ ORDER BY
     first_name,
     last_name,
     account_name
-"#
+"#,
         )
         .fetch_all(&mut *self.0)
         .await?;
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::{SqlResult, Transaction};
+
+#[derive(Deserialize, Debug)]
+pub struct ExchangeRateRecord {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub date: DateTime<Local>,
+    pub rate: f64,
+}
+
+impl Transaction<'_> {
+    pub async fn get_exchange_rate_history(
+        &mut self,
+        base_currency: &str,
+        quote_currency: &str,
+        limit: u32,
+    ) -> SqlResult<Vec<ExchangeRateRecord>> {
+        let result = sqlx::query(
+            r#"
+SELECT
+    Base.currency,
+    Quote.currency,
+    date,
+    rate
+FROM
+    ExchangeRate
+    INNER JOIN Currency Base ON base_currency_id = Base.currency_id
+    INNER JOIN Currency Quote ON quote_currency_id = Quote.currency_id
+WHERE
+    Base.currency = ?
+    AND Quote.currency = ?
+ORDER BY
+    date DESC
+LIMIT
+    ?
+"#,
+        )
+        .bind(base_currency)
+        .bind(quote_currency)
+        .bind(limit)
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|record| ExchangeRateRecord {
+            base_currency: record.get(0),
+            quote_currency: record.get(1),
+            date: record.get(2),
+            rate: record.get(3),
+        })
+        .collect();
+
+        Ok(result)
+    }
+}
@@ -18,19 +18,9 @@ WITH BaseData AS (
     SELECT
         account_kind_id,
         account_type_id,
-        ROUND(
-            SUM(
-                ROUND(
-                    unit * (
-                        COALESCE(debit, 0) - COALESCE(credit, 0)
-                    ),
-                    2
-                )
-            ),
-            2
-        ) AS balance
+        SUM(debit_amount - credit_amount) AS balance
     FROM
-        FinancialEntry
+        FinancialEntryAmount
         INNER JOIN Account USING (account_id)
         INNER JOIN AccountSubtype USING (account_subtype_id)
         INNER JOIN AccountType USING (account_type_id)
@@ -40,6 +30,12 @@ WITH BaseData AS (
         person_id IS NULL
         AND date > ?
         AND account_kind = 'EXPENSE'
+        AND transaction_id NOT IN (
+            SELECT
+                transaction_id
+            FROM
+                ClosingTransaction
+        )
     GROUP BY
         account_kind_id,
         account_type_id
@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Local};
+use sqlx::{sqlite::SqliteRow, Row};
+
+use crate::{Money, SqlResult, Transaction};
+
+/// The investment income of an account (and the security, for a brokerage account) in home currency, in the shape of
+/// the T3/T5 slips, i.e. the interest and the distributions, the part of them that comes from a foreign security or a
+/// foreign currency account, and the tax withheld on them.
+#[derive(Clone, Debug)]
+pub struct InvestmentIncome {
+    pub name: String,
+    pub institution_name: String,
+    pub account_name: String,
+    pub ticker: Option<String>,
+    pub tax_shelter_type: String,
+    pub interest: Money,
+    pub distribution: Money,
+    pub foreign_income: Money,
+    pub foreign_tax_withheld: Money,
+}
+
+impl InvestmentIncome {
+    pub fn is_registered(&self) -> bool {
+        self.tax_shelter_type != "NON-REGISTERED"
+    }
+}
+
+impl From<SqliteRow> for InvestmentIncome {
+    fn from(row: SqliteRow) -> Self {
+        Self {
+            name: row.get(0),
+            institution_name: row.get(1),
+            account_name: row.get(2),
+            ticker: row.get(3),
+            tax_shelter_type: row.get(4),
+            interest: row.get(5),
+            distribution: row.get(6),
+            foreign_income: row.get(7),
+            foreign_tax_withheld: row.get(8),
+        }
+    }
+}
+
+impl Transaction<'_> {
+    pub async fn get_investment_income(
+        &mut self,
+        range: Range<DateTime<Local>>,
+    ) -> SqlResult<Vec<InvestmentIncome>> {
+        let start_ts = range.start.timestamp();
+        let end_ts = range.end.timestamp();
+
+        // the closing entries move the revenue into the retained earnings, so they are left out;
+        // the income is foreign when the security (or the account, for cash and GICs) isn't in the home currency
+        let rows = sqlx::query(
+            r#"
+WITH
+    IncomeEntry AS (
+        SELECT
+            OwnedAccount.person_id,
+            OwnedAccount.account_type_id,
+            StockAccountHolder.security_id,
+            account_subtype,
+            COALESCE(SECURITY.currency_id, OwnedAccount.currency_id) IS NOT HomeCurrency.currency_id AS is_foreign,
+            CAST(
+                ROUND(
+                    (credit_amount - debit_amount) * COALESCE(exchange_rate, 1.0)
+                ) AS INTEGER
+            ) AS amount
+        FROM
+            FinancialEntryAmount
+            INNER JOIN OwnedAccount USING (account_id)
+            INNER JOIN AccountSubtype ON AccountSubtype.account_subtype_id = OwnedAccount.account_subtype_id
+            LEFT JOIN HomeCurrency
+            LEFT JOIN StockAccountEntry ON StockAccountEntry.account_id = FinancialEntryAmount.account_id
+            LEFT JOIN StockAccountHolder USING (stock_account_holder_id)
+            LEFT JOIN SECURITY ON SECURITY.security_id = StockAccountHolder.security_id
+            LEFT JOIN TransactionForex USING (transaction_id)
+        WHERE
+            account_subtype IN ('INTEREST', 'DISTRIBUTION', 'WITHHOLDING-TAX')
+            AND date BETWEEN ?
+            AND ?
+            AND transaction_id NOT IN (
+                SELECT
+                    transaction_id
+                FROM
+                    ClosingTransaction
+            )
+    )
+SELECT
+    first_name || ' ' || last_name,
+    institution_name,
+    account_name,
+    ticker,
+    tax_shelter_type,
+    SUM(
+        CASE
+            WHEN account_subtype = 'INTEREST' THEN amount
+            ELSE 0
+        END
+    ),
+    SUM(
+        CASE
+            WHEN account_subtype = 'DISTRIBUTION' THEN amount
+            ELSE 0
+        END
+    ),
+    SUM(
+        CASE
+            WHEN account_subtype <> 'WITHHOLDING-TAX'
+            AND is_foreign THEN amount
+            ELSE 0
+        END
+    ),
+    - SUM(
+        CASE
+            WHEN account_subtype = 'WITHHOLDING-TAX' THEN amount
+            ELSE 0
+        END
+    )
+FROM
+    IncomeEntry
+    INNER JOIN Person USING (person_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN Institution USING (institution_id)
+    INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+    LEFT JOIN SECURITY USING (security_id)
+GROUP BY
+    person_id,
+    account_type_id,
+    security_id
+ORDER BY
+    person_id,
+    institution_name,
+    account_name,
+    ticker
+"#,
+        )
+        .bind(start_ts)
+        .bind(end_ts)
+        .fetch_all(&mut *self.0)
+        .await?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
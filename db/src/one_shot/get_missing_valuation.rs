@@ -0,0 +1,104 @@
+use sqlx::{sqlite::SqliteRow, Row};
+
+use crate::{SqlResult, Transaction};
+
+/// A held security that has no price on the valuation date, or a held currency that has no exchange rate into the
+/// home currency on that date (`ticker` is `None`), which leaves the holding out of the valuation.
+#[derive(Clone, Debug)]
+pub struct MissingValuation {
+    pub ticker: Option<String>,
+    pub currency: String,
+}
+
+impl From<SqliteRow> for MissingValuation {
+    fn from(row: SqliteRow) -> Self {
+        Self {
+            ticker: row.get(0),
+            currency: row.get(1),
+        }
+    }
+}
+
+impl Transaction<'_> {
+    /// Lists what can't be valued on the valuation date (see `set_valuation_date`).
+    pub async fn get_missing_valuation(&mut self) -> SqlResult<Vec<MissingValuation>> {
+        let rows = sqlx::query(
+            r#"
+WITH Holding AS (
+    SELECT
+        security_id
+    FROM
+        StockUnitHistory
+    WHERE
+        date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
+    GROUP BY
+        security_id
+    HAVING
+        SUM(unit) <> 0
+),
+HeldCurrency AS (
+    SELECT
+        currency_id
+    FROM
+        FinancialEntryAmount
+        INNER JOIN OwnedAccount USING (account_id)
+        INNER JOIN AccountSubtype USING (account_subtype_id)
+        INNER JOIN AccountKind USING (account_kind_id)
+    WHERE
+        account_kind IN ('ASSET', 'LIABILITIES')
+        AND account_subtype <> 'STOCK'
+        AND date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
+    GROUP BY
+        account_id,
+        currency_id
+    HAVING
+        SUM(debit_amount - credit_amount) <> 0
+    UNION
+    SELECT
+        currency_id
+    FROM
+        Holding
+        INNER JOIN SECURITY USING (security_id)
+)
+SELECT
+    ticker,
+    currency
+FROM
+    Holding
+    INNER JOIN SECURITY USING (security_id)
+    INNER JOIN SecurityPriceAsOf USING (security_id)
+    INNER JOIN Currency USING (currency_id)
+WHERE
+    SecurityPriceAsOf.price IS NULL
+UNION
+ALL
+SELECT
+    NULL,
+    currency
+FROM
+    HeldCurrency
+    INNER JOIN MarketExchangeRate USING (currency_id)
+    INNER JOIN Currency USING (currency_id)
+WHERE
+    market_exchange_rate IS NULL
+ORDER BY
+    2,
+    1
+"#,
+        )
+        .fetch_all(&mut *self.0)
+        .await?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
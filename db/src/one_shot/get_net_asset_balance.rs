@@ -8,6 +8,7 @@ impl Transaction<'_> {
         Splits into 2 types of calculations:
         - one for cash equivalent assets based on summing records together
         - one for stocks based on multiplying number of units to market price
+        Both only consider entries and prices up to the valuation date (see `set_valuation_date`).
          */
         let rows = sqlx::query(
             r#"
@@ -15,26 +16,21 @@ WITH BaseData AS (
     SELECT
         person_id,
         currency_id,
-        ROUND(
-            SUM(
-                ROUND(
-                    unit * ROUND(
-                        COALESCE(debit, 0) - COALESCE(credit, 0),
-                        2
-                    ),
-                    2
-                )
-            ),
-            2
-        ) AS balance
+        SUM(debit_amount - credit_amount) AS balance
     FROM
-        FinancialEntry
+        FinancialEntryAmount
         INNER JOIN OwnedAccount USING (account_id)
         INNER JOIN AccountSubtype USING (account_subtype_id)
         INNER JOIN AccountKind USING (account_kind_id)
     WHERE
         account_kind IN ('ASSET', 'LIABILITIES')
         AND account_subtype <> 'STOCK'
+        AND date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
     GROUP BY
         person_id,
         currency_id
@@ -43,26 +39,22 @@ WITH BaseData AS (
     SELECT
         person_id,
         currency_id,
-        ROUND(
+        CAST(
             ROUND(
-                SUM(
-                    CASE
-                        WHEN debit IS NOT NULL THEN unit
-                        ELSE - unit
-                    END
-                ),
-                4
-            ) * price,
-            2
+                SUM(unit) * SecurityPriceAsOf.price / 1000000.0
+            ) AS INTEGER
         ) AS balance
     FROM
-        FinancialEntry
-        INNER JOIN StockAccountEntry USING (account_id)
-        INNER JOIN StockAccountHolder USING (stock_account_holder_id)
-        INNER JOIN AccountSubtype USING (account_subtype_id)
+        StockUnitHistory
         INNER JOIN SECURITY USING (security_id)
+        INNER JOIN SecurityPriceAsOf USING (security_id)
     WHERE
-        account_subtype = 'STOCK'
+        date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
     GROUP BY
         person_id,
         security_id,
@@ -86,7 +78,7 @@ SELECT
     last_name,
     currency AS "currency!:String",
     currency_symbol AS "currency_symbol!:String",
-    balance AS "balance!:f64"
+    balance AS "balance!:Money"
 FROM
     Aggregation
     INNER JOIN Person USING (person_id)
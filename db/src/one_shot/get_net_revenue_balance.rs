@@ -20,20 +20,9 @@ SELECT
     last_name,
     currency AS "currency!:String",
     currency_symbol AS "currency_symbol!:String",
-    ROUND(
-        SUM(
-            ROUND(
-                unit * ROUND(
-                    COALESCE(credit, 0) - COALESCE(debit, 0),
-                    2
-                ),
-                2
-            )
-        ),
-        2
-    ) AS "balance!:f64"
+    SUM(credit_amount - debit_amount) AS "balance!:Money"
 FROM
-    FinancialEntry
+    FinancialEntryAmount
     INNER JOIN OwnedAccount USING (account_id)
     INNER JOIN AccountSubtype USING (account_subtype_id)
     INNER JOIN AccountKind USING (account_kind_id)
@@ -41,8 +30,14 @@ FROM
     INNER JOIN Currency USING (currency_id)
 WHERE
     account_kind IN ('REVENUE', 'EXPENSE')
-    AND FinancialEntry.date BETWEEN ?
+    AND FinancialEntryAmount.date BETWEEN ?
     AND ?
+    AND transaction_id NOT IN (
+        SELECT
+            transaction_id
+        FROM
+            ClosingTransaction
+    )
 GROUP BY
     person_id,
     currency_id
@@ -1,4 +1,6 @@
-use crate::{SqlResult, Transaction};
+use std::path::Path;
+
+use crate::{read_csv_into_map, Error, FinancialEntry, SqlResult, Transaction};
 
 impl Transaction<'_> {
     pub async fn get_next_transaction_id(&mut self) -> SqlResult<i64> {
@@ -6,13 +8,25 @@ impl Transaction<'_> {
             transaction_id: i64,
         }
 
+        // the drafts of the statement import are numbered, but they aren't entries until they are upserted
         let record = sqlx::query_as!(
             Record,
             r#"
 SELECT
-    COALESCE(MAX(transaction_id), 0) + 1 AS transaction_id
+    COALESCE(MAX(transaction_id), 0) + 1 AS "transaction_id!: i64"
 FROM
-    FinancialEntry;
+    (
+        SELECT
+            transaction_id
+        FROM
+            FinancialEntry
+        UNION
+        ALL
+        SELECT
+            transaction_id
+        FROM
+            ImportedStatementTransaction
+    );
 "#
         )
         .fetch_one(&mut *self.0)
@@ -20,4 +34,24 @@ FROM
 
         Ok(record.transaction_id)
     }
+
+    /// The next transaction_id after the database and the entries of transaction.csv, which can have transactions
+    /// that aren't upserted yet. A new transaction numbered by the database alone would be merged with them.
+    pub async fn get_next_transaction_id_after_csv(
+        &mut self,
+        csv_path: Option<&Path>,
+    ) -> Result<i64, Error> {
+        let next_transaction_id = self.get_next_transaction_id().await?;
+
+        let csv_next_transaction_id = match csv_path {
+            Some(csv_path) if csv_path.exists() => read_csv_into_map::<FinancialEntry>(csv_path)?
+                .keys()
+                .map(|(transaction_id, _)| transaction_id + 1)
+                .max()
+                .unwrap_or(1),
+            _ => 1,
+        };
+
+        Ok(next_transaction_id.max(csv_next_transaction_id))
+    }
 }
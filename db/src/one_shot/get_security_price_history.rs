@@ -0,0 +1,51 @@
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::{Price, SqlResult, Transaction};
+
+#[derive(Deserialize, Debug)]
+pub struct SecurityPriceRecord {
+    pub ticker: String,
+    pub date: DateTime<Local>,
+    pub price: Price,
+}
+
+impl Transaction<'_> {
+    pub async fn get_security_price_history(
+        &mut self,
+        ticker: &str,
+        limit: u32,
+    ) -> SqlResult<Vec<SecurityPriceRecord>> {
+        let result = sqlx::query(
+            r#"
+SELECT
+    ticker,
+    date,
+    SecurityPrice.price
+FROM
+    SecurityPrice
+    INNER JOIN SECURITY USING (security_id)
+WHERE
+    ticker = ?
+ORDER BY
+    date DESC
+LIMIT
+    ?
+"#,
+        )
+        .bind(ticker)
+        .bind(limit)
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|record| SecurityPriceRecord {
+            ticker: record.get(0),
+            date: record.get(1),
+            price: record.get(2),
+        })
+        .collect();
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::{SqlResult, Transaction};
+
+#[derive(Deserialize, Debug)]
+pub struct StaleSecurityPrice {
+    pub ticker: String,
+    pub last_update: Option<DateTime<Local>>,
+}
+
+impl Transaction<'_> {
+    /// Lists the securities that are still held but have no price since the given date.
+    pub async fn get_stale_security_price(
+        &mut self,
+        since: &DateTime<Local>,
+    ) -> SqlResult<Vec<StaleSecurityPrice>> {
+        let timestamp = since.timestamp();
+
+        let result = sqlx::query(
+            r#"
+WITH Holding AS (
+    SELECT
+        DISTINCT security_id
+    FROM
+        (
+            SELECT
+                person_id,
+                account_type_id,
+                security_id
+            FROM
+                -- the units are converted by the corporate actions, so a renamed or merged security isn't held
+                StockUnitHistory
+            GROUP BY
+                person_id,
+                account_type_id,
+                security_id
+            HAVING
+                SUM(unit) <> 0
+        )
+),
+LastUpdate AS (
+    SELECT
+        security_id,
+        MAX(date) AS last_update
+    FROM
+        SecurityPrice
+    GROUP BY
+        security_id
+)
+SELECT
+    ticker,
+    last_update
+FROM
+    Holding
+    INNER JOIN SECURITY USING (security_id)
+    LEFT JOIN LastUpdate USING (security_id)
+WHERE
+    last_update IS NULL
+    OR last_update < ?
+ORDER BY
+    ticker
+"#,
+        )
+        .bind(timestamp)
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|record| StaleSecurityPrice {
+            ticker: record.get(0),
+            last_update: record.get(1),
+        })
+        .collect();
+
+        Ok(result)
+    }
+}
@@ -1,15 +1,17 @@
 use chrono::{DateTime, Local};
 
-use crate::{SqlResult, Transaction};
+use crate::{Price, SqlResult, Transaction, Units};
 
 pub struct StockTransaction {
     pub name: String,
     pub account_type: String,
     pub ticker: String,
     pub date: DateTime<Local>,
-    pub unit: f64,
-    pub debit: Option<f64>,
-    pub credit: Option<f64>,
+    pub unit: Units,
+    pub debit: Option<Price>,
+    pub credit: Option<Price>,
+    /// The corporate action (e.g. SPLIT) that changed the units, instead of a trade.
+    pub action: Option<String>,
 }
 
 impl Transaction<'_> {
@@ -22,27 +24,56 @@ impl Transaction<'_> {
             StockTransaction,
             r#"
 SELECT
-    first_name || ' ' || last_name AS name,
-    account_type,
-    ticker,
-    date AS "date:DateTime<Local>",
-    CASE
-        WHEN debit THEN unit
-        ELSE - unit
-    END AS unit,
-    debit,
-    credit
+    first_name || ' ' || last_name AS "name!:String",
+    account_type AS "account_type!:String",
+    ticker AS "ticker!:String",
+    date AS "date!:DateTime<Local>",
+    unit AS "unit!:Units",
+    debit AS "debit:Price",
+    credit AS "credit:Price",
+    action AS "action:String"
 FROM
-    FinancialEntry
-    INNER JOIN StockAccountEntry USING (account_id)
-    INNER JOIN StockAccountHolder USING (stock_account_holder_id)
-    INNER JOIN AccountSubtype USING (account_subtype_id)
+    (
+        SELECT
+            person_id,
+            account_type_id,
+            security_id,
+            date,
+            CASE
+                WHEN debit IS NOT NULL THEN unit
+                ELSE - unit
+            END AS unit,
+            debit,
+            credit,
+            NULL AS action
+        FROM
+            FinancialEntry
+            INNER JOIN StockAccountEntry USING (account_id)
+            INNER JOIN StockAccountHolder USING (stock_account_holder_id)
+            INNER JOIN AccountSubtype USING (account_subtype_id)
+        WHERE
+            account_subtype = 'STOCK'
+        UNION
+        ALL
+        SELECT
+            person_id,
+            account_type_id,
+            security_id,
+            date,
+            unit,
+            NULL,
+            NULL,
+            action
+        FROM
+            CorporateActionUnitChange
+    )
     INNER JOIN SECURITY USING (security_id)
     INNER JOIN AccountType USING (account_type_id)
     INNER JOIN Person USING (person_id)
 WHERE
-    account_subtype = 'STOCK'
-    AND ticker = ?
+    ticker = ?
+ORDER BY
+    date
 LIMIT
     ?
 "#,
@@ -1,52 +1,48 @@
-use chrono::{DateTime, Local};
-
-use crate::{SqlResult, Transaction};
+use crate::{Money, SqlResult, Transaction, Units};
 
+#[derive(sqlx::FromRow)]
 pub struct StockUnit {
     pub name: String,
     pub account_name: String,
     pub ticker: String,
-    pub total_unit: f64,
-    pub market_value: f64,
+    pub total_unit: Units,
+    /// `None` when the security has no price on or before the valuation date.
+    pub market_value: Option<Money>,
 }
 
 impl Transaction<'_> {
-    pub async fn get_stock_unit(&mut self, date: &DateTime<Local>) -> SqlResult<Vec<StockUnit>> {
-        let timestamp = date.timestamp();
-
-        let records = sqlx::query_as!(
-            StockUnit,
+    /// Lists the units held on the valuation date (see `set_valuation_date`), valued with the same cut-off as the
+    /// other valuation views.
+    pub async fn get_stock_unit(&mut self) -> SqlResult<Vec<StockUnit>> {
+        // SecurityPriceAsOf is a TEMP view (see valuation_date.sql), which the checked queries can't see
+        let records = sqlx::query_as::<_, StockUnit>(
             r#"
 SELECT
     first_name || ' ' || last_name AS name,
     account_name,
     ticker,
-    total_unit AS "total_unit!:f64",
-    total_unit * price AS market_value
+    total_unit,
+    -- no quote on or before the date leaves the market value out, rather than using today's quote
+    CAST(
+        ROUND(total_unit * SecurityPriceAsOf.price / 1000000.0) AS INTEGER
+    ) AS market_value
 FROM
     (
         SELECT
             person_id,
             account_type_id,
             security_id,
-            ROUND(
-                SUM(
-                    CASE
-                        WHEN debit IS NOT NULL THEN unit
-                        ELSE - unit
-                    END
-                ),
-                4
-            ) AS total_unit
+            SUM(unit) AS total_unit
         FROM
-            FinancialEntry
-            INNER JOIN StockAccountEntry USING (account_id)
-            INNER JOIN StockAccountHolder USING (stock_account_holder_id)
-            INNER JOIN AccountSubtype USING (account_subtype_id)
-            INNER JOIN AccountType USING (account_type_id)
+            -- the units are converted by the corporate actions
+            StockUnitHistory
         WHERE
-            date < ?
-            AND account_subtype = 'STOCK'
+            date <= (
+                SELECT
+                    as_of
+                FROM
+                    ValuationCutoff
+            )
         GROUP BY
             person_id,
             account_type_id,
@@ -56,9 +52,9 @@ FROM
     )
     INNER JOIN Person USING (person_id)
     INNER JOIN SECURITY USING (security_id)
+    INNER JOIN SecurityPriceAsOf USING (security_id)
     INNER JOIN CashAccountProduct USING (account_type_id)
 "#,
-            timestamp
         )
         .fetch_all(&mut *self.0)
         .await?;
@@ -1,29 +1,65 @@
-use std::ops::Range;
+use std::{collections::BTreeMap, ops::Range};
 
 use chrono::{DateTime, Local};
 use serde::Deserialize;
 use sqlx::Row;
 
-use crate::{SqlResult, Transaction};
+use crate::{Money, Price, SqlResult, Transaction, Units};
 
 #[derive(Deserialize, Debug)]
 pub struct TransactionByAccountKey {
     pub transaction_id: i64,
     pub item_id: i64,
     pub date: DateTime<Local>,
-    pub unit: f64,
-    pub debit: Option<f64>,
-    pub credit: Option<f64>,
+    pub account_key: String,
+    pub unit: Units,
+    pub debit: Option<Price>,
+    pub credit: Option<Price>,
     pub exchange_rate: Option<f64>,
-    pub total_amount: f64,
+    /// The amount in the currency of the account, positive when it increases the balance of the account.
+    pub amount: Money,
+    pub total_amount: Money,
+    /// The other accounts of the transaction, separated by commas.
+    pub counter_accounts: String,
     pub description: String,
 }
 
+/// Selects the accounts of a ledger.
+#[derive(Clone, Copy, Debug)]
+pub struct LedgerFilter<'a> {
+    /// The account_key, which can have the wildcards `*` and `?`, e.g. ALICE-*
+    pub account_key: &'a str,
+    /// Only the accounts of the subtype, e.g. CASH
+    pub account_subtype: Option<&'a str>,
+    /// Only the accounts owned by the person_key
+    pub person_key: Option<&'a str>,
+}
+
+impl<'a> LedgerFilter<'a> {
+    pub fn account_key(account_key: &'a str) -> Self {
+        Self {
+            account_key,
+            account_subtype: None,
+            person_key: None,
+        }
+    }
+}
+
 impl Transaction<'_> {
     pub async fn get_transaction_by_account_key(
         &mut self,
         account_key: &str,
         range: Range<DateTime<Local>>,
+    ) -> SqlResult<Vec<TransactionByAccountKey>> {
+        self.get_ledger(&LedgerFilter::account_key(account_key), range)
+            .await
+    }
+
+    /// Lists the entries of the accounts that match the filter, ordered by date.
+    pub async fn get_ledger(
+        &mut self,
+        filter: &LedgerFilter<'_>,
+        range: Range<DateTime<Local>>,
     ) -> SqlResult<Vec<TransactionByAccountKey>> {
         let start_ts = range.start.timestamp();
         let end_ts = range.end.timestamp();
@@ -34,50 +70,272 @@ SELECT
     transaction_id,
     item_id,
     date AS "date!:DateTime<Local>",
-    unit AS "unit!:f64",
+    account_key,
+    unit AS "unit!:Units",
     debit,
     credit,
     exchange_rate AS "exchange_rate:f64",
-    ROUND(
+    CASE
+        WHEN account_kind IN ('ASSET', 'EXPENSE') THEN debit_amount - credit_amount
+        ELSE credit_amount - debit_amount
+    END AS "amount!:Money",
+    CAST(
         ROUND(
             (
                 CASE
-                    WHEN account_kind IN ('ASSET', 'EXPENSE') THEN COALESCE(debit, 0) - COALESCE(credit, 0)
-                    ELSE COALESCE(credit, 0) - COALESCE(debit, 0)
+                    WHEN account_kind IN ('ASSET', 'EXPENSE') THEN debit_amount - credit_amount
+                    ELSE credit_amount - debit_amount
                 END
-            ) * unit,
-            2
-        ) * COALESCE(exchange_rate, 1.0),
-        2
-    ) AS "total_amount!:f64",
+            ) * COALESCE(exchange_rate, 1.0)
+        ) AS INTEGER
+    ) AS "total_amount!:Money",
+    COALESCE(
+        (
+            SELECT
+                GROUP_CONCAT(DISTINCT Counter.account_key)
+            FROM
+                FinancialEntry CounterEntry
+                INNER JOIN Account Counter USING (account_id)
+            WHERE
+                CounterEntry.transaction_id = FinancialEntryAmount.transaction_id
+                AND CounterEntry.account_id <> FinancialEntryAmount.account_id
+        ),
+        ''
+    ) AS counter_accounts,
     description
 FROM
-    FinancialEntry
+    FinancialEntryAmount
     INNER JOIN Account USING (account_id)
     INNER JOIN AccountSubtype USING(account_subtype_id)
     INNER JOIN AccountKind USING(account_kind_id)
     LEFT JOIN TransactionForex USING (transaction_id)
 WHERE
-    account_key = ?
+    account_key GLOB ?
+    AND (
+        ? IS NULL
+        OR account_subtype = ?
+    )
+    AND (
+        ? IS NULL
+        OR account_id IN (
+            SELECT
+                account_id
+            FROM
+                OwnedAccount
+                INNER JOIN Person USING (person_id)
+            WHERE
+                person_key = ?
+        )
+    )
     AND date BETWEEN ?
     AND ?
-"#)
-        .bind(account_key)
+ORDER BY
+    date,
+    transaction_id,
+    item_id
+"#,
+        )
+        .bind(filter.account_key)
+        .bind(filter.account_subtype)
+        .bind(filter.account_subtype)
+        .bind(filter.person_key)
+        .bind(filter.person_key)
         .bind(start_ts)
         .bind(end_ts)
         .fetch_all(&mut *self.0)
-        .await?.into_iter().map(|record| TransactionByAccountKey {
+        .await?
+        .into_iter()
+        .map(|record| TransactionByAccountKey {
             transaction_id: record.get(0),
             item_id: record.get(1),
             date: record.get(2),
-            unit: record.get(3),
-            debit: record.get(4),
-            credit: record.get(5),
-            exchange_rate: record.get(6),
-            total_amount: record.get(7),
-            description: record.get(8),
-        }).collect();
+            account_key: record.get(3),
+            unit: record.get(4),
+            debit: record.get(5),
+            credit: record.get(6),
+            exchange_rate: record.get(7),
+            amount: record.get(8),
+            total_amount: record.get(9),
+            counter_accounts: record.get(10),
+            description: record.get(11),
+        })
+        .collect();
 
         Ok(result)
     }
+
+    /// The balance of each account that matches the filter before the given date, in the currency of the account.
+    /// The balances aren't summed, since a wildcard can match accounts of different kinds, whose amounts have
+    /// opposite signs.
+    pub async fn get_ledger_opening_balances(
+        &mut self,
+        filter: &LedgerFilter<'_>,
+        before: &DateTime<Local>,
+    ) -> SqlResult<BTreeMap<String, Money>> {
+        let timestamp = before.timestamp();
+
+        let balances = sqlx::query(
+            r#"
+SELECT
+    account_key,
+    SUM(
+        CASE
+            WHEN account_kind IN ('ASSET', 'EXPENSE') THEN debit_amount - credit_amount
+            ELSE credit_amount - debit_amount
+        END
+    )
+FROM
+    FinancialEntryAmount
+    INNER JOIN Account USING (account_id)
+    INNER JOIN AccountSubtype USING(account_subtype_id)
+    INNER JOIN AccountKind USING(account_kind_id)
+WHERE
+    account_key GLOB ?
+    AND (
+        ? IS NULL
+        OR account_subtype = ?
+    )
+    AND (
+        ? IS NULL
+        OR account_id IN (
+            SELECT
+                account_id
+            FROM
+                OwnedAccount
+                INNER JOIN Person USING (person_id)
+            WHERE
+                person_key = ?
+        )
+    )
+    AND date < ?
+GROUP BY
+    account_key
+"#,
+        )
+        .bind(filter.account_key)
+        .bind(filter.account_subtype)
+        .bind(filter.account_subtype)
+        .bind(filter.person_key)
+        .bind(filter.person_key)
+        .bind(timestamp)
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect();
+
+        Ok(balances)
+    }
+}
+
+/// Adds the entries to the balances of their accounts, and returns the balance of each entry's account after it.
+pub fn running_balances(
+    balances: &mut BTreeMap<String, Money>,
+    records: &[TransactionByAccountKey],
+) -> Vec<Money> {
+    records
+        .iter()
+        .map(|record| {
+            let balance = balances.entry(record.account_key.clone()).or_default();
+            *balance += record.amount;
+            *balance
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::Db;
+
+    #[tokio::test]
+    async fn wildcard_of_mixed_kinds() {
+        let mut db = Db::from_path("sqlite::memory:".into()).await.unwrap();
+        let mut transaction = db.begin_wrapped_transaction().await.unwrap();
+
+        for query in [
+            "INSERT INTO AccountType (account_type) VALUES ('BB-BRK')",
+            r#"
+INSERT INTO
+    Account (account_key, account_subtype_id, account_type_id, account_name)
+SELECT
+    'ALICE-BB-BRK-XEQT-' || account_subtype,
+    account_subtype_id,
+    account_type_id,
+    account_subtype
+FROM
+    AccountSubtype,
+    AccountType
+WHERE
+    account_type = 'BB-BRK'
+    AND account_subtype IN ('STOCK', 'OPEN-FIAT')
+"#,
+            // opens the account with 10 units at $25 on January 1st 2024, then buys 2 more units on February 1st
+            r#"
+WITH Entry (date, transaction_id, item_id, account_subtype, unit, debit, credit) AS (
+    VALUES
+        (1704067200, 1, 1, 'STOCK', 100000, 250000, NULL),
+        (1704067200, 1, 2, 'OPEN-FIAT', 10000, NULL, 2500000),
+        (1706745600, 2, 1, 'STOCK', 20000, 250000, NULL),
+        (1706745600, 2, 2, 'OPEN-FIAT', 10000, NULL, 500000)
+)
+INSERT INTO
+    FinancialEntry (date, transaction_id, item_id, account_id, unit, debit, credit, description)
+SELECT
+    date,
+    transaction_id,
+    item_id,
+    account_id,
+    unit,
+    debit,
+    credit,
+    ''
+FROM
+    Entry
+    INNER JOIN Account ON account_key = 'ALICE-BB-BRK-XEQT-' || account_subtype
+"#,
+        ] {
+            sqlx::query(query)
+                .execute(&mut **transaction)
+                .await
+                .unwrap();
+        }
+
+        let filter = LedgerFilter::account_key("ALICE-BB-BRK-XEQT-*");
+        let start = Local.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap();
+        let end = Local.with_ymd_and_hms(2024, 12, 31, 0, 0, 0).unwrap();
+
+        let mut balances = transaction
+            .get_ledger_opening_balances(&filter, &start)
+            .await
+            .unwrap();
+        assert_eq!(
+            balances,
+            BTreeMap::from([
+                (
+                    "ALICE-BB-BRK-XEQT-OPEN-FIAT".into(),
+                    Money::from_cents(25000)
+                ),
+                ("ALICE-BB-BRK-XEQT-STOCK".into(), Money::from_cents(25000)),
+            ])
+        );
+
+        let records = transaction.get_ledger(&filter, start..end).await.unwrap();
+        let running = running_balances(&mut balances, &records);
+
+        // each account keeps its own balance, instead of the two kinds cancelling out
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record.account_key.as_str())
+                .zip(running)
+                .collect::<Vec<_>>(),
+            vec![
+                ("ALICE-BB-BRK-XEQT-STOCK", Money::from_cents(30000)),
+                ("ALICE-BB-BRK-XEQT-OPEN-FIAT", Money::from_cents(30000)),
+            ]
+        );
+    }
 }
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Local};
+use sqlx::Row;
+
+use crate::{Error, Money, Transaction};
+
+#[derive(Clone, Debug)]
+pub struct TrialBalanceRecord {
+    pub account_kind: String,
+    pub account_subtype: String,
+    pub account_key: String,
+    pub account_name: String,
+    /// The net balance of a debit-side account, zero otherwise.
+    pub debit: Money,
+    /// The net balance of a credit-side account, zero otherwise.
+    pub credit: Money,
+}
+
+impl Transaction<'_> {
+    /// Lists the net balance of every account with entries up to `as_of`.
+    /// With `person_key`, only the transactions that touch an account of the person are included,
+    /// so that the shared accounts (e.g. expense categories) keep both sides of the entries.
+    pub async fn get_trial_balance(
+        &mut self,
+        as_of: &DateTime<Local>,
+        person_key: Option<&str>,
+    ) -> Result<Vec<TrialBalanceRecord>, Error> {
+        if let Some(person_key) = person_key {
+            let person = sqlx::query!(
+                "SELECT person_id FROM Person WHERE person_key = ?",
+                person_key
+            )
+            .fetch_optional(&mut *self.0)
+            .await?;
+
+            if person.is_none() {
+                return Err(Error::UnknownKey {
+                    path: None,
+                    line: None,
+                    key: "Person.person_key".to_string(),
+                    record: format!("person_key: {person_key}"),
+                });
+            }
+        }
+
+        let timestamp = as_of.timestamp();
+
+        let records = sqlx::query(
+            r#"
+WITH BaseData AS (
+    SELECT
+        account_id,
+        SUM(debit_amount - credit_amount) AS balance
+    FROM
+        FinancialEntryAmount
+    WHERE
+        date <= ?
+        AND (
+            ? IS NULL
+            OR transaction_id IN (
+                SELECT
+                    transaction_id
+                FROM
+                    FinancialEntry
+                    INNER JOIN OwnedAccount USING (account_id)
+                    INNER JOIN Person USING (person_id)
+                WHERE
+                    person_key = ?
+            )
+        )
+    GROUP BY
+        account_id
+    HAVING
+        balance <> 0
+)
+SELECT
+    account_kind,
+    account_subtype,
+    account_key,
+    account_name,
+    MAX(balance, 0) AS debit,
+    MAX(- balance, 0) AS credit
+FROM
+    BaseData
+    INNER JOIN Account USING (account_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+ORDER BY
+    account_kind,
+    account_subtype,
+    account_key
+"#,
+        )
+        .bind(timestamp)
+        .bind(person_key)
+        .bind(person_key)
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|row| TrialBalanceRecord {
+            account_kind: row.get(0),
+            account_subtype: row.get(1),
+            account_key: row.get(2),
+            account_name: row.get(3),
+            debit: row.get(4),
+            credit: row.get(5),
+        })
+        .collect();
+
+        Ok(records)
+    }
+}
@@ -1,24 +1,21 @@
 use sqlx::Row;
 
-use crate::Transaction;
+use crate::{Money, SqlResult, Transaction};
 
 pub struct JustifyAmex {
     pub year: i64,
     pub month: i64,
-    pub balance: f64,
+    pub balance: Money,
     pub extra_cashback_rate: f64,
-    pub with_amex_cashback: f64,
-    pub without_amex_cashback: f64,
-    pub extra_cashback: f64,
-    pub extra_cashback_after_fee: f64,
-    pub missed_opportunities: f64,
+    pub with_amex_cashback: Money,
+    pub without_amex_cashback: Money,
+    pub extra_cashback: Money,
+    pub extra_cashback_after_fee: Money,
+    pub missed_opportunities: Money,
 }
 
 impl Transaction<'_> {
-    pub async fn justify_amex(
-        &mut self,
-        num_days: u32,
-    ) -> Result<Vec<JustifyAmex>, Box<dyn std::error::Error>> {
+    pub async fn justify_amex(&mut self, num_days: u32) -> SqlResult<Vec<JustifyAmex>> {
         const AVG_DAYS_IN_MONTH: f64 = 30.437;
         let num_months = (num_days as f64 / AVG_DAYS_IN_MONTH).ceil();
 
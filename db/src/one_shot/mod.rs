@@ -5,14 +5,21 @@ mod get_asset_rebalance;
 mod get_balance;
 mod get_credit_card_pad_injection;
 mod get_current_credit_card_balance;
+mod get_disposition;
 mod get_emergency_rebalance;
+mod get_exchange_rate_history;
 mod get_expense_by_category;
+mod get_investment_income;
+mod get_missing_valuation;
 mod get_net_asset_balance;
 mod get_net_revenue_balance;
 mod get_next_transaction_id;
+mod get_security_price_history;
+mod get_stale_security_price;
 mod get_stock_transaction;
 mod get_stock_unit;
 mod get_transaction_by_account_key;
+mod get_trial_balance;
 mod justify_amex;
 
 pub use get_acb::Acb;
@@ -20,10 +27,17 @@ pub use get_asset_last_update::AccountLatestTransaction;
 pub use get_asset_rebalance::AssetRebalance;
 pub use get_balance::BalanceRecord;
 pub use get_credit_card_pad_injection::CreditCardPadInjection;
+pub use get_disposition::Disposition;
 pub use get_emergency_rebalance::EmergencyRebalance;
+pub use get_exchange_rate_history::ExchangeRateRecord;
+pub use get_investment_income::InvestmentIncome;
+pub use get_missing_valuation::MissingValuation;
+pub use get_security_price_history::SecurityPriceRecord;
+pub use get_stale_security_price::StaleSecurityPrice;
 pub use get_stock_transaction::StockTransaction;
 pub use get_stock_unit::StockUnit;
-pub use get_transaction_by_account_key::TransactionByAccountKey;
+pub use get_transaction_by_account_key::{running_balances, LedgerFilter, TransactionByAccountKey};
+pub use get_trial_balance::TrialBalanceRecord;
 pub use justify_amex::JustifyAmex;
 
 #[derive(Clone, serde::Deserialize, Debug)]
@@ -32,5 +46,5 @@ pub struct NetBalanceRecord {
     pub last_name: String,
     pub currency: String,
     pub currency_symbol: String,
-    pub balance: f64,
+    pub balance: crate::Money,
 }
@@ -0,0 +1,370 @@
+use std::path::Path;
+
+use chrono::{DateTime, Days, Local, NaiveDate};
+use common::{end_of_day, start_of_day};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::{Error, LedgerFilter, Money, Transaction};
+
+/// A line of a bank or credit card statement. The amount has the sign of the account's kind, i.e. a deposit is
+/// positive for a chequing account and a purchase is positive for a credit card.
+#[derive(Clone, Debug)]
+pub struct StatementLine {
+    pub line: u64,
+    pub date: NaiveDate,
+    pub amount: Money,
+    pub description: String,
+}
+
+/// An entry of the reconciled account, with the amount in the sign of the account's kind.
+#[derive(Clone, Debug)]
+pub struct LedgerLine {
+    pub transaction_id: i64,
+    pub item_id: i64,
+    pub date: DateTime<Local>,
+    pub amount: Money,
+    pub description: String,
+    /// Reconciled against an earlier statement.
+    pub is_reconciled: bool,
+}
+
+pub struct StatementMatch {
+    pub matched: Vec<(StatementLine, LedgerLine)>,
+    /// Statement lines without an entry in the ledger.
+    pub unmatched_statement: Vec<StatementLine>,
+    /// Entries up to the statement date that aren't on the statement and weren't reconciled before.
+    pub unmatched_ledger: Vec<LedgerLine>,
+    /// The balance of the account at the end of the statement date.
+    pub ledger_balance: Money,
+}
+
+#[derive(Deserialize)]
+struct StatementRecord {
+    date: String,
+    amount: Money,
+    #[serde(default)]
+    description: String,
+}
+
+/// Reads a statement CSV with the date, amount and description columns. Dates are either 01/31/2024 or 2024-01-31.
+pub fn read_statement(csv_path: &Path) -> Result<Vec<StatementLine>, Error> {
+    let csv_error = |line: Option<u64>| {
+        move |source: csv::Error| Error::Csv {
+            path: csv_path.to_path_buf(),
+            line,
+            source,
+        }
+    };
+
+    let mut reader = csv::Reader::from_path(csv_path).map_err(csv_error(None))?;
+    let headers = reader.headers().map_err(csv_error(Some(1)))?.clone();
+
+    let mut lines = vec![];
+    for row in reader.records() {
+        let row = row.map_err(csv_error(None))?;
+        let line = row.position().map(|position| position.line()).unwrap_or(0);
+        let record: StatementRecord = row
+            .deserialize(Some(&headers))
+            .map_err(csv_error(Some(line)))?;
+
+        let date = NaiveDate::parse_from_str(record.date.trim(), "%m/%d/%Y")
+            .or_else(|_| NaiveDate::parse_from_str(record.date.trim(), "%Y-%m-%d"))
+            .map_err(|_| {
+                Error::invalid(format!("{:?} is not a date", record.date)).at(csv_path, line)
+            })?;
+
+        lines.push(StatementLine {
+            line,
+            date,
+            amount: record.amount,
+            description: record.description.trim().to_string(),
+        });
+    }
+
+    lines.sort_by_key(|line| line.date);
+
+    Ok(lines)
+}
+
+impl Transaction<'_> {
+    async fn get_account_id(&mut self, account_key: &str) -> Result<i64, Error> {
+        sqlx::query("SELECT account_id FROM Account WHERE account_key = ?")
+            .bind(account_key)
+            .fetch_optional(&mut *self.0)
+            .await?
+            .map(|row| row.get(0))
+            .ok_or_else(|| Error::UnknownKey {
+                path: None,
+                line: None,
+                key: "Account.account_key".into(),
+                record: account_key.to_string(),
+            })
+    }
+
+    /// The balance of the account at the end of the given date.
+    async fn get_balance_at(
+        &mut self,
+        account_key: &str,
+        date: &NaiveDate,
+    ) -> Result<Money, Error> {
+        let next_day = date
+            .checked_add_days(Days::new(1))
+            .ok_or_else(|| Error::invalid(format!("the statement date {date} is out of range")))?;
+
+        Ok(self
+            .get_ledger_opening_balances(
+                &LedgerFilter::account_key(account_key),
+                &start_of_day(&next_day),
+            )
+            .await?
+            .remove(account_key)
+            .unwrap_or_default())
+    }
+
+    /// Matches the statement lines with the entries of the account that have the same amount and are dated within
+    /// `window_days` of each other, the closest dates first.
+    pub async fn match_statement(
+        &mut self,
+        account_key: &str,
+        statement: &[StatementLine],
+        statement_date: &NaiveDate,
+        window_days: u64,
+    ) -> Result<StatementMatch, Error> {
+        self.get_account_id(account_key).await?;
+
+        let first_date = statement
+            .iter()
+            .map(|line| line.date)
+            .min()
+            .unwrap_or(*statement_date)
+            .min(*statement_date);
+        let window = Days::new(window_days);
+        let start = start_of_day(&first_date.checked_sub_days(window).unwrap_or(first_date));
+        let end = end_of_day(
+            &statement_date
+                .checked_add_days(window)
+                .unwrap_or(*statement_date),
+        );
+
+        let mut candidates: Vec<Option<LedgerLine>> = sqlx::query(
+            r#"
+SELECT
+    transaction_id,
+    item_id,
+    date,
+    CASE
+        WHEN account_kind IN ('ASSET', 'EXPENSE') THEN debit_amount - credit_amount
+        ELSE credit_amount - debit_amount
+    END AS amount,
+    description,
+    EXISTS (
+        SELECT
+            1
+        FROM
+            ReconciledEntry
+        WHERE
+            ReconciledEntry.transaction_id = FinancialEntryAmount.transaction_id
+            AND ReconciledEntry.item_id = FinancialEntryAmount.item_id
+    ) AS is_reconciled
+FROM
+    FinancialEntryAmount
+    INNER JOIN Account USING (account_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+WHERE
+    account_key = ?
+    AND date BETWEEN ?
+    AND ?
+ORDER BY
+    date,
+    transaction_id,
+    item_id
+"#,
+        )
+        .bind(account_key)
+        .bind(start.timestamp())
+        .bind(end.timestamp())
+        .fetch_all(&mut *self.0)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Some(LedgerLine {
+                transaction_id: row.get(0),
+                item_id: row.get(1),
+                date: row.get(2),
+                amount: row.get(3),
+                description: row.get(4),
+                is_reconciled: row.get(5),
+            })
+        })
+        .collect();
+
+        let mut matched = vec![];
+        let mut unmatched_statement = vec![];
+
+        for line in statement {
+            let closest = candidates
+                .iter()
+                .enumerate()
+                .filter_map(|(index, candidate)| {
+                    let candidate = candidate.as_ref()?;
+                    let num_days = (candidate.date.date_naive() - line.date).num_days().abs();
+
+                    (candidate.amount == line.amount && num_days <= window_days as i64)
+                        .then_some((num_days, index))
+                })
+                .min();
+
+            // the closest candidate is still unmatched, as the filter skips the taken ones
+            match closest.and_then(|(_, index)| candidates[index].take()) {
+                Some(candidate) => matched.push((line.clone(), candidate)),
+                None => unmatched_statement.push(line.clone()),
+            }
+        }
+
+        let statement_end = end_of_day(statement_date);
+        let unmatched_ledger = candidates
+            .into_iter()
+            .flatten()
+            .filter(|candidate| !candidate.is_reconciled && candidate.date <= statement_end)
+            .collect();
+
+        let ledger_balance = self.get_balance_at(account_key, statement_date).await?;
+
+        Ok(StatementMatch {
+            matched,
+            unmatched_statement,
+            unmatched_ledger,
+            ledger_balance,
+        })
+    }
+
+    /// Records the values of every entry of the account up to the end of the statement date, once the balance of
+    /// the account agrees with the statement. Returns the number of entries that are newly reconciled.
+    pub async fn mark_reconciled(
+        &mut self,
+        account_key: &str,
+        statement_date: &NaiveDate,
+        closing_balance: Money,
+    ) -> Result<u64, Error> {
+        let account_id = self.get_account_id(account_key).await?;
+
+        let ledger_balance = self.get_balance_at(account_key, statement_date).await?;
+        if ledger_balance != closing_balance {
+            return Err(Error::invalid(format!(
+                "the balance of {account_key} is ${ledger_balance} at the end of {statement_date}, but the statement closes at ${closing_balance}"
+            )));
+        }
+
+        let statement_ts = end_of_day(statement_date).timestamp();
+
+        sqlx::query!(
+            r#"
+INSERT INTO
+    Reconciliation (account_id, statement_date, closing_balance)
+VALUES
+    (?, ?, ?) ON CONFLICT (account_id, statement_date) DO
+UPDATE
+SET
+    closing_balance = excluded.closing_balance
+"#,
+            account_id,
+            statement_ts,
+            closing_balance
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        // the entries that were deleted since the last reconciliation are acknowledged
+        sqlx::query!(
+            r#"
+DELETE FROM
+    ReconciledEntry
+WHERE
+    account_id = ?
+    AND date <= ?
+    AND (transaction_id, item_id) NOT IN (
+        SELECT
+            transaction_id,
+            item_id
+        FROM
+            FinancialEntry
+    )
+"#,
+            account_id,
+            statement_ts
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        let num_new: i64 = sqlx::query(
+            r#"
+SELECT
+    COUNT(*)
+FROM
+    FinancialEntry
+WHERE
+    account_id = ?
+    AND date <= ?
+    AND (transaction_id, item_id) NOT IN (
+        SELECT
+            transaction_id,
+            item_id
+        FROM
+            ReconciledEntry
+    )
+"#,
+        )
+        .bind(account_id)
+        .bind(statement_ts)
+        .fetch_one(&mut *self.0)
+        .await?
+        .get(0);
+
+        // the changed entries are accepted with their current values
+        sqlx::query!(
+            r#"
+INSERT INTO
+    ReconciledEntry (
+        transaction_id,
+        item_id,
+        account_id,
+        statement_date,
+        date,
+        unit,
+        debit,
+        credit
+    )
+SELECT
+    transaction_id,
+    item_id,
+    account_id,
+    ?,
+    date,
+    unit,
+    debit,
+    credit
+FROM
+    FinancialEntry
+WHERE
+    account_id = ?
+    AND date <= ? ON CONFLICT (transaction_id, item_id) DO
+UPDATE
+SET
+    account_id = excluded.account_id,
+    date = excluded.date,
+    unit = excluded.unit,
+    debit = excluded.debit,
+    credit = excluded.credit
+"#,
+            statement_ts,
+            account_id,
+            statement_ts
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        Ok(num_new as u64)
+    }
+}
@@ -0,0 +1,716 @@
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+use chrono::{Days, Local, Months, NaiveDate, TimeZone};
+use common::{end_of_day, start_of_day};
+
+use crate::{
+    Error, Export, FinancialEntry, Money, Price, Query, RecurringEntry, Transaction, Units,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Schedule {
+    Weekly,
+    Biweekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Schedule {
+    /// The nth occurrence counted from the first one, so that the day of month doesn't drift after a short month.
+    fn nth(&self, start: NaiveDate, n: u32) -> Option<NaiveDate> {
+        match self {
+            Self::Weekly => start.checked_add_days(Days::new(7 * n as u64)),
+            Self::Biweekly => start.checked_add_days(Days::new(14 * n as u64)),
+            Self::Monthly => start.checked_add_months(Months::new(n)),
+            Self::Quarterly => start.checked_add_months(Months::new(3 * n)),
+            Self::Yearly => start.checked_add_months(Months::new(12 * n)),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "WEEKLY" => Ok(Self::Weekly),
+            "BIWEEKLY" => Ok(Self::Biweekly),
+            "MONTHLY" => Ok(Self::Monthly),
+            "QUARTERLY" => Ok(Self::Quarterly),
+            "YEARLY" => Ok(Self::Yearly),
+            _ => Err(format!(
+                "{s:?} is not a schedule, expecting WEEKLY, BIWEEKLY, MONTHLY, QUARTERLY or YEARLY"
+            )),
+        }
+    }
+}
+
+/// An exact fraction of dollars, so that a formula is only rounded once, to the cent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Ratio {
+    numer: i128,
+    /// Always positive, and the fraction is reduced.
+    denom: i128,
+}
+
+impl Ratio {
+    fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+
+        let gcd = gcd(numer.unsigned_abs(), denom.unsigned_abs()).max(1) as i128;
+        let sign = denom.signum();
+
+        Some(Self {
+            numer: sign * numer / gcd,
+            denom: sign * denom / gcd,
+        })
+    }
+
+    fn from_money(money: Money) -> Self {
+        Self::new(money.cents() as i128, 100).unwrap_or(Self { numer: 0, denom: 1 })
+    }
+
+    /// Parses a decimal number of dollars, e.g. 1,234.56
+    fn from_decimal(number: &str) -> Option<Self> {
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+            return None;
+        }
+
+        let numer = format!("{whole}{fraction}").parse().ok()?;
+        let denom = 10_i128.checked_pow(fraction.len() as u32)?;
+
+        Self::new(numer, denom)
+    }
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::new(
+            self.numer
+                .checked_mul(rhs.denom)?
+                .checked_add(rhs.numer.checked_mul(self.denom)?)?,
+            self.denom.checked_mul(rhs.denom)?,
+        )
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(rhs.numer)?,
+            self.denom.checked_mul(rhs.denom)?,
+        )
+    }
+
+    fn checked_div(self, rhs: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(rhs.denom)?,
+            self.denom.checked_mul(rhs.numer)?,
+        )
+    }
+
+    fn neg(self) -> Self {
+        Self {
+            numer: -self.numer,
+            denom: self.denom,
+        }
+    }
+
+    /// Rounded half away from zero to the cent.
+    fn to_money(self) -> Option<Money> {
+        let cents = self.numer.checked_mul(100)?;
+        let half = self.denom / 2;
+        let rounded = if cents < 0 {
+            (cents - half) / self.denom
+        } else {
+            (cents + half) / self.denom
+        };
+
+        i64::try_from(rounded).ok().map(Money::from_cents)
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Evaluates the amount formulas of a template, e.g. `(#1 + #2) * 5.95%`.
+/// Numbers are in dollars, `#N` is the amount of the item N and `%` divides the number before it by 100.
+struct Formula<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    amounts: &'a BTreeMap<i64, Money>,
+}
+
+/// Why a formula can't be evaluated (yet).
+#[derive(Debug, PartialEq, Eq)]
+enum FormulaError {
+    /// The formula refers to an item that isn't evaluated yet.
+    Pending(i64),
+    Invalid(String),
+}
+
+impl<'a> Formula<'a> {
+    fn evaluate(formula: &str, amounts: &'a BTreeMap<i64, Money>) -> Result<Money, FormulaError> {
+        let mut parser = Formula {
+            chars: formula.chars().filter(|c| !c.is_whitespace()).collect(),
+            pos: 0,
+            amounts,
+        };
+
+        let value = parser.expr()?;
+        if parser.pos != parser.chars.len() {
+            return Err(parser.unexpected());
+        }
+
+        value.to_money().ok_or_else(|| parser.out_of_range())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn formula(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn unexpected(&self) -> FormulaError {
+        let formula = self.formula();
+
+        match self.peek() {
+            Some(c) => FormulaError::Invalid(format!("unexpected {c:?} in {formula:?}")),
+            None => FormulaError::Invalid(format!("{formula:?} ends unexpectedly")),
+        }
+    }
+
+    /// Divided by zero, or too large to be an amount.
+    fn out_of_range(&self) -> FormulaError {
+        FormulaError::Invalid(format!(
+            "{:?} doesn't evaluate to an amount",
+            self.formula()
+        ))
+    }
+
+    fn expr(&mut self) -> Result<Ratio, FormulaError> {
+        let mut value = self.term()?;
+
+        loop {
+            let rhs = match self.peek() {
+                Some('+') => {
+                    self.pos += 1;
+                    self.term()?
+                }
+                Some('-') => {
+                    self.pos += 1;
+                    self.term()?.neg()
+                }
+                _ => return Ok(value),
+            };
+            value = value.checked_add(rhs).ok_or_else(|| self.out_of_range())?;
+        }
+    }
+
+    fn term(&mut self) -> Result<Ratio, FormulaError> {
+        let mut value = self.factor()?;
+
+        loop {
+            value = match self.peek() {
+                Some('*') => {
+                    self.pos += 1;
+                    value.checked_mul(self.factor()?)
+                }
+                Some('/') => {
+                    self.pos += 1;
+                    value.checked_div(self.factor()?)
+                }
+                _ => return Ok(value),
+            }
+            .ok_or_else(|| self.out_of_range())?;
+        }
+    }
+
+    fn factor(&mut self) -> Result<Ratio, FormulaError> {
+        let value = match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                self.factor()?.neg()
+            }
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expr()?;
+                if self.peek() != Some(')') {
+                    return Err(self.unexpected());
+                }
+                self.pos += 1;
+                value
+            }
+            Some('#') => {
+                self.pos += 1;
+                let item_id: i64 = self.digits().parse().map_err(|_| self.unexpected())?;
+
+                match self.amounts.get(&item_id) {
+                    Some(amount) => Ratio::from_money(*amount),
+                    None => return Err(FormulaError::Pending(item_id)),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self.digits();
+                Ratio::from_decimal(&number)
+                    .ok_or_else(|| FormulaError::Invalid(format!("{number:?} is not a number")))?
+            }
+            _ => return Err(self.unexpected()),
+        };
+
+        if self.peek() == Some('%') {
+            self.pos += 1;
+            let hundred = Ratio {
+                numer: 100,
+                denom: 1,
+            };
+            return value
+                .checked_div(hundred)
+                .ok_or_else(|| self.out_of_range());
+        }
+
+        Ok(value)
+    }
+
+    fn digits(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || c == '.' || c == ',')
+        {
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos]
+            .iter()
+            .filter(|c| **c != ',')
+            .collect()
+    }
+}
+
+/// The debit and the credit of each item of a template, keyed by item_id.
+type TemplateAmounts = BTreeMap<i64, (Option<Money>, Option<Money>)>;
+
+/// The debits and credits of one occurrence of a template.
+fn evaluate_template(
+    recurring_key: &str,
+    items: &[RecurringEntry],
+) -> Result<TemplateAmounts, Error> {
+    let invalid = |item_id: i64, message: String| {
+        Error::invalid(format!(
+            "recurring template {recurring_key}, item {item_id}: {message}"
+        ))
+    };
+
+    let mut amounts = BTreeMap::new();
+    let mut balancing_item = None;
+
+    // formulas can refer to the items that come after them, so keep evaluating until nothing changes
+    let mut pending: Vec<&RecurringEntry> = vec![];
+    for item in items {
+        match (&item.debit, &item.credit) {
+            (Some(_), Some(_)) => {
+                return Err(invalid(
+                    item.item_id,
+                    "only one of debit or credit can be filled".into(),
+                ));
+            }
+            (None, None) => match balancing_item {
+                Some(other) => {
+                    return Err(invalid(
+                        item.item_id,
+                        format!("item {other} already balances the template, fill in the debit or the credit"),
+                    ));
+                }
+                None => balancing_item = Some(item.item_id),
+            },
+            _ => pending.push(item),
+        }
+    }
+
+    let mut sides = BTreeMap::new();
+    while !pending.is_empty() {
+        let mut still_pending = vec![];
+        let mut last_reference = None;
+
+        for item in pending.iter().copied() {
+            let (formula, is_debit) = match (&item.debit, &item.credit) {
+                (Some(debit), _) => (debit, true),
+                (_, Some(credit)) => (credit, false),
+                (None, None) => unreachable!("the balancing item isn't evaluated"),
+            };
+
+            match Formula::evaluate(formula, &amounts) {
+                Ok(amount) => {
+                    amounts.insert(item.item_id, amount);
+                    sides.insert(item.item_id, is_debit);
+                }
+                Err(FormulaError::Pending(item_id)) => {
+                    last_reference = Some((item.item_id, item_id));
+                    still_pending.push(item);
+                }
+                Err(FormulaError::Invalid(message)) => {
+                    return Err(invalid(item.item_id, message));
+                }
+            }
+        }
+
+        // nothing was evaluated in this round, so every item left refers to one that can't be
+        if still_pending.len() == pending.len() {
+            return Err(match last_reference {
+                Some((item_id, reference)) => invalid(
+                    item_id,
+                    format!(
+                        "#{reference} is not an item with an amount, or the items refer to each other"
+                    ),
+                ),
+                None => Error::invalid(format!(
+                    "the amounts of recurring template {recurring_key} cannot be evaluated"
+                )),
+            });
+        }
+
+        pending = still_pending;
+    }
+
+    let mut result = BTreeMap::new();
+    let mut net_debit = Money::ZERO;
+
+    for (item_id, amount) in amounts {
+        let is_debit = sides[&item_id];
+
+        // a negative amount goes to the other side
+        let (debit, credit) = match (is_debit, amount.is_negative()) {
+            (true, false) | (false, true) => (Some(amount.abs()), None),
+            (false, false) | (true, true) => (None, Some(amount.abs())),
+        };
+
+        net_debit += debit.unwrap_or(Money::ZERO) - credit.unwrap_or(Money::ZERO);
+        result.insert(item_id, (debit, credit));
+    }
+
+    match balancing_item {
+        Some(item_id) => {
+            let side = if net_debit.is_negative() {
+                (Some(net_debit.abs()), None)
+            } else {
+                (None, Some(net_debit))
+            };
+            result.insert(item_id, side);
+        }
+        None if !net_debit.is_zero() => {
+            return Err(Error::invalid(format!(
+                "recurring template {recurring_key} is off by ${}, leave the debit and the credit of one item empty to balance it",
+                net_debit.abs()
+            )));
+        }
+        None => {}
+    }
+
+    Ok(result)
+}
+
+impl Transaction<'_> {
+    /// Posts the occurrences of the recurring templates up to the end of the given date.
+    /// An occurrence is already posted when a transaction on its day has an entry for every account of the
+    /// template, which includes the ones typed in by hand. Occurrences in closed periods are skipped. The transactions
+    /// are numbered after the ones of `transaction_csv` when they are appended to it.
+    pub async fn generate_recurring(
+        &mut self,
+        until: &NaiveDate,
+        transaction_csv: Option<&Path>,
+    ) -> Result<Vec<FinancialEntry>, Error> {
+        let records = sqlx::query_as::<_, RecurringEntry>(RecurringEntry::EXPORT_QUERY)
+            .fetch_all(&mut *self.0)
+            .await?;
+
+        let mut templates: BTreeMap<String, Vec<RecurringEntry>> = BTreeMap::new();
+        for record in records {
+            templates
+                .entry(record.recurring_key.clone())
+                .or_default()
+                .push(record);
+        }
+
+        let closed_until = self.get_closed_until().await?;
+        let mut generated = vec![];
+
+        for (recurring_key, items) in templates {
+            let first = &items[0];
+            if items.iter().any(|item| {
+                item.schedule != first.schedule
+                    || item.start_date != first.start_date
+                    || item.end_date != first.end_date
+            }) {
+                return Err(Error::invalid(format!(
+                    "the items of recurring template {recurring_key} have different schedules or dates"
+                )));
+            }
+
+            let schedule: Schedule = first.schedule.parse().map_err(Error::invalid)?;
+            let to_date = |timestamp: i64| {
+                Local
+                    .timestamp_opt(timestamp, 0)
+                    .single()
+                    .map(|datetime| datetime.date_naive())
+                    .ok_or_else(|| Error::invalid(format!("invalid timestamp {timestamp}")))
+            };
+            let start_date = to_date(first.start_date)?;
+            let end_date = match first.end_date {
+                Some(end_date) => to_date(end_date)?.min(*until),
+                None => *until,
+            };
+
+            let amounts = evaluate_template(&recurring_key, &items)?;
+
+            for n in 0.. {
+                let date = match schedule.nth(start_date, n) {
+                    Some(date) if date <= end_date => date,
+                    _ => break,
+                };
+
+                let timestamp = start_of_day(&date);
+                if closed_until.is_some_and(|closed_until| timestamp <= closed_until) {
+                    continue;
+                }
+
+                let is_posted = sqlx::query(
+                    r#"
+SELECT
+    transaction_id
+FROM
+    FinancialEntry
+    INNER JOIN RecurringEntry USING (account_id)
+WHERE
+    recurring_key = ?
+    AND date BETWEEN ?
+    AND ?
+GROUP BY
+    transaction_id
+HAVING
+    COUNT(DISTINCT account_id) = (
+        SELECT
+            COUNT(DISTINCT account_id)
+        FROM
+            RecurringEntry
+        WHERE
+            recurring_key = ?
+    )
+"#,
+                )
+                .bind(&recurring_key)
+                .bind(timestamp.timestamp())
+                .bind(end_of_day(&date).timestamp())
+                .bind(&recurring_key)
+                .fetch_optional(&mut *self.0)
+                .await?
+                .is_some();
+
+                if is_posted {
+                    continue;
+                }
+
+                let transaction_id = self
+                    .get_next_transaction_id_after_csv(transaction_csv)
+                    .await?;
+
+                for item in &items {
+                    let (debit, credit) = amounts[&item.item_id];
+                    let record = FinancialEntry {
+                        transaction_id,
+                        item_id: item.item_id,
+                        date: timestamp.timestamp(),
+                        account_key: item.account_key.clone(),
+                        unit: Units::from_raw(Units::SCALE),
+                        debit: debit.map(Price::from),
+                        credit: credit.map(Price::from),
+                        description: item.description.clone(),
+                    };
+
+                    if let Err(err) = self.execute(record.query()).await {
+                        return Err(Error::from_record(err, &record).of_entry(record.entry_id()));
+                    }
+
+                    generated.push(record);
+                }
+            }
+        }
+
+        Ok(generated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(formula: &str, amounts: &[(i64, i64)]) -> Result<Money, FormulaError> {
+        let amounts = amounts
+            .iter()
+            .map(|(item_id, cents)| (*item_id, Money::from_cents(*cents)))
+            .collect();
+        Formula::evaluate(formula, &amounts)
+    }
+
+    fn cents(cents: i64) -> Result<Money, FormulaError> {
+        Ok(Money::from_cents(cents))
+    }
+
+    fn item(item_id: i64, debit: Option<&str>, credit: Option<&str>) -> RecurringEntry {
+        RecurringEntry {
+            recurring_key: "PAY".into(),
+            item_id,
+            schedule: "MONTHLY".into(),
+            start_date: 0,
+            end_date: None,
+            account_key: format!("ACCOUNT-{item_id}"),
+            debit: debit.map(str::to_string),
+            credit: credit.map(str::to_string),
+            description: "".into(),
+        }
+    }
+
+    #[test]
+    fn formula_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3", &[]), cents(700));
+        assert_eq!(evaluate("(1 + 2) * 3", &[]), cents(900));
+        assert_eq!(evaluate("10 - 4 - 3", &[]), cents(300));
+        assert_eq!(evaluate("12 / 4 / 3", &[]), cents(100));
+        assert_eq!(evaluate("-2 * -3", &[]), cents(600));
+        assert_eq!(evaluate("1,234.56", &[]), cents(123_456));
+    }
+
+    #[test]
+    fn formula_percent() {
+        assert_eq!(evaluate("#1 * 5.95%", &[(1, 250_000)]), cents(14_875));
+        assert_eq!(
+            evaluate("(#1 + #2) * 5.95%", &[(1, 100_000), (2, 50_000)]),
+            cents(8_925)
+        );
+        // 1/3 of a cent is only rounded at the end, 3 * (1/3) is exact
+        assert_eq!(evaluate("1 / 3 * 3", &[]), cents(100));
+        assert_eq!(evaluate("0.01 / 3 * 3", &[]), cents(1));
+        assert_eq!(evaluate("50% * 0.01", &[]), cents(1));
+        assert_eq!(evaluate("-50% * 0.01", &[]), cents(-1));
+        assert_eq!(evaluate("10 * 33.3333%", &[]), cents(333));
+    }
+
+    #[test]
+    fn formula_errors() {
+        assert_eq!(
+            evaluate("#3 * 2", &[(1, 100)]),
+            Err(FormulaError::Pending(3))
+        );
+        assert!(matches!(
+            evaluate("1 / 0", &[]),
+            Err(FormulaError::Invalid(_))
+        ));
+        assert!(matches!(
+            evaluate("1 +", &[]),
+            Err(FormulaError::Invalid(_))
+        ));
+        assert!(matches!(
+            evaluate("(1 + 2", &[]),
+            Err(FormulaError::Invalid(_))
+        ));
+        assert!(matches!(
+            evaluate("1.2.3", &[]),
+            Err(FormulaError::Invalid(_))
+        ));
+        assert!(matches!(
+            evaluate("2 x 3", &[]),
+            Err(FormulaError::Invalid(_))
+        ));
+        assert!(matches!(
+            evaluate("99999999999999999999 * 99999999999999999999", &[]),
+            Err(FormulaError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn template_forward_references() {
+        // the first item refers to the ones after it
+        let items = [
+            item(1, None, Some("#2 + #3")),
+            item(2, Some("#3 * 10%"), None),
+            item(3, Some("1000"), None),
+        ];
+        let amounts = evaluate_template("PAY", &items).unwrap();
+
+        assert_eq!(amounts[&1], (None, Some(Money::from_cents(110_000))));
+        assert_eq!(amounts[&2], (Some(Money::from_cents(10_000)), None));
+        assert_eq!(amounts[&3], (Some(Money::from_cents(100_000)), None));
+    }
+
+    #[test]
+    fn template_balancing_item() {
+        let items = [
+            item(1, Some("3000"), None),
+            item(2, None, Some("#1 * 20%")),
+            item(3, None, None),
+            item(4, Some("-50"), None),
+        ];
+        let amounts = evaluate_template("PAY", &items).unwrap();
+
+        // a negative debit is a credit
+        assert_eq!(amounts[&4], (None, Some(Money::from_cents(5_000))));
+        assert_eq!(amounts[&3], (None, Some(Money::from_cents(235_000))));
+    }
+
+    #[test]
+    fn template_errors() {
+        let circular = [item(1, Some("#2"), None), item(2, None, Some("#1"))];
+        assert!(evaluate_template("PAY", &circular).is_err());
+
+        let unknown = [item(1, Some("#5"), None), item(2, None, None)];
+        assert!(evaluate_template("PAY", &unknown).is_err());
+
+        let unbalanced = [item(1, Some("10"), None), item(2, None, Some("9.99"))];
+        assert!(evaluate_template("PAY", &unbalanced).is_err());
+
+        let two_balancing = [
+            item(1, Some("10"), None),
+            item(2, None, None),
+            item(3, None, None),
+        ];
+        assert!(evaluate_template("PAY", &two_balancing).is_err());
+    }
+
+    #[test]
+    fn schedule_anchors_on_the_first_occurrence() {
+        let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        let start = date(2024, 1, 31);
+
+        // a short month doesn't move the day of the later occurrences
+        assert_eq!(Schedule::Monthly.nth(start, 0), Some(start));
+        assert_eq!(Schedule::Monthly.nth(start, 1), Some(date(2024, 2, 29)));
+        assert_eq!(Schedule::Monthly.nth(start, 2), Some(date(2024, 3, 31)));
+        assert_eq!(Schedule::Monthly.nth(start, 3), Some(date(2024, 4, 30)));
+        assert_eq!(Schedule::Quarterly.nth(start, 1), Some(date(2024, 4, 30)));
+        assert_eq!(Schedule::Quarterly.nth(start, 2), Some(date(2024, 7, 31)));
+        assert_eq!(
+            Schedule::Yearly.nth(date(2024, 2, 29), 1),
+            Some(date(2025, 2, 28))
+        );
+        assert_eq!(
+            Schedule::Yearly.nth(date(2024, 2, 29), 4),
+            Some(date(2028, 2, 29))
+        );
+        assert_eq!(Schedule::Weekly.nth(start, 1), Some(date(2024, 2, 7)));
+        assert_eq!(Schedule::Biweekly.nth(start, 2), Some(date(2024, 2, 28)));
+    }
+
+    #[test]
+    fn parse_schedule() {
+        assert_eq!(" monthly ".parse(), Ok(Schedule::Monthly));
+        assert!("DAILY".parse::<Schedule>().is_err());
+    }
+}
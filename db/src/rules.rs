@@ -0,0 +1,227 @@
+use std::collections::BTreeSet;
+
+use regex::{Regex, RegexBuilder};
+use sqlx::Row;
+
+use crate::{CategorizationRule, Error, Export, Money, Query, Transaction, TransactionStore};
+
+enum Matcher {
+    /// Lowercased, since the substrings are case-insensitive.
+    Substring(String),
+    Regex(Regex),
+}
+
+pub struct Rule {
+    pub record: CategorizationRule,
+    /// The expense account of the rule's account type.
+    pub expense_account_key: Option<String>,
+    matcher: Matcher,
+}
+
+impl Rule {
+    /// Whether the rule fires for a transaction with the description and the (unsigned) amount, paid from the
+    /// account when it's known.
+    pub fn is_match(&self, description: &str, amount: Money, account_key: Option<&str>) -> bool {
+        let is_description = match &self.matcher {
+            Matcher::Substring(pattern) => description.to_lowercase().contains(pattern),
+            Matcher::Regex(regex) => regex.is_match(description),
+        };
+        let is_amount = !matches!(self.record.min_amount, Some(min) if amount < min)
+            && !matches!(self.record.max_amount, Some(max) if amount > max);
+        let is_account = match (&self.record.account_key, account_key) {
+            (Some(rule_account_key), Some(account_key)) => rule_account_key == account_key,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+
+        is_description && is_amount && is_account
+    }
+}
+
+/// The categorization rules, in the order they are tried.
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    /// The first rule that fires.
+    pub fn find(
+        &self,
+        description: &str,
+        amount: Money,
+        account_key: Option<&str>,
+    ) -> Option<&Rule> {
+        self.0
+            .iter()
+            .find(|rule| rule.is_match(description, amount, account_key))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A store that `apply_rules` assigned to a purchase.
+pub struct AppliedRule {
+    pub rule_id: i64,
+    pub description: String,
+    pub store: TransactionStore,
+}
+
+impl Transaction<'_> {
+    pub async fn get_rules(&mut self) -> Result<Rules, Error> {
+        let records: Vec<CategorizationRule> = sqlx::query_as(CategorizationRule::EXPORT_QUERY)
+            .fetch_all(&mut *self.0)
+            .await?;
+
+        let mut rules = Vec::with_capacity(records.len());
+        for record in records {
+            let matcher = if record.is_regex {
+                let regex = RegexBuilder::new(&record.pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|err| {
+                        Error::invalid(format!(
+                            "the pattern of rule {} is not a regular expression, {err}",
+                            record.rule_id
+                        ))
+                    })?;
+                Matcher::Regex(regex)
+            } else {
+                Matcher::Substring(record.pattern.to_lowercase())
+            };
+
+            let expense_account_key = match &record.account_type {
+                Some(account_type) => {
+                    let account_keys: Vec<String> = sqlx::query(
+                        r#"
+SELECT
+    account_key
+FROM
+    Account
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+WHERE
+    account_type = ?
+    AND account_kind = 'EXPENSE'
+ORDER BY
+    account_key
+"#,
+                    )
+                    .bind(account_type)
+                    .fetch_all(&mut *self.0)
+                    .await?
+                    .into_iter()
+                    .map(|row| row.get(0))
+                    .collect();
+
+                    // the type has to name a single account, rather than the one that sorts first
+                    match account_keys.as_slice() {
+                        [account_key] => Some(account_key.clone()),
+                        [] => {
+                            return Err(Error::invalid(format!(
+                                "the account type {account_type} of rule {} has no expense account",
+                                record.rule_id
+                            )))
+                        }
+                        _ => {
+                            return Err(Error::invalid(format!(
+                                "the account type {account_type} of rule {} has more than one expense account ({})",
+                                record.rule_id,
+                                account_keys.join(", ")
+                            )))
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            rules.push(Rule {
+                record,
+                expense_account_key,
+                matcher,
+            });
+        }
+
+        Ok(Rules(rules))
+    }
+
+    /// Assigns the store of the first rule that fires to the purchases without one, i.e. the ones flagged by
+    /// `check_transaction_store`. Only the given transactions are considered, when they are given.
+    pub async fn apply_rules(
+        &mut self,
+        transaction_ids: Option<&BTreeSet<i64>>,
+    ) -> Result<Vec<AppliedRule>, Error> {
+        let rules = self.get_rules().await?;
+        if rules.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // the paying side of the purchases, e.g. the credit to the credit card
+        let purchases = sqlx::query(
+            r#"
+SELECT
+    transaction_id,
+    description,
+    account_key,
+    ABS(credit_amount - debit_amount) AS amount
+FROM
+    CashbackTransaction
+    INNER JOIN Account USING (account_id)
+WHERE
+    transaction_id NOT IN (
+        SELECT
+            transaction_id
+        FROM
+            TransactionStore
+    )
+ORDER BY
+    transaction_id,
+    item_id
+"#,
+        )
+        .fetch_all(&mut *self.0)
+        .await?;
+
+        let mut seen = BTreeSet::new();
+        let mut applied = vec![];
+
+        for row in purchases {
+            let transaction_id: i64 = row.get(0);
+            if transaction_ids
+                .is_some_and(|transaction_ids| !transaction_ids.contains(&transaction_id))
+                || !seen.insert(transaction_id)
+            {
+                continue;
+            }
+
+            let description: String = row.get(1);
+            let account_key: String = row.get(2);
+            let amount: Money = row.get(3);
+
+            let rule = match rules.find(&description, amount, Some(&account_key)) {
+                Some(rule) => rule,
+                None => continue,
+            };
+            let store_key = match &rule.record.store_key {
+                Some(store_key) => store_key.clone(),
+                None => continue,
+            };
+
+            let store = TransactionStore {
+                transaction_id,
+                store_key,
+            };
+            if let Err(err) = self.execute(store.query()).await {
+                return Err(Error::from_record(err, &store));
+            }
+
+            applied.push(AppliedRule {
+                rule_id: rule.record.rule_id,
+                description,
+                store,
+            });
+        }
+
+        Ok(applied)
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use sqlx::Connection;
+
+use crate::{Db, Error, SqlResult};
+
+impl Db {
+    /// Snapshots are stored next to the ledger, i.e. `<ledger dir>/snapshots/<ledger name>/`.
+    pub fn snapshot_dir(path: &Path) -> PathBuf {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "data".to_string());
+
+        path.parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join("snapshots")
+            .join(stem)
+    }
+
+    /// Writes a consistent copy of the database, even while it is in use.
+    pub async fn backup(&mut self, target: &Path) -> Result<(), Error> {
+        if target.exists() {
+            return Err(Error::invalid(format!(
+                "{} already exists",
+                target.to_string_lossy()
+            )));
+        }
+
+        if let Some(parent) = target.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(Error::io(parent))?;
+            }
+        }
+
+        sqlx::query("VACUUM INTO ?")
+            .bind(target.to_string_lossy().to_string())
+            .execute(&mut self.0)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Saves a timestamped snapshot of the ledger at `path` into its snapshot directory.
+    pub async fn snapshot(&mut self, path: &Path) -> Result<PathBuf, Error> {
+        let name = Local::now().format("%Y%m%d-%H%M%S%.3f");
+        let target = Self::snapshot_dir(path).join(format!("{name}.sqlite3"));
+
+        self.backup(&target).await?;
+
+        Ok(target)
+    }
+
+    /// Lists the snapshots of the ledger at `path`, oldest first.
+    pub fn list_snapshots(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let dir = Self::snapshot_dir(path);
+
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) == Some("sqlite3") {
+                snapshots.push(path);
+            }
+        }
+
+        // names are timestamps, so sorting by name is sorting by time
+        snapshots.sort();
+
+        Ok(snapshots)
+    }
+
+    /// Deletes all but the `keep` most recent snapshots, returns the deleted ones.
+    pub fn prune_snapshots(path: &Path, keep: usize) -> std::io::Result<Vec<PathBuf>> {
+        let snapshots = Self::list_snapshots(path)?;
+        let num_pruned = snapshots.len().saturating_sub(keep);
+
+        let pruned: Vec<_> = snapshots.into_iter().take(num_pruned).collect();
+
+        for snapshot in &pruned {
+            fs::remove_file(snapshot)?;
+        }
+
+        Ok(pruned)
+    }
+
+    /// Replaces the ledger at `path` with `snapshot`. The current ledger is snapshotted first,
+    /// so a restore can be undone; the path of that snapshot is returned.
+    pub async fn restore(path: &Path, snapshot: &Path) -> Result<PathBuf, Error> {
+        if !snapshot.is_file() {
+            return Err(Error::invalid(format!(
+                "snapshot {} does not exist",
+                snapshot.to_string_lossy()
+            )));
+        }
+
+        let mut db = Self::from_path(path.to_path_buf()).await?;
+        let current = db.snapshot(path).await?;
+        db.close().await?;
+
+        // stale write-ahead log would be replayed on top of the restored file
+        for suffix in ["-wal", "-shm"] {
+            let mut file = path.as_os_str().to_owned();
+            file.push(suffix);
+
+            let file = PathBuf::from(file);
+            if file.exists() {
+                fs::remove_file(&file).map_err(Error::io(&file))?;
+            }
+        }
+
+        fs::copy(snapshot, path).map_err(Error::io(path))?;
+
+        // bring older snapshots up to the latest schema
+        Self::from_path(path.to_path_buf()).await?.close().await?;
+
+        Ok(current)
+    }
+
+    pub async fn close(self) -> SqlResult<()> {
+        self.0.close().await
+    }
+}
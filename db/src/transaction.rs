@@ -1,15 +1,13 @@
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    fs::File,
-    path::PathBuf,
-};
+use std::{collections::BTreeMap, path::Path};
 
-use common::{deserialize_into_map, Id};
+use common::Id;
 use owo_colors::OwoColorize;
 use serde::de;
 use sqlx::Sqlite;
 
-use crate::{Query, SqlQuery, SqlResult, Transaction};
+use crate::{
+    read_csv_into_map, write_csv, Error, Export, Prune, Query, SqlQuery, SqlResult, Transaction,
+};
 
 impl Transaction<'_> {
     pub async fn commit(self) -> SqlResult<()> {
@@ -27,80 +25,103 @@ impl Transaction<'_> {
         Ok(query.execute(&mut *self.0).await?)
     }
 
-    pub async fn upsert_all<T>(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<BTreeMap<T::IdType, T>, Box<dyn std::error::Error>>
+    pub async fn upsert_all<T>(&mut self, csv_path: &Path) -> Result<BTreeMap<T::IdType, T>, Error>
+    where
+        T: Id + de::DeserializeOwned + Query + std::fmt::Debug,
+    {
+        Ok(self
+            .upsert_all_in_order::<T>(csv_path)
+            .await?
+            .into_iter()
+            .map(|record| (record.id(), record))
+            .collect())
+    }
+
+    pub async fn upsert_all_in_order<T>(&mut self, csv_path: &Path) -> Result<Vec<T>, Error>
     where
         T: Id + de::DeserializeOwned + Query + std::fmt::Debug,
     {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<T>(csv_path).await?;
-
-        for (_, record) in &parsed_records {
-            match self.execute(record.query()).await {
-                Ok(result) => {
-                    if result.rows_affected() != 1 {
-                        println!(
-                            "{}: no row was affected, record: {:?}",
-                            "Warning".bold().yellow(),
-                            record
-                        );
-                    }
-                }
-                Err(err) => {
-                    return Err(format!("{}, record: {:?}", err.to_string(), record).into());
-                }
+        let mut parsed_records: Vec<_> = read_csv_into_map::<T>(csv_path)?.into_values().collect();
+        parsed_records.sort_by_key(|(line, _)| *line);
+
+        for (line, record) in &parsed_records {
+            // no row is affected when the record is unchanged
+            if let Err(err) = self.execute(record.query()).await {
+                return Err(Error::from_record(err, record)
+                    .of_entry(record.entry_id())
+                    .at(csv_path, *line));
             }
         }
 
-        Ok(parsed_records)
+        Ok(parsed_records
+            .into_iter()
+            .map(|(_, record)| record)
+            .collect())
     }
 
-    pub async fn upsert_all_in_order<T>(
+    /// Deletes the rows that aren't in `parsed_records`, i.e. the ones removed from the CSV file.
+    /// Rows that are still referenced (e.g. an account with entries) fail on the foreign keys.
+    pub async fn prune_all<T>(
         &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<Vec<T>, Box<dyn std::error::Error>>
+        parsed_records: &BTreeMap<T::IdType, T>,
+    ) -> Result<usize, Error>
     where
-        T: Id + de::DeserializeOwned + Query + std::fmt::Debug,
+        T: Id + Export + Prune + Query + std::fmt::Debug,
     {
-        println!("updating data with {}", csv_path.to_string_lossy());
-
-        let mut parsed_records = Vec::new();
+        let existing_records = sqlx::query_as::<_, T>(T::EXPORT_QUERY)
+            .fetch_all(&mut *self.0)
+            .await?;
 
-        let file = File::open(csv_path)?;
-        let mut set = BTreeSet::new();
+        let mut num_deleted = 0;
 
-        for result in csv::Reader::from_reader(file).deserialize::<T>() {
-            let record = result?;
-            let id = record.id();
-
-            if !set.insert(id.clone()) {
-                return Err(format!("deserialization failed due to duplicated id: {id:#?}").into());
+        for record in existing_records {
+            if parsed_records.contains_key(&record.id()) {
+                continue;
             }
 
-            parsed_records.push(record);
-        }
-
-        for record in &parsed_records {
-            match self.execute(record.query()).await {
-                Ok(result) => {
-                    if result.rows_affected() != 1 {
-                        println!(
-                            "{}: no row was affected, record: {:?}",
-                            "Warning".bold().yellow(),
-                            record
-                        );
-                    }
+            match self.execute(record.delete_query()).await {
+                Ok(_) => {
+                    println!("{}: {:?}", "Deleted".bold().red(), record);
+                    num_deleted += 1;
                 }
                 Err(err) => {
-                    return Err(format!("{}, record: {:?}", err.to_string(), record).into());
+                    return Err(Error::from_record(err, &record).of_entry(record.entry_id()));
                 }
             }
         }
 
-        Ok(parsed_records)
+        Ok(num_deleted)
+    }
+
+    /// Prunes with the records of the CSV file, for the callers that prune after upserting other tables.
+    pub async fn prune_csv<T>(&mut self, csv_path: &Path) -> Result<usize, Error>
+    where
+        T: Id + Export + Prune + Query + de::DeserializeOwned + std::fmt::Debug,
+    {
+        let parsed_records = read_csv_into_map::<T>(csv_path)?
+            .into_iter()
+            .map(|(id, (_, record))| (id, record))
+            .collect();
+
+        self.prune_all::<T>(&parsed_records).await
+    }
+
+    /// Writes the records into a CSV file that `upsert_all` (or the custom upsert) reads back.
+    pub async fn export_all<T>(&mut self, csv_path: &Path) -> Result<usize, Error>
+    where
+        T: Export + de::DeserializeOwned,
+    {
+        println!("exporting data to {}", csv_path.to_string_lossy());
+
+        let records = sqlx::query_as::<_, T>(T::EXPORT_QUERY)
+            .fetch_all(&mut *self.0)
+            .await?;
+
+        write_csv(csv_path, &records)?;
+
+        Ok(records.len())
     }
 }
 
@@ -1,7 +1,13 @@
-use crate::{SqlResult, Transaction};
+use chrono::{DateTime, Local};
+
+use crate::{Error, Price, Transaction};
 
 impl Transaction<'_> {
-    pub async fn update_security_price(&mut self, security_id: i64, price: f64) -> SqlResult<()> {
+    pub async fn update_security_price(
+        &mut self,
+        security_id: i64,
+        price: Price,
+    ) -> Result<(), Error> {
         let result = sqlx::query!(
             "UPDATE Security SET price = ? WHERE security_id = ?",
             price,
@@ -10,33 +16,116 @@ impl Transaction<'_> {
         .execute(&mut *self.0)
         .await?;
 
-        assert_eq!(
-            result.rows_affected(),
-            1,
-            "sanity check: was not able to update price"
-        );
+        if result.rows_affected() != 1 {
+            return Err(Error::UnknownKey {
+                path: None,
+                line: None,
+                key: "SECURITY.security_id".to_string(),
+                record: format!("security_id: {security_id}, price: {price}"),
+            });
+        }
 
         Ok(())
     }
 
-    pub async fn update_currency_exchange_rate(
+    /// Records the price of a security on the given date. The current quote follows the most recent record.
+    pub async fn set_security_price(
         &mut self,
-        currency_id: i64,
-        exchange_rate: f64,
-    ) -> SqlResult<()> {
+        ticker: &str,
+        date: &DateTime<Local>,
+        price: Price,
+    ) -> Result<(), Error> {
+        let timestamp = date.timestamp();
+
+        let result = sqlx::query!(
+            r#"
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+SELECT
+    security_id,
+    ?,
+    ?
+FROM
+    SECURITY
+WHERE
+    ticker = ? ON CONFLICT(security_id, date) DO
+UPDATE
+SET
+    price = excluded.price
+"#,
+            timestamp,
+            price,
+            ticker
+        )
+        .execute(&mut *self.0)
+        .await?;
+
+        // nothing is inserted when the ticker doesn't exist
+        if result.rows_affected() != 1 {
+            return Err(Error::UnknownKey {
+                path: None,
+                line: None,
+                key: "SECURITY.ticker".to_string(),
+                record: format!("ticker: {ticker}, date: {date}, price: {price}"),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Records how much of the `quote` currency one unit of the `base` currency buys on the given date.
+    pub async fn set_exchange_rate(
+        &mut self,
+        base_currency: &str,
+        quote_currency: &str,
+        date: &DateTime<Local>,
+        rate: f64,
+    ) -> Result<(), Error> {
+        let timestamp = date.timestamp();
+
         let result = sqlx::query!(
-            "UPDATE Currency SET market_exchange_rate = ? WHERE currency_id = ?",
-            exchange_rate,
-            currency_id
+            r#"
+INSERT INTO
+    ExchangeRate (
+        base_currency_id,
+        quote_currency_id,
+        date,
+        rate
+    )
+SELECT
+    Base.currency_id,
+    Quote.currency_id,
+    ?,
+    ?
+FROM
+    Currency Base
+    INNER JOIN Currency Quote
+WHERE
+    Base.currency = ?
+    AND Quote.currency = ? ON CONFLICT(base_currency_id, quote_currency_id, date) DO
+UPDATE
+SET
+    rate = excluded.rate
+"#,
+            timestamp,
+            rate,
+            base_currency,
+            quote_currency
         )
         .execute(&mut *self.0)
         .await?;
 
-        assert_eq!(
-            result.rows_affected(),
-            1,
-            "sanity check: was not able to update exchange rate"
-        );
+        // nothing is inserted when either currency doesn't exist
+        if result.rows_affected() != 1 {
+            return Err(Error::UnknownKey {
+                path: None,
+                line: None,
+                key: "Currency.currency".to_string(),
+                record: format!(
+                    "base_currency: {base_currency}, quote_currency: {quote_currency}, date: {date}, rate: {rate}"
+                ),
+            });
+        }
 
         Ok(())
     }
@@ -1,9 +1,9 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Account {
     pub account_key: String,
     pub account_subtype: String,
@@ -21,7 +21,7 @@ impl Id for Account {
 }
 
 impl Query for Account {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -59,10 +59,10 @@ SET
     account_type_id = excluded.account_type_id,
     account_name = excluded.account_name
 WHERE
-    account_subtype_id <> excluded.account_subtype_id
-    OR account_type_id <> excluded.account_type_id
-    OR account_type_id <> excluded.account_type_id
-    OR account_name <> excluded.account_name
+    account_subtype_id IS NOT excluded.account_subtype_id
+    OR account_type_id IS NOT excluded.account_type_id
+    OR account_type_id IS NOT excluded.account_type_id
+    OR account_name IS NOT excluded.account_name
 "#,
             self.account_key,
             self.account_subtype,
@@ -71,3 +71,88 @@ WHERE
         )
     }
 }
+
+impl Prune for Account {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    Account
+WHERE
+    account_key = ?
+"#,
+            self.account_key
+        )
+    }
+}
+
+impl Export for Account {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_key,
+    account_subtype,
+    account_type,
+    NULL AS stock_ticker,
+    account_name
+FROM
+    Account
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountType USING (account_type_id)
+WHERE
+    -- accounts of holders are created by their upserts
+    account_id NOT IN (
+        SELECT
+            account_id
+        FROM
+            CashAccountEntry
+        UNION
+        ALL
+        SELECT
+            account_id
+        FROM
+            CreditCardEntry
+        UNION
+        ALL
+        SELECT
+            account_id
+        FROM
+            StockAccountEntry
+        UNION
+        ALL
+        SELECT
+            account_id
+        FROM
+            GicEntry
+        UNION
+        ALL
+        SELECT
+            account_id
+        FROM
+            IncomeAccountMapping
+        UNION
+        ALL
+        -- created by close-period
+        SELECT
+            account_id
+        FROM
+            RetainedEarningsAccount
+    )
+ORDER BY
+    account_key
+"#;
+
+    const DIFF_QUERY: &'static str = r#"
+SELECT
+    account_key,
+    account_subtype,
+    account_type,
+    NULL AS stock_ticker,
+    account_name
+FROM
+    Account
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountType USING (account_type_id)
+ORDER BY
+    account_key
+"#;
+}
@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct AccountSubtype {
     #[serde(deserialize_with = "string_trim")]
     pub account_kind: String,
@@ -21,7 +21,7 @@ impl Id for AccountSubtype {
 }
 
 impl Query for AccountSubtype {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -45,10 +45,37 @@ UPDATE
 SET
     account_kind_id = excluded.account_kind_id
 WHERE
-    excluded.account_kind_id <> account_kind_id;
+    excluded.account_kind_id IS NOT account_kind_id;
 "#,
             self.account_kind,
             self.account_subtype,
         )
     }
 }
+
+impl Export for AccountSubtype {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_kind,
+    account_subtype
+FROM
+    AccountSubtype
+    INNER JOIN AccountKind USING (account_kind_id)
+WHERE
+    -- created by close-period
+    account_subtype <> 'RETAINED-EARNINGS'
+ORDER BY
+    account_subtype
+"#;
+
+    const DIFF_QUERY: &'static str = r#"
+SELECT
+    account_kind,
+    account_subtype
+FROM
+    AccountSubtype
+    INNER JOIN AccountKind USING (account_kind_id)
+ORDER BY
+    account_subtype
+"#;
+}
@@ -1,13 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{deserialize_into_map, Id};
-use owo_colors::OwoColorize;
-use serde::Deserialize;
+use common::Id;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Transaction;
+use crate::{read_csv_into_map, Error, Export, Transaction};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct AccountType {
     #[serde(deserialize_with = "string_trim")]
     pub account_type: String,
@@ -22,26 +21,28 @@ impl Id for AccountType {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_account_type(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_account_type(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<AccountType>(csv_path).await?;
+        let parsed_records = read_csv_into_map::<AccountType>(csv_path)?;
 
-        for (_, record) in parsed_records {
-            self.upsert_account_type_helper(&record.account_type)
-                .await?;
+        for (line, record) in parsed_records.values() {
+            self.upsert_account_type_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
         }
 
         Ok(())
     }
 
-    pub async fn upsert_account_type_helper(
-        &mut self,
-        account_type: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    async fn upsert_account_type_record(&mut self, record: &AccountType) -> Result<(), Error> {
+        self.upsert_account_type_helper(&record.account_type)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn upsert_account_type_helper(&mut self, account_type: &str) -> Result<(), Error> {
         println!("inserting account type {account_type}");
 
         let result = sqlx::query!(
@@ -56,17 +57,51 @@ VALUES
         .execute(&mut *self.0)
         .await;
 
-        match result {
-            Ok(result) => {
-                if result.rows_affected() != 1 {
-                    println!(
-                        "{}: account type already existed",
-                        "Warning".bold().yellow(),
-                    );
-                }
-                Ok(())
-            }
-            Err(err) => Err(format!("{}, record: {}", err.to_string(), account_type).into()),
-        }
+        // an existing account type is left as is
+        result
+            .map(|_| ())
+            .map_err(|err| Error::from_record(err, &account_type))
     }
 }
+
+impl Export for AccountType {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_type
+FROM
+    AccountType
+WHERE
+    -- account types of products are created by their upserts
+    account_type_id NOT IN (
+        SELECT
+            account_type_id
+        FROM
+            CashAccountProduct
+        UNION
+        ALL
+        SELECT
+            account_type_id
+        FROM
+            CreditCardProduct
+        UNION
+        ALL
+        SELECT
+            account_type_id
+        FROM
+            IncomeAccount
+    )
+    -- created by close-period
+    AND account_type <> 'RETAINED-EARNINGS'
+ORDER BY
+    account_type
+"#;
+
+    const DIFF_QUERY: &'static str = r#"
+SELECT
+    account_type
+FROM
+    AccountType
+ORDER BY
+    account_type
+"#;
+}
@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct AssetAllocation {
     #[serde(deserialize_with = "string_trim")]
     pub ticker: String,
@@ -22,7 +22,7 @@ impl Id for AssetAllocation {
 }
 
 impl Query for AssetAllocation {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -58,7 +58,7 @@ UPDATE
 SET
     weight = excluded.weight
 WHERE
-    weight <> excluded.weight
+    weight IS NOT excluded.weight
 "#,
             self.asset_class_name,
             self.ticker,
@@ -66,3 +66,49 @@ WHERE
         )
     }
 }
+
+impl Prune for AssetAllocation {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    AssetAllocation
+WHERE
+    security_id = (
+        SELECT
+            security_id
+        FROM
+            SECURITY
+        WHERE
+            ticker = ?
+    )
+    AND asset_class_name_id = (
+        SELECT
+            asset_class_name_id
+        FROM
+            AssetClassName
+        WHERE
+            asset_class_name = ?
+    )
+"#,
+            self.ticker,
+            self.asset_class_name
+        )
+    }
+}
+
+impl Export for AssetAllocation {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    ticker,
+    asset_class_name,
+    weight
+FROM
+    AssetAllocation
+    INNER JOIN SECURITY USING (security_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+ORDER BY
+    ticker,
+    asset_class_name
+"#;
+}
@@ -1,12 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::{Query, Transaction};
+use crate::{Error, Export, Query, Transaction};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct AssetClass {
     #[serde(deserialize_with = "string_trim")]
     pub person: String,
@@ -26,8 +26,7 @@ impl Id for AssetClass {
 }
 
 impl Query for AssetClass {
-    fn query(&self) -> crate::SqlQuery {
-        println!("{:#?}", self);
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -85,8 +84,8 @@ SET
     weight = excluded.weight,
     parent_id = excluded.parent_id
 WHERE
-    weight <> excluded.weight
-    OR parent_id <> excluded.parent_id
+    weight IS NOT excluded.weight
+    OR parent_id IS NOT excluded.parent_id
 "#,
             self.person,
             self.parent,
@@ -98,11 +97,8 @@ WHERE
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_asset_class(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.upsert_all_in_order::<AssetClass>(&csv_path).await?;
+    pub async fn upsert_asset_class(&mut self, csv_path: &Path) -> Result<(), Error> {
+        self.upsert_all_in_order::<AssetClass>(csv_path).await?;
 
         struct Check {
             is_each_person_has_model: bool,
@@ -140,6 +136,52 @@ SELECT
             }
         }
 
-        Err("Someone does not have a model (AssetClass)".into())
+        Err(Error::invalid("Someone does not have a model (AssetClass)"))
     }
 }
+
+impl Export for AssetClass {
+    const EXPORT_QUERY: &'static str = r#"
+WITH RECURSIVE Tree AS (
+    SELECT
+        asset_class_id,
+        0 AS depth
+    FROM
+        AssetClass
+    WHERE
+        parent_id IS NULL
+    UNION
+    ALL
+    SELECT
+        AssetClass.asset_class_id,
+        depth + 1
+    FROM
+        AssetClass
+        INNER JOIN Tree ON AssetClass.parent_id = Tree.asset_class_id
+)
+SELECT
+    person_key AS person,
+    -- the root doesn't have a parent
+    COALESCE(Parent.asset_class_name, '') AS parent,
+    AssetClassName.asset_class_name,
+    weight
+FROM
+    Tree
+    INNER JOIN AssetClass USING (asset_class_id)
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+    LEFT JOIN (
+        SELECT
+            asset_class_id AS parent_id,
+            asset_class_name
+        FROM
+            AssetClass
+            INNER JOIN AssetClassName USING (asset_class_name_id)
+    ) Parent USING (parent_id)
+-- the parents come first, as the records are upserted in order
+ORDER BY
+    depth,
+    person,
+    AssetClassName.asset_class_name
+"#;
+}
@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct AssetClassName {
     #[serde(deserialize_with = "string_trim")]
     pub asset_class_name: String,
@@ -19,7 +19,7 @@ impl Id for AssetClassName {
 }
 
 impl Query for AssetClassName {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT
@@ -31,3 +31,28 @@ VALUES
         )
     }
 }
+
+impl Prune for AssetClassName {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    AssetClassName
+WHERE
+    asset_class_name = ?
+"#,
+            self.asset_class_name
+        )
+    }
+}
+
+impl Export for AssetClassName {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    asset_class_name
+FROM
+    AssetClassName
+ORDER BY
+    asset_class_name
+"#;
+}
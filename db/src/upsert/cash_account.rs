@@ -1,13 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{deserialize_into_map, Id};
-use owo_colors::OwoColorize;
-use serde::Deserialize;
+use common::Id;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::{SqlResult, Transaction};
+use crate::{read_csv_into_map, Error, Export, Money, SqlResult, Transaction};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct CashAccountProduct {
     #[serde(deserialize_with = "string_trim")]
     pub account_name: String,
@@ -19,7 +18,7 @@ pub struct CashAccountProduct {
     pub tax_shelter_type: String,
     #[serde(deserialize_with = "string_trim")]
     pub currency: String,
-    pub min_balance_waiver: f64,
+    pub min_balance_waiver: Money,
     #[serde(default = "i64max")]
     pub inactive_fee_months: i64,
 }
@@ -38,28 +37,36 @@ impl Id for CashAccountProduct {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_cash_account(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_cash_account(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<CashAccountProduct>(csv_path).await?;
-
-        for (_, record) in &parsed_records {
-            self.upsert_account_type_helper(&record.account_type)
-                .await?;
+        let parsed_records = read_csv_into_map::<CashAccountProduct>(csv_path)?;
 
-            self.upsert_cash_cash_account_helper(&record).await?;
+        for (line, record) in parsed_records.values() {
+            self.upsert_cash_account_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
         }
 
         Ok(())
     }
 
+    async fn upsert_cash_account_record(
+        &mut self,
+        record: &CashAccountProduct,
+    ) -> Result<(), Error> {
+        self.upsert_account_type_helper(&record.account_type)
+            .await?;
+
+        self.upsert_cash_cash_account_helper(record).await?;
+
+        Ok(())
+    }
+
     async fn upsert_cash_cash_account_helper(
         &mut self,
         record: &CashAccountProduct,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), Error> {
         println!("> inserting cash account");
 
         let result = sqlx::query!(
@@ -121,12 +128,12 @@ SET
     inactive_fee_months = excluded.inactive_fee_months,
     account_name = excluded.account_name
 WHERE
-    institution_id <> excluded.institution_id
-    OR tax_shelter_type_id <> excluded.tax_shelter_type_id
-    OR currency_id <> excluded.currency_id
-    OR min_balance_waiver <> excluded.min_balance_waiver
-    OR inactive_fee_months <> excluded.inactive_fee_months
-    OR account_name <> excluded.account_name
+    institution_id IS NOT excluded.institution_id
+    OR tax_shelter_type_id IS NOT excluded.tax_shelter_type_id
+    OR currency_id IS NOT excluded.currency_id
+    OR min_balance_waiver IS NOT excluded.min_balance_waiver
+    OR inactive_fee_months IS NOT excluded.inactive_fee_months
+    OR account_name IS NOT excluded.account_name
 "#,
             record.account_type,
             record.institution_name,
@@ -139,22 +146,9 @@ WHERE
         .execute(&mut *self.0)
         .await;
 
-        match result {
-            Ok(result) => {
-                if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-                }
-
-                Ok(())
-            }
-            Err(err) => {
-                return Err(format!("{}, record: {:?}", err.to_string(), record).into());
-            }
-        }
+        result
+            .map(|_| ())
+            .map_err(|err| Error::from_record(err, record))
     }
 
     pub async fn has_cash_account(&mut self, account_type: &str) -> SqlResult<bool> {
@@ -179,3 +173,24 @@ SELECT
         Ok(result.is_account_exist)
     }
 }
+
+impl Export for CashAccountProduct {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_name,
+    account_type,
+    institution_name,
+    tax_shelter_type,
+    currency,
+    min_balance_waiver,
+    inactive_fee_months
+FROM
+    CashAccountProduct
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN Institution USING (institution_id)
+    INNER JOIN TaxShelterType USING (tax_shelter_type_id)
+    INNER JOIN Currency USING (currency_id)
+ORDER BY
+    account_type
+"#;
+}
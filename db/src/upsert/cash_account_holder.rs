@@ -1,20 +1,19 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{bool_from_str, deserialize_into_map, Id};
-use owo_colors::OwoColorize;
-use serde::Deserialize;
+use common::{bool_from_str, Id};
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 use sqlx::Sqlite;
 
-use crate::{SqlResult, Transaction};
+use crate::{read_csv_into_map, Error, Export, Money, SqlResult, Transaction};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct CashAccountHolder {
     #[serde(deserialize_with = "string_trim")]
     pub person_key: String,
     #[serde(deserialize_with = "string_trim")]
     pub account_type: String,
-    pub emergency_target: f64,
+    pub emergency_target: Money,
     #[serde(deserialize_with = "bool_from_str")]
     pub is_closed: bool,
 }
@@ -34,32 +33,40 @@ impl CashAccountHolder {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_cash_account_holder(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_cash_account_holder(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<CashAccountHolder>(csv_path).await?;
+        let parsed_records = read_csv_into_map::<CashAccountHolder>(csv_path)?;
 
-        for (_, record) in &parsed_records {
-            let account_key = record.to_account_key();
+        for (line, record) in parsed_records.values() {
+            self.upsert_cash_account_holder_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
+        }
 
-            println!(
-                "> inserting supplementary accounts into Account {}",
-                account_key
-            );
-            self.upsert_cash_account_helper(record, &account_key)
-                .await?;
+        Ok(())
+    }
+
+    async fn upsert_cash_account_holder_record(
+        &mut self,
+        record: &CashAccountHolder,
+    ) -> Result<(), Error> {
+        let account_key = record.to_account_key();
 
-            println!("> inserting account holder");
-            let cash_account_holder_id = self.upsert_cash_account_holder_helper(record).await?;
+        println!(
+            "> inserting supplementary accounts into Account {}",
+            account_key
+        );
+        self.upsert_cash_account_helper(record, &account_key)
+            .await?;
 
-            if let Some(cash_account_holder_id) = cash_account_holder_id {
-                println!("> upsert mapping");
-                self.upsert_cash_account_mapping_helper(cash_account_holder_id, &account_key)
-                    .await?;
-            }
+        println!("> inserting account holder");
+        let cash_account_holder_id = self.upsert_cash_account_holder_helper(record).await?;
+
+        if let Some(cash_account_holder_id) = cash_account_holder_id {
+            println!("> upsert mapping");
+            self.upsert_cash_account_mapping_helper(cash_account_holder_id, &account_key)
+                .await?;
         }
 
         Ok(())
@@ -68,7 +75,7 @@ impl Transaction<'_> {
     async fn upsert_cash_account_holder_helper(
         &mut self,
         record: &CashAccountHolder,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT INTO
@@ -104,8 +111,8 @@ SET
     emergency_target = excluded.emergency_target,
     is_closed = excluded.is_closed
 WHERE
-    emergency_target <> excluded.emergency_target
-    OR is_closed <> excluded.is_closed
+    emergency_target IS NOT excluded.emergency_target
+    OR is_closed IS NOT excluded.is_closed
 "#,
             record.person_key,
             record.account_type,
@@ -118,20 +125,12 @@ WHERE
         match result {
             Ok(result) => {
                 if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => {
-                return Err(format!("{}, record: {:?}", err.to_string(), record).into());
-            }
+            Err(err) => Err(Error::from_record(err, record)),
         }
     }
 
@@ -163,7 +162,7 @@ UPDATE
 SET
     account_id = excluded.account_id
 WHERE
-    account_id <> excluded.account_id
+    account_id IS NOT excluded.account_id
 "#,
             cash_account_holder_id,
             search_term
@@ -176,7 +175,7 @@ WHERE
         &mut self,
         record: &CashAccountHolder,
         account_key: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), Error> {
         let result = sqlx::query!(
             r#"
 INSERT INTO
@@ -225,9 +224,9 @@ SET
     account_subtype_id = excluded.account_subtype_id,
     account_name = excluded.account_name
 WHERE
-    account_type_id <> excluded.account_type_id
-    OR account_subtype_id <> excluded.account_subtype_id
-    OR account_name <> excluded.account_name
+    account_type_id IS NOT excluded.account_type_id
+    OR account_subtype_id IS NOT excluded.account_subtype_id
+    OR account_name IS NOT excluded.account_name
 "#,
             account_key,
             record.account_type,
@@ -235,22 +234,25 @@ WHERE
         .execute(&mut *self.0)
         .await;
 
-        match result {
-            Ok(result) => {
-                // cash, fees, interest, open balance (digit & fiat), withholding tax, bonus, forex (revenue, expense)
-                const NUM_SUBTYPE_ACCOUNTS: u64 = 9;
-
-                if result.rows_affected() != NUM_SUBTYPE_ACCOUNTS {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-                }
-
-                Ok(())
-            }
-            Err(err) => Err(format!("{}, record: {:?}", err.to_string(), record).into()),
-        }
+        result
+            .map(|_| ())
+            .map_err(|err| Error::from_record(err, record))
     }
 }
+
+impl Export for CashAccountHolder {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    person_key,
+    account_type,
+    emergency_target,
+    is_closed
+FROM
+    CashAccountHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+ORDER BY
+    person_key,
+    account_type
+"#;
+}
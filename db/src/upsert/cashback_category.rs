@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct CashbackCategory {
     #[serde(deserialize_with = "string_trim")]
     pub account_type: String,
@@ -25,7 +25,7 @@ impl Id for CashbackCategory {
 }
 
 impl Query for CashbackCategory {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -58,7 +58,7 @@ UPDATE
 SET
     cashback_rate = excluded.cashback_rate
 WHERE
-    cashback_rate <> excluded.cashback_rate
+    cashback_rate IS NOT excluded.cashback_rate
 "#,
             self.account_type,
             self.cashback_category_name,
@@ -66,3 +66,49 @@ WHERE
         )
     }
 }
+
+impl Prune for CashbackCategory {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    CashbackCategory
+WHERE
+    account_type_id = (
+        SELECT
+            account_type_id
+        FROM
+            AccountType
+        WHERE
+            account_type = ?
+    )
+    AND cashback_category_name_id = (
+        SELECT
+            cashback_category_name_id
+        FROM
+            CashbackCategoryName
+        WHERE
+            cashback_category_name = ?
+    )
+"#,
+            self.account_type,
+            self.cashback_category_name
+        )
+    }
+}
+
+impl Export for CashbackCategory {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_type,
+    cashback_category_name,
+    cashback_rate
+FROM
+    CashbackCategory
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN CashbackCategoryName USING (cashback_category_name_id)
+ORDER BY
+    account_type,
+    cashback_category_name
+"#;
+}
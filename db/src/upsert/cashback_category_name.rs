@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct CashbackCategoryName {
     #[serde(deserialize_with = "string_trim")]
     pub cashback_category_name: String,
@@ -19,7 +19,7 @@ impl Id for CashbackCategoryName {
 }
 
 impl Query for CashbackCategoryName {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT
@@ -31,3 +31,28 @@ VALUES
         )
     }
 }
+
+impl Prune for CashbackCategoryName {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    CashbackCategoryName
+WHERE
+    cashback_category_name = ?
+"#,
+            self.cashback_category_name
+        )
+    }
+}
+
+impl Export for CashbackCategoryName {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    cashback_category_name
+FROM
+    CashbackCategoryName
+ORDER BY
+    cashback_category_name
+"#;
+}
@@ -0,0 +1,158 @@
+use common::{bool_from_str, Id};
+use serde::{Deserialize, Serialize};
+use serde_trim::string_trim;
+
+use crate::{Export, Money, Prune, Query};
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct CategorizationRule {
+    pub rule_id: i64,
+    #[serde(deserialize_with = "string_trim")]
+    pub pattern: String,
+    #[serde(deserialize_with = "bool_from_str")]
+    pub is_regex: bool,
+    pub min_amount: Option<Money>,
+    pub max_amount: Option<Money>,
+    pub account_key: Option<String>,
+    pub store_key: Option<String>,
+    pub account_type: Option<String>,
+}
+
+impl Id for CategorizationRule {
+    type IdType = i64;
+
+    fn id(&self) -> Self::IdType {
+        self.rule_id
+    }
+}
+
+impl Query for CategorizationRule {
+    fn query(&self) -> crate::SqlQuery<'_> {
+        // the keys are optional, so an unknown key is turned into an id that fails the foreign key instead of NULL
+        sqlx::query!(
+            r#"
+INSERT INTO
+    CategorizationRule (
+        rule_id,
+        pattern,
+        is_regex,
+        min_amount,
+        max_amount,
+        account_id,
+        store_id,
+        account_type_id
+    )
+VALUES
+    (
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        CASE
+            WHEN ?6 IS NULL THEN NULL
+            ELSE COALESCE(
+                (
+                    SELECT
+                        account_id
+                    FROM
+                        Account
+                    WHERE
+                        account_key = ?6
+                ),
+                -1
+            )
+        END,
+        CASE
+            WHEN ?7 IS NULL THEN NULL
+            ELSE COALESCE(
+                (
+                    SELECT
+                        store_id
+                    FROM
+                        Store
+                    WHERE
+                        store_key = ?7
+                ),
+                -1
+            )
+        END,
+        CASE
+            WHEN ?8 IS NULL THEN NULL
+            ELSE COALESCE(
+                (
+                    SELECT
+                        account_type_id
+                    FROM
+                        AccountType
+                    WHERE
+                        account_type = ?8
+                ),
+                -1
+            )
+        END
+    ) ON CONFLICT (rule_id) DO
+UPDATE
+SET
+    pattern = excluded.pattern,
+    is_regex = excluded.is_regex,
+    min_amount = excluded.min_amount,
+    max_amount = excluded.max_amount,
+    account_id = excluded.account_id,
+    store_id = excluded.store_id,
+    account_type_id = excluded.account_type_id
+WHERE
+    pattern IS NOT excluded.pattern
+    OR is_regex IS NOT excluded.is_regex
+    OR min_amount IS NOT excluded.min_amount
+    OR max_amount IS NOT excluded.max_amount
+    OR account_id IS NOT excluded.account_id
+    OR store_id IS NOT excluded.store_id
+    OR account_type_id IS NOT excluded.account_type_id
+"#,
+            self.rule_id,
+            self.pattern,
+            self.is_regex,
+            self.min_amount,
+            self.max_amount,
+            self.account_key,
+            self.store_key,
+            self.account_type
+        )
+    }
+}
+
+impl Prune for CategorizationRule {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    CategorizationRule
+WHERE
+    rule_id = ?
+"#,
+            self.rule_id
+        )
+    }
+}
+
+impl Export for CategorizationRule {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    rule_id,
+    pattern,
+    is_regex,
+    min_amount,
+    max_amount,
+    account_key,
+    store_key,
+    account_type
+FROM
+    CategorizationRule
+    LEFT JOIN Account USING (account_id)
+    LEFT JOIN Store USING (store_id)
+    LEFT JOIN AccountType ON AccountType.account_type_id = CategorizationRule.account_type_id
+ORDER BY
+    rule_id
+"#;
+}
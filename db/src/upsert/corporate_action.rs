@@ -0,0 +1,140 @@
+use common::{excel_date_format, serialize_excel_date, Id};
+use serde::{Deserialize, Serialize};
+use serde_trim::string_trim;
+
+use crate::{Export, Prune, Query, Units};
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct CorporateAction {
+    #[serde(deserialize_with = "string_trim")]
+    pub ticker: String,
+    /// The effective date, the units held before it are converted.
+    #[serde(
+        deserialize_with = "excel_date_format",
+        serialize_with = "serialize_excel_date"
+    )]
+    pub date: i64,
+    /// One of SPLIT, CONSOLIDATION, TICKER-CHANGE or MERGER.
+    #[serde(deserialize_with = "string_trim")]
+    pub action: String,
+    /// `old_units` of the security become `new_units`, e.g. 1 and 4 for a 4-for-1 split.
+    pub old_units: Units,
+    pub new_units: Units,
+    /// The security that the units become, for a ticker change or a merger.
+    pub new_ticker: Option<String>,
+}
+
+impl Id for CorporateAction {
+    type IdType = (String, i64);
+
+    fn id(&self) -> Self::IdType {
+        (self.ticker.clone(), self.date)
+    }
+}
+
+impl Query for CorporateAction {
+    fn query(&self) -> crate::SqlQuery<'_> {
+        // the new ticker is optional, so an unknown ticker is turned into an id that fails the foreign key instead of NULL
+        sqlx::query!(
+            r#"
+INSERT INTO
+    CorporateAction (
+        security_id,
+        date,
+        action,
+        old_units,
+        new_units,
+        new_security_id
+    )
+VALUES
+    (
+        (
+            SELECT
+                security_id
+            FROM
+                SECURITY
+            WHERE
+                ticker = ?1
+        ),
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        CASE
+            WHEN ?6 IS NULL THEN NULL
+            ELSE COALESCE(
+                (
+                    SELECT
+                        security_id
+                    FROM
+                        SECURITY
+                    WHERE
+                        ticker = ?6
+                ),
+                -1
+            )
+        END
+    ) ON CONFLICT (security_id, date) DO
+UPDATE
+SET
+    action = excluded.action,
+    old_units = excluded.old_units,
+    new_units = excluded.new_units,
+    new_security_id = excluded.new_security_id
+WHERE
+    action IS NOT excluded.action
+    OR old_units IS NOT excluded.old_units
+    OR new_units IS NOT excluded.new_units
+    OR new_security_id IS NOT excluded.new_security_id
+"#,
+            self.ticker,
+            self.date,
+            self.action,
+            self.old_units,
+            self.new_units,
+            self.new_ticker
+        )
+    }
+}
+
+impl Prune for CorporateAction {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    CorporateAction
+WHERE
+    security_id = (
+        SELECT
+            security_id
+        FROM
+            SECURITY
+        WHERE
+            ticker = ?
+    )
+    AND date = ?
+"#,
+            self.ticker,
+            self.date
+        )
+    }
+}
+
+impl Export for CorporateAction {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    SECURITY.ticker,
+    date,
+    action,
+    old_units,
+    new_units,
+    NewSecurity.ticker AS new_ticker
+FROM
+    CorporateAction
+    INNER JOIN SECURITY USING (security_id)
+    LEFT JOIN SECURITY NewSecurity ON NewSecurity.security_id = CorporateAction.new_security_id
+ORDER BY
+    SECURITY.ticker,
+    date
+"#;
+}
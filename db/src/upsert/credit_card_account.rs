@@ -1,13 +1,13 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{deserialize_into_map, Id};
+use common::Id;
 use owo_colors::OwoColorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Transaction;
+use crate::{read_csv_into_map, Error, Export, Money, Transaction};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct CreditCard {
     #[serde(deserialize_with = "string_trim")]
     pub account_type: String,
@@ -15,8 +15,8 @@ pub struct CreditCard {
     pub institution_name: String,
     #[serde(deserialize_with = "string_trim")]
     pub account_name: String,
-    pub annual_fee: f64,
-    pub credit_limit: Option<f64>,
+    pub annual_fee: Money,
+    pub credit_limit: Option<Money>,
     #[serde(deserialize_with = "string_trim")]
     pub currency: String,
 }
@@ -30,28 +30,36 @@ impl Id for CreditCard {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_credit_card(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_credit_card(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<CreditCard>(csv_path).await?;
-
-        for (_, record) in &parsed_records {
-            self.upsert_account_type_helper(&record.account_type)
-                .await?;
+        let parsed_records = read_csv_into_map::<CreditCard>(csv_path)?;
 
-            self.upsert_credit_card_helper(record).await?;
+        for (line, record) in parsed_records.values() {
+            self.upsert_credit_card_account_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
         }
 
         Ok(())
     }
 
+    async fn upsert_credit_card_account_record(
+        &mut self,
+        record: &CreditCard,
+    ) -> Result<(), Error> {
+        self.upsert_account_type_helper(&record.account_type)
+            .await?;
+
+        self.upsert_credit_card_helper(record).await?;
+
+        Ok(())
+    }
+
     async fn upsert_credit_card_helper(
         &mut self,
         record: &CreditCard,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         println!("inserting credit card {}", record.account_type);
 
         let result = sqlx::query!(
@@ -124,7 +132,26 @@ SET
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => Err(format!("{}, record: {:?}", err.to_string(), record).into()),
+            Err(err) => Err(Error::from_record(err, record)),
         }
     }
 }
+
+impl Export for CreditCard {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_type,
+    institution_name,
+    account_name,
+    annual_fee,
+    credit_limit,
+    currency
+FROM
+    CreditCardProduct
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN Institution USING (institution_id)
+    INNER JOIN Currency USING (currency_id)
+ORDER BY
+    account_type
+"#;
+}
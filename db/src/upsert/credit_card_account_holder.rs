@@ -1,13 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{bool_from_str, deserialize_into_map, Id};
-use owo_colors::OwoColorize;
-use serde::Deserialize;
+use common::{bool_from_str, Id};
+use serde::{Deserialize, Serialize};
 use serde_trim::{option_string_trim, string_trim};
 
-use crate::{SqlResult, Transaction};
+use crate::{read_csv_into_map, Error, Export, SqlResult, Transaction};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct CreditCardHolder {
     #[serde(deserialize_with = "string_trim")]
     pub person_key: String,
@@ -34,35 +33,43 @@ impl CreditCardHolder {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_credit_card_holder(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_credit_card_holder(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<CreditCardHolder>(csv_path).await?;
+        let parsed_records = read_csv_into_map::<CreditCardHolder>(csv_path)?;
 
-        for (_, record) in &parsed_records {
-            let account_key = record.get_account_key();
+        for (line, record) in parsed_records.values() {
+            self.upsert_credit_card_account_holder_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
+        }
 
-            println!("> inserting credit card account {account_key}");
-            self.upsert_credit_card_account_helper(&account_key, &record.account_type)
-                .await?;
+        Ok(())
+    }
 
-            println!("> inserting credit card account holder");
-            let id = self
-                .upsert_credit_card_account_holder_helper(record)
-                .await?;
+    async fn upsert_credit_card_account_holder_record(
+        &mut self,
+        record: &CreditCardHolder,
+    ) -> Result<(), Error> {
+        let account_key = record.get_account_key();
 
-            if let Some(credit_card_holder_id) = id {
-                println!("> inserting credit card account mapping");
-                self.upsert_credit_card_account_mapping_helper(credit_card_holder_id, &account_key)
-                    .await?;
+        println!("> inserting credit card account {account_key}");
+        self.upsert_credit_card_account_helper(&account_key, &record.account_type)
+            .await?;
 
-                println!("> inserting credit card PAD source");
-                self.upsert_credit_card_pad_source(credit_card_holder_id, &record.pad_source)
-                    .await?;
-            }
+        println!("> inserting credit card account holder");
+        let id = self
+            .upsert_credit_card_account_holder_helper(record)
+            .await?;
+
+        if let Some(credit_card_holder_id) = id {
+            println!("> inserting credit card account mapping");
+            self.upsert_credit_card_account_mapping_helper(credit_card_holder_id, &account_key)
+                .await?;
+
+            println!("> inserting credit card PAD source");
+            self.upsert_credit_card_pad_source(credit_card_holder_id, &record.pad_source)
+                .await?;
         }
 
         Ok(())
@@ -95,7 +102,7 @@ UPDATE
 SET
     cash_account_holder_id = excluded.cash_account_holder_id
 WHERE
-    cash_account_holder_id <> excluded.cash_account_holder_id
+    cash_account_holder_id IS NOT excluded.cash_account_holder_id
 "#,
                 credit_card_holder_id,
                 pad_source
@@ -123,7 +130,7 @@ WHERE
         &mut self,
         account_key: &str,
         account_type: &str,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT
@@ -172,9 +179,9 @@ SET
     account_subtype_id = excluded.account_subtype_id,
     account_name = excluded.account_name
 WHERE
-    account_type_id <> excluded.account_type_id
-    OR account_subtype_id <> excluded.account_subtype_id
-    OR account_name <> excluded.account_name
+    account_type_id IS NOT excluded.account_type_id
+    OR account_subtype_id IS NOT excluded.account_subtype_id
+    OR account_name IS NOT excluded.account_name
 "#,
             account_key,
             account_type,
@@ -188,32 +195,19 @@ WHERE
                 const NUM_SUBTYPE_ACCOUNTS: u64 = 6;
 
                 if result.rows_affected() != NUM_SUBTYPE_ACCOUNTS {
-                    println!(
-                        "{}: no row was affected, record: {}, {}",
-                        "Warning".bold().yellow(),
-                        account_key,
-                        account_type
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => Err(format!(
-                "{}, record: {}, {}",
-                err.to_string(),
-                account_key,
-                account_type
-            )
-            .into()),
+            Err(err) => Err(Error::from_record(err, &(account_key, &account_type))),
         }
     }
 
     async fn upsert_credit_card_account_holder_helper(
         &mut self,
         record: &CreditCardHolder,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT INTO
@@ -246,7 +240,7 @@ UPDATE
 SET
     is_closed = excluded.is_closed
 WHERE
-    is_closed <> excluded.is_closed
+    is_closed IS NOT excluded.is_closed
 "#,
             record.person_key,
             record.account_type,
@@ -258,20 +252,12 @@ WHERE
         match result {
             Ok(result) => {
                 if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => {
-                return Err(format!("{}, record: {:?}", err.to_string(), record).into());
-            }
+            Err(err) => Err(Error::from_record(err, record)),
         }
     }
 
@@ -279,7 +265,7 @@ WHERE
         &mut self,
         credit_card_holder_id: i64,
         account_key: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), Error> {
         let search_term = format!("{account_key}%");
 
         sqlx::query!(
@@ -302,7 +288,7 @@ UPDATE
 SET
     account_id = excluded.account_id
 WHERE
-    account_id <> excluded.account_id
+    account_id IS NOT excluded.account_id
 "#,
             credit_card_holder_id,
             search_term
@@ -313,3 +299,31 @@ WHERE
         Ok(())
     }
 }
+
+impl Export for CreditCardHolder {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    person_key,
+    account_type,
+    (
+        SELECT
+            account_key
+        FROM
+            CashAccountEntry
+            INNER JOIN Account USING (account_id)
+            INNER JOIN AccountSubtype ON AccountSubtype.account_subtype_id = CashAccountEntry.account_subtype_id
+        WHERE
+            CashAccountEntry.cash_account_holder_id = CreditCardPadSource.cash_account_holder_id
+            AND account_subtype = 'CASH'
+    ) AS pad_source,
+    is_closed
+FROM
+    CreditCardHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+    LEFT JOIN CreditCardPadSource USING (credit_card_holder_id)
+ORDER BY
+    person_key,
+    account_type
+"#;
+}
@@ -1,11 +1,13 @@
-use common::Id;
-use serde::Deserialize;
+use std::path::Path;
+
+use common::{option_bool_from_str, Id};
+use serde::{Deserialize, Serialize};
 use serde_trim::option_string_trim;
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{read_csv_into_map, Error, Export, Prune, Query, Transaction};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Currency {
     #[serde(deserialize_with = "string_trim")]
     pub currency: String,
@@ -13,8 +15,10 @@ pub struct Currency {
     pub currency_name: String,
     #[serde(deserialize_with = "option_string_trim")]
     pub currency_symbol: Option<String>,
-    #[serde(deserialize_with = "option_string_trim")]
-    pub market_exchange_rate: Option<String>,
+    /// The currency of the reports, at most one currency can be the home currency.
+    /// Leaving it out keeps the current setting.
+    #[serde(default, deserialize_with = "option_bool_from_str")]
+    pub is_home: Option<bool>,
 }
 
 impl Id for Currency {
@@ -26,32 +30,100 @@ impl Id for Currency {
 }
 
 impl Query for Currency {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
-    Currency (
-        currency,
-        currency_name,
-        currency_symbol,
-        market_exchange_rate
-    )
+    Currency (currency, currency_name, currency_symbol, is_home)
 VALUES
-    (?, ?, ?, ?) ON CONFLICT(currency) DO
+    (?1, ?2, ?3, COALESCE(?4, FALSE)) ON CONFLICT(currency) DO
 UPDATE
 SET
     currency_name = excluded.currency_name,
     currency_symbol = excluded.currency_symbol,
-    market_exchange_rate = excluded.market_exchange_rate
+    is_home = COALESCE(?4, is_home)
 WHERE
-    currency_name <> excluded.currency_name
-    OR currency_symbol <> excluded.currency_symbol
-    OR market_exchange_rate <> excluded.market_exchange_rate
+    currency_name IS NOT excluded.currency_name
+    OR currency_symbol IS NOT excluded.currency_symbol
+    OR is_home IS NOT COALESCE(?4, is_home)
 "#,
             self.currency,
             self.currency_name,
             self.currency_symbol,
-            self.market_exchange_rate
+            self.is_home
+        )
+    }
+}
+
+impl Transaction<'_> {
+    /// Clears the previous home currency before the upsert, so that currency.csv can move it in any row order.
+    pub async fn upsert_currency(&mut self, csv_path: &Path) -> Result<(), Error> {
+        let parsed_records = read_csv_into_map::<Currency>(csv_path)?;
+
+        let mut home_records: Vec<_> = parsed_records
+            .values()
+            .filter(|(_, record)| record.is_home == Some(true))
+            .collect();
+        home_records.sort_by_key(|(line, _)| *line);
+
+        if let Some((_, home_record)) = home_records.first() {
+            if let Some((line, _)) = home_records.get(1) {
+                return Err(Error::invalid("only one currency can be the home currency")
+                    .at(csv_path, *line));
+            }
+
+            sqlx::query!(
+                r#"
+UPDATE
+    Currency
+SET
+    is_home = FALSE
+WHERE
+    is_home
+    AND currency <> ?
+"#,
+                home_record.currency
+            )
+            .execute(&mut *self.0)
+            .await?;
+        }
+
+        self.upsert_all::<Currency>(csv_path).await?;
+
+        Ok(())
+    }
+}
+
+impl Prune for Currency {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    Currency
+WHERE
+    currency = ?
+"#,
+            self.currency
         )
     }
 }
+
+impl Export for Currency {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    currency,
+    currency_name,
+    currency_symbol,
+    is_home
+FROM
+    Currency
+ORDER BY
+    currency
+"#;
+
+    fn keep_existing(&mut self, existing: &Self) {
+        if self.is_home.is_none() {
+            self.is_home = existing.is_home;
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Exchange {
     #[serde(deserialize_with = "string_trim")]
     pub exchange_key: String,
@@ -21,7 +21,7 @@ impl Id for Exchange {
 }
 
 impl Query for Exchange {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -38,10 +38,36 @@ UPDATE
 SET
     exchange_name = excluded.exchange_name
 WHERE
-    exchange_name <> excluded.exchange_name
+    exchange_name IS NOT excluded.exchange_name
 "#,
             self.exchange_key,
             self.exchange_name
         )
     }
 }
+
+impl Prune for Exchange {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    Exchange
+WHERE
+    exchange_key = ?
+"#,
+            self.exchange_key
+        )
+    }
+}
+
+impl Export for Exchange {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    exchange_key,
+    exchange_name
+FROM
+    Exchange
+ORDER BY
+    exchange_key
+"#;
+}
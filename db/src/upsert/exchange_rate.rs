@@ -0,0 +1,95 @@
+use common::{excel_date_format, serialize_excel_date, Id};
+use serde::{Deserialize, Serialize};
+use serde_trim::string_trim;
+
+use crate::{Export, Query};
+
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
+pub struct ExchangeRate {
+    #[serde(deserialize_with = "string_trim")]
+    pub base_currency: String,
+    #[serde(deserialize_with = "string_trim")]
+    pub quote_currency: String,
+    #[serde(
+        deserialize_with = "excel_date_format",
+        serialize_with = "serialize_excel_date"
+    )]
+    pub date: i64,
+    pub rate: f64,
+}
+
+impl Id for ExchangeRate {
+    type IdType = (String, String, i64);
+
+    fn id(&self) -> Self::IdType {
+        (
+            self.base_currency.clone(),
+            self.quote_currency.clone(),
+            self.date,
+        )
+    }
+}
+
+impl Query for ExchangeRate {
+    fn query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+INSERT INTO
+    ExchangeRate (
+        base_currency_id,
+        quote_currency_id,
+        date,
+        rate
+    )
+VALUES
+    (
+        (
+            SELECT
+                currency_id
+            FROM
+                Currency
+            WHERE
+                currency = ?
+        ),
+        (
+            SELECT
+                currency_id
+            FROM
+                Currency
+            WHERE
+                currency = ?
+        ),
+        ?,
+        ?
+    ) ON CONFLICT(base_currency_id, quote_currency_id, date) DO
+UPDATE
+SET
+    rate = excluded.rate
+WHERE
+    rate IS NOT excluded.rate
+"#,
+            self.base_currency,
+            self.quote_currency,
+            self.date,
+            self.rate
+        )
+    }
+}
+
+impl Export for ExchangeRate {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    Base.currency AS base_currency,
+    Quote.currency AS quote_currency,
+    date,
+    rate
+FROM
+    ExchangeRate
+    INNER JOIN Currency Base ON base_currency_id = Base.currency_id
+    INNER JOIN Currency Quote ON quote_currency_id = Quote.currency_id
+ORDER BY
+    base_currency,
+    quote_currency,
+    date
+"#;
+}
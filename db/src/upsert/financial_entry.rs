@@ -1,20 +1,23 @@
-use common::{excel_date_optional_time_format, Id};
-use serde::Deserialize;
+use common::{excel_date_optional_time_format, serialize_excel_date_optional_time, Id};
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Price, Prune, Query, Units};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct FinancialEntry {
     pub transaction_id: i64,
     pub item_id: i64,
-    #[serde(deserialize_with = "excel_date_optional_time_format")]
+    #[serde(
+        deserialize_with = "excel_date_optional_time_format",
+        serialize_with = "serialize_excel_date_optional_time"
+    )]
     pub date: i64,
     #[serde(deserialize_with = "string_trim")]
     pub account_key: String,
-    pub unit: f64,
-    pub debit: Option<f64>,
-    pub credit: Option<f64>,
+    pub unit: Units,
+    pub debit: Option<Price>,
+    pub credit: Option<Price>,
     #[serde(deserialize_with = "string_trim")]
     pub description: String,
 }
@@ -28,7 +31,7 @@ impl Id for FinancialEntry {
 }
 
 impl Query for FinancialEntry {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -69,12 +72,12 @@ SET
     credit = excluded.credit,
     description = excluded.description
 WHERE
-    date <> excluded.date
-    OR account_id <> excluded.account_id
-    OR unit <> excluded.unit
-    OR debit <> excluded.debit
-    OR credit <> excluded.credit
-    OR description <> excluded.description;
+    date IS NOT excluded.date
+    OR account_id IS NOT excluded.account_id
+    OR unit IS NOT excluded.unit
+    OR debit IS NOT excluded.debit
+    OR credit IS NOT excluded.credit
+    OR description IS NOT excluded.description;
 "#,
             self.transaction_id,
             self.item_id,
@@ -86,4 +89,70 @@ WHERE
             self.description
         )
     }
+
+    fn entry_id(&self) -> Option<(i64, i64)> {
+        Some((self.transaction_id, self.item_id))
+    }
+}
+
+impl Prune for FinancialEntry {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    FinancialEntry
+WHERE
+    transaction_id = ?
+    AND item_id = ?
+"#,
+            self.transaction_id,
+            self.item_id
+        )
+    }
+}
+
+impl Export for FinancialEntry {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    transaction_id,
+    item_id,
+    date,
+    account_key,
+    unit,
+    debit,
+    credit,
+    description
+FROM
+    FinancialEntry
+    INNER JOIN Account USING (account_id)
+WHERE
+    -- closing entries are posted by close-period
+    transaction_id NOT IN (
+        SELECT
+            transaction_id
+        FROM
+            ClosingTransaction
+    )
+ORDER BY
+    transaction_id,
+    item_id
+"#;
+
+    const DIFF_QUERY: &'static str = r#"
+SELECT
+    transaction_id,
+    item_id,
+    date,
+    account_key,
+    unit,
+    debit,
+    credit,
+    description
+FROM
+    FinancialEntry
+    INNER JOIN Account USING (account_id)
+ORDER BY
+    transaction_id,
+    item_id
+"#;
 }
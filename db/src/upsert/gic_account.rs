@@ -1,13 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{deserialize_into_map, Id};
-use owo_colors::OwoColorize;
-use serde::Deserialize;
+use common::Id;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Transaction;
+use crate::{read_csv_into_map, Error, Export, Transaction};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct GicAccount {
     #[serde(deserialize_with = "string_trim")]
     pub account_type: String,
@@ -22,33 +21,34 @@ impl Id for GicAccount {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_gic_account(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_gic_account(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<GicAccount>(csv_path).await?;
+        let parsed_records = read_csv_into_map::<GicAccount>(csv_path)?;
 
-        for (_, record) in &parsed_records {
-            if !self.has_cash_account(&record.account_type).await? {
-                return Err(format!(
-                    "cannot insert {} as a stock account unless it is also a cash account",
-                    record.account_type
-                )
-                .into());
-            }
+        for (line, record) in parsed_records.values() {
+            self.upsert_gic_account_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
+        }
 
-            self.upsert_gic_gic_account_helper(record).await?;
+        Ok(())
+    }
+
+    async fn upsert_gic_account_record(&mut self, record: &GicAccount) -> Result<(), Error> {
+        if !self.has_cash_account(&record.account_type).await? {
+            return Err(Error::invalid(format!(
+                "cannot insert {} as a stock account unless it is also a cash account",
+                record.account_type
+            )));
         }
 
+        self.upsert_gic_gic_account_helper(record).await?;
+
         Ok(())
     }
 
-    async fn upsert_gic_gic_account_helper(
-        &mut self,
-        record: &GicAccount,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    async fn upsert_gic_gic_account_helper(&mut self, record: &GicAccount) -> Result<(), Error> {
         let result = sqlx::query!(
             r#"
 INSERT
@@ -70,19 +70,20 @@ VALUES
         .execute(&mut *self.0)
         .await;
 
-        match result {
-            Ok(result) => {
-                if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-                }
-
-                Ok(())
-            }
-            Err(err) => Err(format!("{}, record: {:?}", err.to_string(), record).into()),
-        }
+        result
+            .map(|_| ())
+            .map_err(|err| Error::from_record(err, record))
     }
 }
+
+impl Export for GicAccount {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_type
+FROM
+    GicAccount
+    INNER JOIN AccountType USING (account_type_id)
+ORDER BY
+    account_type
+"#;
+}
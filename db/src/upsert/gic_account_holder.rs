@@ -1,14 +1,14 @@
-use std::path::PathBuf;
+use std::path::Path;
 
 use chrono::NaiveDate;
-use common::{deserialize_into_map, Id};
+use common::Id;
 use owo_colors::OwoColorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Transaction;
+use crate::{read_csv_into_map, Error, Export, Transaction};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct GicAccountHolder {
     #[serde(deserialize_with = "string_trim")]
     pub person_key: String,
@@ -47,29 +47,36 @@ impl GicAccountHolder {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_gic_account_holder(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_gic_account_holder(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<GicAccountHolder>(csv_path).await?;
+        let parsed_records = read_csv_into_map::<GicAccountHolder>(csv_path)?;
 
-        for (_, record) in &parsed_records {
-            let account_key = record.get_account_key();
+        for (line, record) in parsed_records.values() {
+            self.upsert_gic_account_holder_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
+        }
 
-            println!("> inserting GIC account {}", account_key.yellow());
-            self.upsert_gic_account_helper(&account_key, &record)
-                .await?;
+        Ok(())
+    }
 
-            println!("> inserting GIC account holder {}", account_key.yellow());
-            let id = self.upsert_gic_account_holder_helper(record).await?;
+    async fn upsert_gic_account_holder_record(
+        &mut self,
+        record: &GicAccountHolder,
+    ) -> Result<(), Error> {
+        let account_key = record.get_account_key();
 
-            if let Some(gic_account_holder_id) = id {
-                println!("> inserting GIC account mapping {}", account_key.yellow());
-                self.upsert_gic_account_mapping_helper(gic_account_holder_id, &account_key)
-                    .await?
-            }
+        println!("> inserting GIC account {}", account_key.yellow());
+        self.upsert_gic_account_helper(&account_key, record).await?;
+
+        println!("> inserting GIC account holder {}", account_key.yellow());
+        let id = self.upsert_gic_account_holder_helper(record).await?;
+
+        if let Some(gic_account_holder_id) = id {
+            println!("> inserting GIC account mapping {}", account_key.yellow());
+            self.upsert_gic_account_mapping_helper(gic_account_holder_id, &account_key)
+                .await?
         }
 
         Ok(())
@@ -79,7 +86,7 @@ impl Transaction<'_> {
         &mut self,
         account_key: &str,
         record: &GicAccountHolder,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let apr = format!("{:.2}%", record.apr * 100.0);
 
         let result = sqlx::query!(
@@ -130,9 +137,9 @@ SET
     account_subtype_id = excluded.account_subtype_id,
     account_name = excluded.account_name
 WHERE
-    account_type_id <> excluded.account_type_id
-    OR account_subtype_id <> excluded.account_subtype_id
-    OR account_name <> excluded.account_name
+    account_type_id IS NOT excluded.account_type_id
+    OR account_subtype_id IS NOT excluded.account_subtype_id
+    OR account_name IS NOT excluded.account_name
 "#,
             account_key,
             record.account_type,
@@ -147,32 +154,22 @@ WHERE
                 const NUM_SUBTYPE_ACCOUNTS: u64 = 3;
 
                 if result.rows_affected() != NUM_SUBTYPE_ACCOUNTS {
-                    println!(
-                        "{}: no row was affected, record: {}, {}",
-                        "Warning".bold().yellow(),
-                        account_key,
-                        record.account_type
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => Err(format!(
-                "{}, record: {}, {}",
-                err.to_string(),
-                account_key,
-                record.account_type
-            )
-            .into()),
+            Err(err) => Err(Error::from_record(
+                err,
+                &(account_key, &record.account_type),
+            )),
         }
     }
 
     async fn upsert_gic_account_holder_helper(
         &mut self,
         record: &GicAccountHolder,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT
@@ -218,20 +215,12 @@ VALUES
         match result {
             Ok(result) => {
                 if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => {
-                return Err(format!("{}, record: {:?}", err.to_string(), record).into());
-            }
+            Err(err) => Err(Error::from_record(err, record)),
         }
     }
 
@@ -239,7 +228,7 @@ VALUES
         &mut self,
         gic_account_holder_id: i64,
         account_key: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), Error> {
         let search_term = format!("{account_key}%");
 
         sqlx::query!(
@@ -262,7 +251,7 @@ UPDATE
 SET
     account_id = excluded.account_id
 WHERE
-    account_id <> excluded.account_id
+    account_id IS NOT excluded.account_id
 "#,
             gic_account_holder_id,
             search_term
@@ -273,3 +262,24 @@ WHERE
         Ok(())
     }
 }
+
+impl Export for GicAccountHolder {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    person_key,
+    account_type,
+    issue_date,
+    maturity_date,
+    apr
+FROM
+    GicAccountHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+ORDER BY
+    person_key,
+    account_type,
+    issue_date,
+    maturity_date,
+    apr
+"#;
+}
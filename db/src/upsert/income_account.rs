@@ -1,13 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{deserialize_into_map, Id};
-use owo_colors::OwoColorize;
-use serde::Deserialize;
+use common::Id;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Transaction;
+use crate::{read_csv_into_map, Error, Export, Transaction};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct IncomeAccount {
     #[serde(deserialize_with = "string_trim")]
     pub account_type: String,
@@ -26,28 +25,33 @@ impl Id for IncomeAccount {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_income_account(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_income_account(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<IncomeAccount>(csv_path).await?;
-
-        for (_, record) in &parsed_records {
-            self.upsert_account_type_helper(&record.account_type)
-                .await?;
+        let parsed_records = read_csv_into_map::<IncomeAccount>(csv_path)?;
 
-            self.upsert_income_income_account_helper(record).await?;
+        for (line, record) in parsed_records.values() {
+            self.upsert_income_account_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
         }
 
         Ok(())
     }
 
+    async fn upsert_income_account_record(&mut self, record: &IncomeAccount) -> Result<(), Error> {
+        self.upsert_account_type_helper(&record.account_type)
+            .await?;
+
+        self.upsert_income_income_account_helper(record).await?;
+
+        Ok(())
+    }
+
     async fn upsert_income_income_account_helper(
         &mut self,
         record: &IncomeAccount,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), Error> {
         let result = sqlx::query!(
             r#"
 INSERT INTO
@@ -77,8 +81,8 @@ SET
     currency_id = excluded.currency_id,
     account_name = excluded.account_name
 WHERE
-    currency_id <> excluded.currency_id
-    OR account_name <> excluded.account_name
+    currency_id IS NOT excluded.currency_id
+    OR account_name IS NOT excluded.account_name
 "#,
             record.account_type,
             record.currency,
@@ -87,19 +91,23 @@ WHERE
         .execute(&mut *self.0)
         .await;
 
-        match result {
-            Ok(result) => {
-                if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-                }
-
-                Ok(())
-            }
-            Err(err) => Err(format!("{}, record: {:?}", err.to_string(), record).into()),
-        }
+        result
+            .map(|_| ())
+            .map_err(|err| Error::from_record(err, record))
     }
 }
+
+impl Export for IncomeAccount {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_type,
+    account_name,
+    currency
+FROM
+    IncomeAccount
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN Currency USING (currency_id)
+ORDER BY
+    account_type
+"#;
+}
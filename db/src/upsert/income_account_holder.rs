@@ -1,13 +1,13 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{deserialize_into_map, Id};
+use common::Id;
 use owo_colors::OwoColorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Transaction;
+use crate::{read_csv_into_map, Error, Export, Transaction};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct IncomeAccountHolder {
     #[serde(deserialize_with = "string_trim")]
     pub person_key: String,
@@ -30,32 +30,40 @@ impl IncomeAccountHolder {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_income_account_holder(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_income_account_holder(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<IncomeAccountHolder>(csv_path).await?;
-
-        for (_, record) in &parsed_records {
-            let account_key = record.get_account_key();
+        let parsed_records = read_csv_into_map::<IncomeAccountHolder>(csv_path)?;
 
-            println!("> inserting income account {}", account_key.yellow());
-            self.upsert_income_account_helper(&account_key, &record)
-                .await?;
+        for (line, record) in parsed_records.values() {
+            self.upsert_income_account_holder_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
+        }
 
-            println!("> inserting income account holder {}", account_key.yellow());
-            let id = self.upsert_income_account_holder_helper(record).await?;
+        Ok(())
+    }
 
-            if let Some(income_account_holder_id) = id {
-                println!(
-                    "> inserting income account mapping {}",
-                    account_key.yellow()
-                );
-                self.upsert_income_account_mapping_helper(income_account_holder_id, &account_key)
-                    .await?
-            }
+    async fn upsert_income_account_holder_record(
+        &mut self,
+        record: &IncomeAccountHolder,
+    ) -> Result<(), Error> {
+        let account_key = record.get_account_key();
+
+        println!("> inserting income account {}", account_key.yellow());
+        self.upsert_income_account_helper(&account_key, record)
+            .await?;
+
+        println!("> inserting income account holder {}", account_key.yellow());
+        let id = self.upsert_income_account_holder_helper(record).await?;
+
+        if let Some(income_account_holder_id) = id {
+            println!(
+                "> inserting income account mapping {}",
+                account_key.yellow()
+            );
+            self.upsert_income_account_mapping_helper(income_account_holder_id, &account_key)
+                .await?
         }
 
         Ok(())
@@ -65,7 +73,7 @@ impl Transaction<'_> {
         &mut self,
         account_key: &str,
         record: &IncomeAccountHolder,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT INTO
@@ -114,9 +122,9 @@ SET
     account_subtype_id = excluded.account_subtype_id,
     account_name = excluded.account_name
 WHERE
-    account_type_id <> excluded.account_type_id
-    OR account_subtype_id <> excluded.account_subtype_id
-    OR account_name <> excluded.account_name
+    account_type_id IS NOT excluded.account_type_id
+    OR account_subtype_id IS NOT excluded.account_subtype_id
+    OR account_name IS NOT excluded.account_name
 "#,
             account_key,
             record.account_type,
@@ -130,32 +138,22 @@ WHERE
                 const NUM_SUBTYPE_ACCOUNTS: u64 = 5;
 
                 if result.rows_affected() != NUM_SUBTYPE_ACCOUNTS {
-                    println!(
-                        "{}: no row was affected, record: {}, {}",
-                        "Warning".bold().yellow(),
-                        account_key,
-                        record.account_type
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => Err(format!(
-                "{}, record: {}, {}",
-                err.to_string(),
-                account_key,
-                record.account_type
-            )
-            .into()),
+            Err(err) => Err(Error::from_record(
+                err,
+                &(account_key, &record.account_type),
+            )),
         }
     }
 
     async fn upsert_income_account_holder_helper(
         &mut self,
         record: &IncomeAccountHolder,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT
@@ -192,20 +190,12 @@ VALUES
         match result {
             Ok(result) => {
                 if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => {
-                return Err(format!("{}, record: {:?}", err.to_string(), record).into());
-            }
+            Err(err) => Err(Error::from_record(err, record)),
         }
     }
 
@@ -213,7 +203,7 @@ VALUES
         &mut self,
         income_account_holder_id: i64,
         account_key: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), Error> {
         let search_term = format!("{account_key}%");
 
         sqlx::query!(
@@ -236,7 +226,7 @@ UPDATE
 SET
     account_id = excluded.account_id
 WHERE
-    account_id <> excluded.account_id
+    account_id IS NOT excluded.account_id
 "#,
             income_account_holder_id,
             search_term
@@ -247,3 +237,18 @@ WHERE
         Ok(())
     }
 }
+
+impl Export for IncomeAccountHolder {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    person_key,
+    account_type
+FROM
+    IncomeAccountHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+ORDER BY
+    person_key,
+    account_type
+"#;
+}
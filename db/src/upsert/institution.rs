@@ -1,13 +1,13 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Institution {
     #[serde(deserialize_with = "string_trim")]
-    institution_name: String,
+    pub institution_name: String,
 }
 
 impl Id for Institution {
@@ -19,7 +19,7 @@ impl Id for Institution {
 }
 
 impl Query for Institution {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT
@@ -31,3 +31,28 @@ VALUES
         )
     }
 }
+
+impl Prune for Institution {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    Institution
+WHERE
+    institution_name = ?
+"#,
+            self.institution_name
+        )
+    }
+}
+
+impl Export for Institution {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    institution_name
+FROM
+    Institution
+ORDER BY
+    institution_name
+"#;
+}
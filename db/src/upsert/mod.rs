@@ -8,10 +8,13 @@ mod cash_account;
 mod cash_account_holder;
 mod cashback_category;
 mod cashback_category_name;
+mod categorization_rule;
+mod corporate_action;
 mod credit_card_account;
 mod credit_card_account_holder;
 mod currency;
 mod exchange;
+mod exchange_rate;
 mod financial_entry;
 mod gic_account;
 mod gic_account_holder;
@@ -20,7 +23,9 @@ mod income_account_holder;
 mod institution;
 mod person;
 mod prepaid_account;
+mod recurring_entry;
 mod security;
+mod security_price;
 mod stock_account;
 mod stock_account_holder;
 mod store;
@@ -39,14 +44,25 @@ pub use cash_account::CashAccountProduct;
 pub use cash_account_holder::CashAccountHolder;
 pub use cashback_category::CashbackCategory;
 pub use cashback_category_name::CashbackCategoryName;
+pub use categorization_rule::CategorizationRule;
+pub use corporate_action::CorporateAction;
 pub use credit_card_account::CreditCard;
+pub use credit_card_account_holder::CreditCardHolder;
 pub use currency::Currency;
 pub use exchange::Exchange;
+pub use exchange_rate::ExchangeRate;
 pub use financial_entry::FinancialEntry;
+pub use gic_account::GicAccount;
+pub use gic_account_holder::GicAccountHolder;
+pub use income_account::IncomeAccount;
+pub use income_account_holder::IncomeAccountHolder;
 pub use institution::Institution;
 pub use person::Person;
 pub use prepaid_account::PrepaidAccount;
+pub use recurring_entry::RecurringEntry;
 pub use security::Security;
+pub use security_price::SecurityPrice;
+pub use stock_account::StockAccount;
 pub use stock_account_holder::StockAccountHolder;
 pub use store::Store;
 pub use store_cashback_mapping::StoreCashbackMapping;
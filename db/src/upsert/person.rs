@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct Person {
     #[serde(deserialize_with = "string_trim")]
     pub person_key: String,
@@ -23,7 +23,7 @@ impl Id for Person {
 }
 
 impl Query for Person {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -35,8 +35,8 @@ SET
     first_name = excluded.first_name,
     last_name = excluded.last_name
 WHERE
-    first_name <> excluded.first_name
-    OR last_name <> excluded.last_name
+    first_name IS NOT excluded.first_name
+    OR last_name IS NOT excluded.last_name
 "#,
             self.person_key,
             self.first_name,
@@ -44,3 +44,30 @@ WHERE
         )
     }
 }
+
+impl Prune for Person {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    Person
+WHERE
+    person_key = ?
+"#,
+            self.person_key
+        )
+    }
+}
+
+impl Export for Person {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    person_key,
+    first_name,
+    last_name
+FROM
+    Person
+ORDER BY
+    person_key
+"#;
+}
@@ -1,13 +1,13 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct PrepaidAccount {
     #[serde(deserialize_with = "string_trim")]
-    account_type: String,
+    pub account_type: String,
 }
 
 impl Id for PrepaidAccount {
@@ -19,7 +19,7 @@ impl Id for PrepaidAccount {
 }
 
 impl Query for PrepaidAccount {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT
@@ -40,3 +40,36 @@ VALUES
         )
     }
 }
+
+impl Prune for PrepaidAccount {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    PrepaidAccount
+WHERE
+    account_type_id = (
+        SELECT
+            account_type_id
+        FROM
+            AccountType
+        WHERE
+            account_type = ?
+    )
+"#,
+            self.account_type
+        )
+    }
+}
+
+impl Export for PrepaidAccount {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    account_type
+FROM
+    PrepaidAccount
+    INNER JOIN AccountType USING (account_type_id)
+ORDER BY
+    account_type
+"#;
+}
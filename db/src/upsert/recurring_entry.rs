@@ -0,0 +1,145 @@
+use common::{
+    excel_date_format, optional_excel_date_format, serialize_excel_date,
+    serialize_optional_excel_date, Id,
+};
+use serde::{Deserialize, Serialize};
+use serde_trim::string_trim;
+
+use crate::{Export, Prune, Query};
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct RecurringEntry {
+    #[serde(deserialize_with = "string_trim")]
+    pub recurring_key: String,
+    pub item_id: i64,
+    #[serde(deserialize_with = "string_trim")]
+    pub schedule: String,
+    #[serde(
+        deserialize_with = "excel_date_format",
+        serialize_with = "serialize_excel_date"
+    )]
+    pub start_date: i64,
+    #[serde(
+        deserialize_with = "optional_excel_date_format",
+        serialize_with = "serialize_optional_excel_date"
+    )]
+    pub end_date: Option<i64>,
+    #[serde(deserialize_with = "string_trim")]
+    pub account_key: String,
+    /// An amount or a formula, e.g. `#1 * 5.95%`
+    pub debit: Option<String>,
+    pub credit: Option<String>,
+    #[serde(deserialize_with = "string_trim")]
+    pub description: String,
+}
+
+impl Id for RecurringEntry {
+    type IdType = (String, i64);
+
+    fn id(&self) -> Self::IdType {
+        (self.recurring_key.clone(), self.item_id)
+    }
+}
+
+impl Query for RecurringEntry {
+    fn query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+INSERT INTO
+    RecurringEntry (
+        recurring_key,
+        item_id,
+        schedule,
+        start_date,
+        end_date,
+        account_id,
+        debit,
+        credit,
+        description
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?,
+        ?,
+        ?,
+        (
+            SELECT
+                account_id
+            FROM
+                Account
+            WHERE
+                account_key = ?
+        ),
+        ?,
+        ?,
+        ?
+    ) ON CONFLICT (recurring_key, item_id) DO
+UPDATE
+SET
+    schedule = excluded.schedule,
+    start_date = excluded.start_date,
+    end_date = excluded.end_date,
+    account_id = excluded.account_id,
+    debit = excluded.debit,
+    credit = excluded.credit,
+    description = excluded.description
+WHERE
+    schedule IS NOT excluded.schedule
+    OR start_date IS NOT excluded.start_date
+    OR end_date IS NOT excluded.end_date
+    OR account_id IS NOT excluded.account_id
+    OR debit IS NOT excluded.debit
+    OR credit IS NOT excluded.credit
+    OR description IS NOT excluded.description
+"#,
+            self.recurring_key,
+            self.item_id,
+            self.schedule,
+            self.start_date,
+            self.end_date,
+            self.account_key,
+            self.debit,
+            self.credit,
+            self.description
+        )
+    }
+}
+
+impl Prune for RecurringEntry {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    RecurringEntry
+WHERE
+    recurring_key = ?
+    AND item_id = ?
+"#,
+            self.recurring_key,
+            self.item_id
+        )
+    }
+}
+
+impl Export for RecurringEntry {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    recurring_key,
+    item_id,
+    schedule,
+    start_date,
+    end_date,
+    account_key,
+    debit,
+    credit,
+    description
+FROM
+    RecurringEntry
+    INNER JOIN Account USING (account_id)
+ORDER BY
+    recurring_key,
+    item_id
+"#;
+}
@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Price, Prune, Query};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct Security {
     #[serde(deserialize_with = "string_trim")]
     pub exchange_key: String,
@@ -14,7 +14,7 @@ pub struct Security {
     pub ticker: String,
     #[serde(deserialize_with = "string_trim")]
     pub security_name: String,
-    pub price: f64,
+    pub price: Price,
 }
 
 impl Id for Security {
@@ -26,7 +26,8 @@ impl Id for Security {
 }
 
 impl Query for Security {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
+        // the price history owns the price, the price in the CSV file only seeds a security without a history
         sqlx::query!(
             r#"
 INSERT INTO
@@ -64,12 +65,32 @@ SET
     exchange_id = excluded.exchange_id,
     security_name = excluded.security_name,
     currency_id = excluded.currency_id,
-    price = excluded.price
+    price = CASE
+        WHEN EXISTS (
+            SELECT
+                *
+            FROM
+                SecurityPrice
+            WHERE
+                SecurityPrice.security_id = SECURITY.security_id
+        ) THEN price
+        ELSE excluded.price
+    END
 WHERE
-    exchange_id <> excluded.exchange_id
-    OR security_name <> excluded.security_name
-    OR currency_id <> excluded.currency_id
-    OR price <> excluded.price
+    exchange_id IS NOT excluded.exchange_id
+    OR security_name IS NOT excluded.security_name
+    OR currency_id IS NOT excluded.currency_id
+    OR (
+        price IS NOT excluded.price
+        AND NOT EXISTS (
+            SELECT
+                *
+            FROM
+                SecurityPrice
+            WHERE
+                SecurityPrice.security_id = SECURITY.security_id
+        )
+    )
 "#,
             self.exchange_key,
             self.currency,
@@ -79,3 +100,34 @@ WHERE
         )
     }
 }
+
+impl Prune for Security {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    SECURITY
+WHERE
+    ticker = ?
+"#,
+            self.ticker
+        )
+    }
+}
+
+impl Export for Security {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    exchange_key,
+    currency,
+    ticker,
+    security_name,
+    price
+FROM
+    SECURITY
+    INNER JOIN Exchange USING (exchange_id)
+    INNER JOIN Currency USING (currency_id)
+ORDER BY
+    ticker
+"#;
+}
@@ -0,0 +1,72 @@
+use common::{excel_date_format, serialize_excel_date, Id};
+use serde::{Deserialize, Serialize};
+use serde_trim::string_trim;
+
+use crate::{Export, Price, Query};
+
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
+pub struct SecurityPrice {
+    #[serde(deserialize_with = "string_trim")]
+    pub ticker: String,
+    #[serde(
+        deserialize_with = "excel_date_format",
+        serialize_with = "serialize_excel_date"
+    )]
+    pub date: i64,
+    pub price: Price,
+}
+
+impl Id for SecurityPrice {
+    type IdType = (String, i64);
+
+    fn id(&self) -> Self::IdType {
+        (self.ticker.to_string(), self.date)
+    }
+}
+
+impl Query for SecurityPrice {
+    fn query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+INSERT INTO
+    SecurityPrice (security_id, date, price)
+VALUES
+    (
+        (
+            SELECT
+                security_id
+            FROM
+                SECURITY
+            WHERE
+                ticker = ?
+        ),
+        ?,
+        ?
+    ) ON CONFLICT(security_id, date) DO
+UPDATE
+SET
+    price = excluded.price
+WHERE
+    price IS NOT excluded.price
+"#,
+            self.ticker,
+            self.date,
+            self.price
+        )
+    }
+}
+
+impl Export for SecurityPrice {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    ticker,
+    date,
+    SecurityPrice.price
+FROM
+    SecurityPrice
+    INNER JOIN SECURITY USING (security_id)
+ORDER BY
+    ticker,
+    date
+"#;
+}
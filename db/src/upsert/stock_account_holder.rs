@@ -1,13 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use common::{deserialize_into_map, Id};
-use owo_colors::OwoColorize;
-use serde::Deserialize;
+use common::Id;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Transaction;
+use crate::{read_csv_into_map, Error, Export, Transaction};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct StockAccountHolder {
     #[serde(deserialize_with = "string_trim")]
     pub person_key: String,
@@ -36,29 +35,41 @@ impl StockAccountHolder {
 }
 
 impl Transaction<'_> {
-    pub async fn upsert_stock_account_holder(
-        &mut self,
-        csv_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn upsert_stock_account_holder(&mut self, csv_path: &Path) -> Result<(), Error> {
         println!("updating data with {}", csv_path.to_string_lossy());
 
-        let parsed_records = deserialize_into_map::<StockAccountHolder>(csv_path).await?;
+        let parsed_records = read_csv_into_map::<StockAccountHolder>(csv_path)?;
+
+        for (line, record) in parsed_records.values() {
+            self.upsert_stock_account_holder_record(record)
+                .await
+                .map_err(|err| err.at(csv_path, *line))?;
+        }
 
-        for (_, record) in &parsed_records {
-            let account_key = record.create_account_key();
+        Ok(())
+    }
 
-            println!("> inserting into Account {account_key}");
-            self.upsert_stock_account_helper(record, &account_key)
-                .await?;
+    async fn upsert_stock_account_holder_record(
+        &mut self,
+        record: &StockAccountHolder,
+    ) -> Result<(), Error> {
+        let account_key = record.create_account_key();
 
-            println!("> inserting into StockAccountHolder {account_key}");
-            let id = self.upsert_stock_account_holder_helper(record).await?;
+        println!("> inserting into Account {account_key}");
+        self.upsert_stock_account_helper(record, &account_key)
+            .await?;
 
-            if let Some(stock_account_holder_id) = id {
-                println!("> inserting credit card account mapping {account_key}");
-                self.upsert_stock_account_mapping_helper(stock_account_holder_id, &account_key)
-                    .await?
-            }
+        println!("> inserting into StockAccountHolder {account_key}");
+        let id = match self.upsert_stock_account_holder_helper(record).await? {
+            Some(id) => Some(id),
+            // an existing holder still needs the accounts of the subtypes that were added later
+            None => self.get_stock_account_holder_id(record).await?,
+        };
+
+        if let Some(stock_account_holder_id) = id {
+            println!("> inserting credit card account mapping {account_key}");
+            self.upsert_stock_account_mapping_helper(stock_account_holder_id, &account_key)
+                .await?
         }
 
         Ok(())
@@ -68,7 +79,7 @@ impl Transaction<'_> {
         &mut self,
         record: &StockAccountHolder,
         account_key: &str,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT
@@ -118,9 +129,9 @@ SET
     account_subtype_id = excluded.account_subtype_id,
     account_name = excluded.account_name
 WHERE
-    account_type_id <> excluded.account_type_id
-    OR account_subtype_id <> excluded.account_subtype_id
-    OR account_name <> excluded.account_name
+    account_type_id IS NOT excluded.account_type_id
+    OR account_subtype_id IS NOT excluded.account_subtype_id
+    OR account_name IS NOT excluded.account_name
 "#,
             account_key,
             record.account_type,
@@ -131,29 +142,24 @@ WHERE
 
         match result {
             Ok(result) => {
-                // stock, distribution, commission, open balance, withholding tax, capital gain/loss/bookkeeping (equity)
-                const NUM_SUBTYPE_ACCOUNTS: u64 = 9;
+                // stock, distribution, commission, open balance, withholding tax, capital gain/loss/bookkeeping (equity),
+                // return of capital, reinvested distribution
+                const NUM_SUBTYPE_ACCOUNTS: u64 = 11;
 
                 if result.rows_affected() != NUM_SUBTYPE_ACCOUNTS {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => Err(format!("{}, record: {:?}", err.to_string(), record).into()),
+            Err(err) => Err(Error::from_record(err, record)),
         }
     }
 
     async fn upsert_stock_account_holder_helper(
         &mut self,
         record: &StockAccountHolder,
-    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<i64>, Error> {
         let result = sqlx::query!(
             r#"
 INSERT
@@ -200,26 +206,49 @@ VALUES
         match result {
             Ok(result) => {
                 if result.rows_affected() != 1 {
-                    println!(
-                        "{}: no row was affected, record: {:?}",
-                        "Warning".bold().yellow(),
-                        record
-                    );
-
                     Ok(None)
                 } else {
                     Ok(Some(result.last_insert_rowid()))
                 }
             }
-            Err(err) => Err(format!("{}, record: {:?}", err.to_string(), record).into()),
+            Err(err) => Err(Error::from_record(err, record)),
         }
     }
 
+    async fn get_stock_account_holder_id(
+        &mut self,
+        record: &StockAccountHolder,
+    ) -> Result<Option<i64>, Error> {
+        let id = sqlx::query!(
+            r#"
+SELECT
+    stock_account_holder_id
+FROM
+    StockAccountHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN SECURITY USING (security_id)
+WHERE
+    person_key = ?
+    AND account_type = ?
+    AND ticker = ?
+"#,
+            record.person_key,
+            record.account_type,
+            record.ticker,
+        )
+        .fetch_optional(&mut *self.0)
+        .await?
+        .map(|row| row.stock_account_holder_id);
+
+        Ok(id)
+    }
+
     async fn upsert_stock_account_mapping_helper(
         &mut self,
         stock_account_holder_id: i64,
         account_key: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), Error> {
         let search_term = format!("{account_key}%");
 
         sqlx::query!(
@@ -242,7 +271,7 @@ UPDATE
 SET
     account_id = excluded.account_id
 WHERE
-    account_id <> excluded.account_id
+    account_id IS NOT excluded.account_id
 "#,
             stock_account_holder_id,
             search_term
@@ -253,3 +282,21 @@ WHERE
         Ok(())
     }
 }
+
+impl Export for StockAccountHolder {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    person_key,
+    account_type,
+    ticker
+FROM
+    StockAccountHolder
+    INNER JOIN Person USING (person_id)
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN SECURITY USING (security_id)
+ORDER BY
+    person_key,
+    account_type,
+    ticker
+"#;
+}
@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Store {
     #[serde(deserialize_with = "string_trim")]
     pub store_key: String,
@@ -21,7 +21,7 @@ impl Id for Store {
 }
 
 impl Query for Store {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -38,10 +38,36 @@ UPDATE
 SET
     store_name = excluded.store_name
 WHERE
-    store_name <> excluded.store_name
+    store_name IS NOT excluded.store_name
 "#,
             self.store_key,
             self.store_name
         )
     }
 }
+
+impl Prune for Store {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    Store
+WHERE
+    store_key = ?
+"#,
+            self.store_key
+        )
+    }
+}
+
+impl Export for Store {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    store_key,
+    store_name
+FROM
+    Store
+ORDER BY
+    store_key
+"#;
+}
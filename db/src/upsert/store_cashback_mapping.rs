@@ -1,10 +1,10 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Serialize, sqlx::FromRow)]
 pub struct StoreCashbackMapping {
     #[serde(deserialize_with = "string_trim")]
     pub store_key: String,
@@ -23,7 +23,7 @@ impl Id for StoreCashbackMapping {
 }
 
 impl Query for StoreCashbackMapping {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -59,7 +59,7 @@ UPDATE
 SET
     cashback_category_name_id = excluded.cashback_category_name_id
 WHERE
-    cashback_category_name_id <> excluded.cashback_category_name_id
+    cashback_category_name_id IS NOT excluded.cashback_category_name_id
 "#,
             self.store_key,
             self.account_type,
@@ -67,3 +67,50 @@ WHERE
         )
     }
 }
+
+impl Prune for StoreCashbackMapping {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    StoreCashbackMapping
+WHERE
+    store_id = (
+        SELECT
+            store_id
+        FROM
+            Store
+        WHERE
+            store_key = ?
+    )
+    AND account_type_id = (
+        SELECT
+            account_type_id
+        FROM
+            AccountType
+        WHERE
+            account_type = ?
+    )
+"#,
+            self.store_key,
+            self.account_type
+        )
+    }
+}
+
+impl Export for StoreCashbackMapping {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    store_key,
+    account_type,
+    cashback_category_name
+FROM
+    StoreCashbackMapping
+    INNER JOIN Store USING (store_id)
+    INNER JOIN AccountType USING (account_type_id)
+    INNER JOIN CashbackCategoryName USING (cashback_category_name_id)
+ORDER BY
+    store_key,
+    account_type
+"#;
+}
@@ -1,15 +1,15 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct TaxShelterType {
     #[serde(deserialize_with = "string_trim")]
-    tax_shelter_type: String,
+    pub tax_shelter_type: String,
     #[serde(deserialize_with = "string_trim")]
-    tax_shelter_name: String,
+    pub tax_shelter_name: String,
 }
 
 impl Id for TaxShelterType {
@@ -21,7 +21,7 @@ impl Id for TaxShelterType {
 }
 
 impl Query for TaxShelterType {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT
@@ -32,10 +32,36 @@ UPDATE
 SET
     tax_shelter_name = excluded.tax_shelter_name
 WHERE
-    tax_shelter_name <> excluded.tax_shelter_name
+    tax_shelter_name IS NOT excluded.tax_shelter_name
 "#,
             self.tax_shelter_type,
             self.tax_shelter_name
         )
     }
 }
+
+impl Prune for TaxShelterType {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    TaxShelterType
+WHERE
+    tax_shelter_type = ?
+"#,
+            self.tax_shelter_type
+        )
+    }
+}
+
+impl Export for TaxShelterType {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    tax_shelter_type,
+    tax_shelter_name
+FROM
+    TaxShelterType
+ORDER BY
+    tax_shelter_type
+"#;
+}
@@ -1,12 +1,12 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct TransactionForex {
-    transaction_id: i64,
-    exchange_rate: f64,
+    pub transaction_id: i64,
+    pub exchange_rate: f64,
 }
 
 impl Id for TransactionForex {
@@ -18,7 +18,7 @@ impl Id for TransactionForex {
 }
 
 impl Query for TransactionForex {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -29,10 +29,36 @@ UPDATE
 SET
     exchange_rate = excluded.exchange_rate
 WHERE
-    exchange_rate <> excluded.exchange_rate
+    exchange_rate IS NOT excluded.exchange_rate
 "#,
             self.transaction_id,
             self.exchange_rate
         )
     }
 }
+
+impl Prune for TransactionForex {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    TransactionForex
+WHERE
+    transaction_id = ?
+"#,
+            self.transaction_id
+        )
+    }
+}
+
+impl Export for TransactionForex {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    transaction_id,
+    exchange_rate
+FROM
+    TransactionForex
+ORDER BY
+    transaction_id
+"#;
+}
@@ -1,14 +1,14 @@
 use common::Id;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_trim::string_trim;
 
-use crate::Query;
+use crate::{Export, Prune, Query};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct TransactionStore {
-    transaction_id: i64,
+    pub transaction_id: i64,
     #[serde(deserialize_with = "string_trim")]
-    store_key: String,
+    pub store_key: String,
 }
 
 impl Id for TransactionStore {
@@ -20,7 +20,7 @@ impl Id for TransactionStore {
 }
 
 impl Query for TransactionStore {
-    fn query(&self) -> crate::SqlQuery {
+    fn query(&self) -> crate::SqlQuery<'_> {
         sqlx::query!(
             r#"
 INSERT INTO
@@ -41,10 +41,37 @@ UPDATE
 SET
     store_id = excluded.store_id
 WHERE
-    store_id <> excluded.store_id
+    store_id IS NOT excluded.store_id
 "#,
             self.store_key,
             self.transaction_id
         )
     }
 }
+
+impl Prune for TransactionStore {
+    fn delete_query(&self) -> crate::SqlQuery<'_> {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    TransactionStore
+WHERE
+    transaction_id = ?
+"#,
+            self.transaction_id
+        )
+    }
+}
+
+impl Export for TransactionStore {
+    const EXPORT_QUERY: &'static str = r#"
+SELECT
+    transaction_id,
+    store_key
+FROM
+    TransactionStore
+    INNER JOIN Store USING (store_id)
+ORDER BY
+    transaction_id
+"#;
+}
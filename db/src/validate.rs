@@ -0,0 +1,493 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use common::Id;
+use serde::de;
+
+use crate::{
+    csv_file::read_csv_collecting_errors, Account, AccountSubtype, AccountType, AssetAllocation,
+    AssetClass, AssetClassName, CashAccountHolder, CashAccountProduct, CashbackCategory,
+    CashbackCategoryName, CategorizationRule, CorporateAction, CreditCard, CreditCardHolder,
+    Currency, Error, Exchange, ExchangeRate, FinancialEntry, GicAccount, GicAccountHolder,
+    IncomeAccount, IncomeAccountHolder, Institution, Person, PrepaidAccount, RecurringEntry,
+    Security, SecurityPrice, StockAccount, StockAccountHolder, Store, StoreCashbackMapping,
+    TaxShelterType, Transaction, TransactionForex, TransactionStore,
+};
+
+/// The parsed rows of a CSV file, in the order of the file.
+struct Table<T> {
+    path: PathBuf,
+    rows: Vec<(u64, T)>,
+}
+
+impl<T> Table<T> {
+    fn keys(&self, field: impl Fn(&T) -> String) -> BTreeSet<String> {
+        self.rows.iter().map(|(_, record)| field(record)).collect()
+    }
+}
+
+/// Collects the problems of the CSV folder instead of stopping at the first one.
+struct Validation<'a> {
+    csv_folder: &'a Path,
+    errors: Vec<Error>,
+}
+
+impl Validation<'_> {
+    fn read<T>(&mut self, file_name: &str) -> Table<T>
+    where
+        T: Id + de::DeserializeOwned,
+    {
+        let path = self.csv_folder.join(file_name);
+        let mut rows: Vec<_> = read_csv_collecting_errors::<T>(&path, &mut self.errors)
+            .into_values()
+            .collect();
+        rows.sort_by_key(|(line, _)| *line);
+
+        Table { path, rows }
+    }
+
+    /// Reads the files that `upsert all` skips when they are missing.
+    fn read_optional<T>(&mut self, file_name: &str) -> Table<T>
+    where
+        T: Id + de::DeserializeOwned,
+    {
+        let path = self.csv_folder.join(file_name);
+
+        if path.exists() {
+            self.read(file_name)
+        } else {
+            Table { path, rows: vec![] }
+        }
+    }
+
+    /// Reports the rows whose `column` isn't one of the `keys`, along with the closest key.
+    fn check<T>(
+        &mut self,
+        table: &Table<T>,
+        column: &'static str,
+        keys: &BTreeSet<String>,
+        field: impl Fn(&T) -> Option<String>,
+    ) {
+        for (line, record) in &table.rows {
+            let value = match field(record) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            if !keys.contains(&value) {
+                self.errors.push(Error::UnresolvedKey {
+                    path: table.path.clone(),
+                    line: *line,
+                    column,
+                    suggestion: closest_key(&value, keys),
+                    value,
+                });
+            }
+        }
+    }
+}
+
+/// Number of single character edits (insertion, deletion or substitution) to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+
+            row[j + 1] = (previous_row[j] + substitution_cost)
+                .min(previous_row[j + 1] + 1)
+                .min(row[j] + 1);
+        }
+
+        previous_row = row;
+    }
+
+    previous_row[b.len()]
+}
+
+/// The key that is the most likely to be meant, allowing about one typo per 3 characters.
+fn closest_key(value: &str, keys: &BTreeSet<String>) -> Option<String> {
+    let value = value.to_uppercase();
+    let max_distance = (value.chars().count() / 3).max(1);
+
+    keys.iter()
+        .map(|key| (edit_distance(&value, &key.to_uppercase()), key))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key.clone())
+}
+
+impl Transaction<'_> {
+    /// Parses every CSV file of the folder read by `upsert all` and resolves the keys that the files refer to.
+    /// All the problems are returned, sorted by file and line. The database is only read for the account subtypes,
+    /// which are prepopulated by the migrations.
+    pub async fn validate_csv_folder(&mut self, csv_folder: &Path) -> Result<Vec<Error>, Error> {
+        let mut validation = Validation {
+            csv_folder,
+            errors: vec![],
+        };
+
+        let person = validation.read::<Person>("person.csv");
+        let institution = validation.read::<Institution>("institution.csv");
+        let currency = validation.read::<Currency>("currency.csv");
+        let exchange_rate = validation.read_optional::<ExchangeRate>("exchange_rate.csv");
+        let exchange = validation.read::<Exchange>("exchange.csv");
+        let security = validation.read::<Security>("security.csv");
+        let security_price = validation.read_optional::<SecurityPrice>("security_price.csv");
+        let corporate_action = validation.read_optional::<CorporateAction>("corporate_action.csv");
+        let asset_class_name = validation.read::<AssetClassName>("asset_class_name.csv");
+        let asset_class = validation.read::<AssetClass>("asset_class.csv");
+        let asset_allocation = validation.read::<AssetAllocation>("asset_allocation.csv");
+        let tax_shelter_type = validation.read::<TaxShelterType>("tax_shelter_type.csv");
+        let account_subtype = validation.read::<AccountSubtype>("account_subtype.csv");
+        let account_type = validation.read::<AccountType>("account_type.csv");
+        let account = validation.read::<Account>("account.csv");
+        let cash_account = validation.read::<CashAccountProduct>("cash_account.csv");
+        let cash_account_holder = validation.read::<CashAccountHolder>("cash_account_holder.csv");
+        let cashback_category_name =
+            validation.read::<CashbackCategoryName>("cashback_category_name.csv");
+        let credit_card = validation.read::<CreditCard>("credit_card_account.csv");
+        let credit_card_holder =
+            validation.read::<CreditCardHolder>("credit_card_account_holder.csv");
+        let cashback_category = validation.read::<CashbackCategory>("cashback_category.csv");
+        let store = validation.read::<Store>("store.csv");
+        let store_cashback_mapping =
+            validation.read::<StoreCashbackMapping>("store_cashback_mapping.csv");
+        let stock_account = validation.read::<StockAccount>("stock_account.csv");
+        let stock_account_holder =
+            validation.read::<StockAccountHolder>("stock_account_holder.csv");
+        let gic_account = validation.read::<GicAccount>("gic_account.csv");
+        let gic_account_holder = validation.read::<GicAccountHolder>("gic_account_holder.csv");
+        let income_account = validation.read::<IncomeAccount>("income_account.csv");
+        let income_account_holder =
+            validation.read::<IncomeAccountHolder>("income_account_holder.csv");
+        let prepaid_account = validation.read::<PrepaidAccount>("prepaid_account.csv");
+        let transaction_store = validation.read::<TransactionStore>("transaction_store.csv");
+        let transaction_forex = validation.read::<TransactionForex>("transaction_forex.csv");
+        let recurring = validation.read_optional::<RecurringEntry>("recurring.csv");
+        let rule = validation.read_optional::<CategorizationRule>("rule.csv");
+        let financial_entry = validation.read::<FinancialEntry>("transaction.csv");
+
+        let person_keys = person.keys(|record| record.person_key.clone());
+        let institution_names = institution.keys(|record| record.institution_name.clone());
+        let currencies = currency.keys(|record| record.currency.clone());
+        let exchange_keys = exchange.keys(|record| record.exchange_key.clone());
+        let tickers = security.keys(|record| record.ticker.clone());
+        let asset_class_names = asset_class_name.keys(|record| record.asset_class_name.clone());
+        let tax_shelter_types = tax_shelter_type.keys(|record| record.tax_shelter_type.clone());
+        // the cash account, credit card and income products insert their account type as well
+        let mut account_types = account_type.keys(|record| record.account_type.clone());
+        account_types.extend(cash_account.keys(|record| record.account_type.clone()));
+        account_types.extend(credit_card.keys(|record| record.account_type.clone()));
+        account_types.extend(income_account.keys(|record| record.account_type.clone()));
+        let cashback_category_names =
+            cashback_category_name.keys(|record| record.cashback_category_name.clone());
+        let store_keys = store.keys(|record| record.store_key.clone());
+        let transaction_ids = financial_entry.keys(|record| record.transaction_id.to_string());
+
+        let mut account_subtypes = account_subtype.keys(|record| record.account_subtype.clone());
+        account_subtypes.extend(
+            sqlx::query_scalar!("SELECT account_subtype FROM AccountSubtype")
+                .fetch_all(&mut *self.0)
+                .await?,
+        );
+
+        // the holders create one account per subtype of their kind of account, e.g. ALICE-BB-CHQ-CASH
+        let holder_subtypes = sqlx::query!(
+            r#"
+SELECT
+    'CASH' AS "holder!: String",
+    account_subtype
+FROM
+    CashAccountEntryType
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+UNION ALL
+SELECT
+    'CREDIT_CARD',
+    account_subtype
+FROM
+    CreditCardEntryType
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+UNION ALL
+SELECT
+    'GIC',
+    account_subtype
+FROM
+    GicAccountSubtype
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+UNION ALL
+SELECT
+    'INCOME',
+    account_subtype
+FROM
+    IncomeAccountSubtype
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+UNION ALL
+SELECT
+    'STOCK',
+    account_subtype
+FROM
+    StockAccountEntryType
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+"#
+        )
+        .fetch_all(&mut *self.0)
+        .await?;
+
+        let holder_account_keys = cash_account_holder
+            .rows
+            .iter()
+            .map(|(_, record)| ("CASH", record.to_account_key()))
+            .chain(
+                credit_card_holder
+                    .rows
+                    .iter()
+                    .map(|(_, record)| ("CREDIT_CARD", record.get_account_key())),
+            )
+            .chain(
+                gic_account_holder
+                    .rows
+                    .iter()
+                    .map(|(_, record)| ("GIC", record.get_account_key())),
+            )
+            .chain(
+                income_account_holder
+                    .rows
+                    .iter()
+                    .map(|(_, record)| ("INCOME", record.get_account_key())),
+            )
+            .chain(
+                stock_account_holder
+                    .rows
+                    .iter()
+                    .map(|(_, record)| ("STOCK", record.create_account_key())),
+            );
+
+        let mut account_keys = account.keys(|record| record.account_key.clone());
+        for (holder, holder_account_key) in holder_account_keys {
+            account_keys.extend(
+                holder_subtypes
+                    .iter()
+                    .filter(|subtype| subtype.holder == holder)
+                    .map(|subtype| format!("{holder_account_key}-{}", subtype.account_subtype)),
+            );
+        }
+
+        validation.check(&exchange_rate, "base_currency", &currencies, |record| {
+            Some(record.base_currency.clone())
+        });
+        validation.check(&exchange_rate, "quote_currency", &currencies, |record| {
+            Some(record.quote_currency.clone())
+        });
+        validation.check(&security, "exchange_key", &exchange_keys, |record| {
+            Some(record.exchange_key.clone())
+        });
+        validation.check(&security, "currency", &currencies, |record| {
+            Some(record.currency.clone())
+        });
+        validation.check(&security_price, "ticker", &tickers, |record| {
+            Some(record.ticker.clone())
+        });
+        validation.check(&corporate_action, "ticker", &tickers, |record| {
+            Some(record.ticker.clone())
+        });
+        validation.check(&corporate_action, "new_ticker", &tickers, |record| {
+            record.new_ticker.clone()
+        });
+        validation.check(&asset_class, "person", &person_keys, |record| {
+            Some(record.person.clone())
+        });
+        // the root asset class has no parent
+        validation.check(&asset_class, "parent", &asset_class_names, |record| {
+            (!record.parent.is_empty()).then(|| record.parent.clone())
+        });
+        validation.check(
+            &asset_class,
+            "asset_class_name",
+            &asset_class_names,
+            |record| Some(record.asset_class_name.clone()),
+        );
+        validation.check(&asset_allocation, "ticker", &tickers, |record| {
+            Some(record.ticker.clone())
+        });
+        validation.check(
+            &asset_allocation,
+            "asset_class_name",
+            &asset_class_names,
+            |record| Some(record.asset_class_name.clone()),
+        );
+        validation.check(&account, "account_subtype", &account_subtypes, |record| {
+            Some(record.account_subtype.clone())
+        });
+        validation.check(&account, "account_type", &account_types, |record| {
+            Some(record.account_type.clone())
+        });
+        validation.check(&account, "stock_ticker", &tickers, |record| {
+            record.stock_ticker.clone()
+        });
+        validation.check(
+            &cash_account,
+            "institution_name",
+            &institution_names,
+            |record| Some(record.institution_name.clone()),
+        );
+        validation.check(
+            &cash_account,
+            "tax_shelter_type",
+            &tax_shelter_types,
+            |record| Some(record.tax_shelter_type.clone()),
+        );
+        validation.check(&cash_account, "currency", &currencies, |record| {
+            Some(record.currency.clone())
+        });
+        validation.check(&cash_account_holder, "person_key", &person_keys, |record| {
+            Some(record.person_key.clone())
+        });
+        validation.check(
+            &cash_account_holder,
+            "account_type",
+            &account_types,
+            |record| Some(record.account_type.clone()),
+        );
+        validation.check(
+            &credit_card,
+            "institution_name",
+            &institution_names,
+            |record| Some(record.institution_name.clone()),
+        );
+        validation.check(&credit_card, "currency", &currencies, |record| {
+            Some(record.currency.clone())
+        });
+        validation.check(&credit_card_holder, "person_key", &person_keys, |record| {
+            Some(record.person_key.clone())
+        });
+        validation.check(
+            &credit_card_holder,
+            "account_type",
+            &account_types,
+            |record| Some(record.account_type.clone()),
+        );
+        validation.check(&credit_card_holder, "pad_source", &account_keys, |record| {
+            record.pad_source.clone()
+        });
+        validation.check(
+            &cashback_category,
+            "account_type",
+            &account_types,
+            |record| Some(record.account_type.clone()),
+        );
+        validation.check(
+            &cashback_category,
+            "cashback_category_name",
+            &cashback_category_names,
+            |record| Some(record.cashback_category_name.clone()),
+        );
+        validation.check(
+            &store_cashback_mapping,
+            "store_key",
+            &store_keys,
+            |record| Some(record.store_key.clone()),
+        );
+        validation.check(
+            &store_cashback_mapping,
+            "account_type",
+            &account_types,
+            |record| Some(record.account_type.clone()),
+        );
+        validation.check(
+            &store_cashback_mapping,
+            "cashback_category_name",
+            &cashback_category_names,
+            |record| Some(record.cashback_category_name.clone()),
+        );
+        validation.check(&stock_account, "account_type", &account_types, |record| {
+            Some(record.account_type.clone())
+        });
+        validation.check(
+            &stock_account_holder,
+            "person_key",
+            &person_keys,
+            |record| Some(record.person_key.clone()),
+        );
+        validation.check(
+            &stock_account_holder,
+            "account_type",
+            &account_types,
+            |record| Some(record.account_type.clone()),
+        );
+        validation.check(&stock_account_holder, "ticker", &tickers, |record| {
+            Some(record.ticker.clone())
+        });
+        validation.check(&gic_account, "account_type", &account_types, |record| {
+            Some(record.account_type.clone())
+        });
+        validation.check(&gic_account_holder, "person_key", &person_keys, |record| {
+            Some(record.person_key.clone())
+        });
+        validation.check(
+            &gic_account_holder,
+            "account_type",
+            &account_types,
+            |record| Some(record.account_type.clone()),
+        );
+        validation.check(&income_account, "currency", &currencies, |record| {
+            Some(record.currency.clone())
+        });
+        validation.check(
+            &income_account_holder,
+            "person_key",
+            &person_keys,
+            |record| Some(record.person_key.clone()),
+        );
+        validation.check(
+            &income_account_holder,
+            "account_type",
+            &account_types,
+            |record| Some(record.account_type.clone()),
+        );
+        validation.check(&prepaid_account, "account_type", &account_types, |record| {
+            Some(record.account_type.clone())
+        });
+        validation.check(
+            &transaction_store,
+            "transaction_id",
+            &transaction_ids,
+            |record| Some(record.transaction_id.to_string()),
+        );
+        validation.check(
+            &transaction_forex,
+            "transaction_id",
+            &transaction_ids,
+            |record| Some(record.transaction_id.to_string()),
+        );
+        validation.check(&transaction_store, "store_key", &store_keys, |record| {
+            Some(record.store_key.clone())
+        });
+        validation.check(&recurring, "account_key", &account_keys, |record| {
+            Some(record.account_key.clone())
+        });
+        validation.check(&rule, "account_key", &account_keys, |record| {
+            record.account_key.clone()
+        });
+        validation.check(&rule, "store_key", &store_keys, |record| {
+            record.store_key.clone()
+        });
+        validation.check(&rule, "account_type", &account_types, |record| {
+            record.account_type.clone()
+        });
+        validation.check(&financial_entry, "account_key", &account_keys, |record| {
+            Some(record.account_key.clone())
+        });
+
+        let mut errors = validation.errors;
+        errors.sort_by(|a, b| a.location().cmp(&b.location()));
+
+        Ok(errors)
+    }
+}
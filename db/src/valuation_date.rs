@@ -0,0 +1,84 @@
+use chrono::{DateTime, Local};
+
+use crate::{Db, SqlResult, Transaction};
+
+impl Db {
+    /// Creates the valuation date and the valuation views in the TEMP schema of the connection, valuing everything
+    /// up to now until a report sets the date.
+    pub(crate) async fn create_valuation_views(&mut self) -> SqlResult<()> {
+        sqlx::query(include_str!("valuation_date.sql"))
+            .execute(&mut self.0)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl Transaction<'_> {
+    /// Makes the valuation views (e.g. `AllocationView`) only consider entries and prices up to `date`.
+    /// `None` values everything up to now with the current quotes.
+    ///
+    /// The date goes into the one-row `ValuationDate` table that the views join, which is a TEMP table of the
+    /// connection, so nothing is written to the ledger and other connections keep their own date.
+    pub async fn set_valuation_date(&mut self, date: Option<&DateTime<Local>>) -> SqlResult<()> {
+        let timestamp = date.map(|date| date.timestamp());
+
+        sqlx::query(
+            r#"
+UPDATE
+    temp.ValuationDate
+SET
+    date = ?
+WHERE
+    valuation_date_id = 1
+    AND date IS NOT ?
+"#,
+        )
+        .bind(timestamp)
+        .bind(timestamp)
+        .execute(&mut *self.0)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use sqlx::Row;
+
+    use super::*;
+
+    async fn get_as_of(db: &mut Db) -> i64 {
+        sqlx::query("SELECT as_of FROM ValuationCutoff")
+            .fetch_one(&mut **db)
+            .await
+            .unwrap()
+            .get(0)
+    }
+
+    #[tokio::test]
+    async fn valuation_date_of_each_connection() {
+        let path =
+            std::env::temp_dir().join(format!("pit-valuation-{}.sqlite3", std::process::id()));
+        let mut db = Db::from_path(path.clone()).await.unwrap();
+        let mut other_db = Db::from_path(path.clone()).await.unwrap();
+
+        let as_of = Local.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap();
+        let mut transaction = db.begin_wrapped_transaction().await.unwrap();
+        transaction.set_valuation_date(Some(&as_of)).await.unwrap();
+        transaction.commit().await.unwrap();
+
+        let as_of_of_db = get_as_of(&mut db).await;
+        let as_of_of_other_db = get_as_of(&mut other_db).await;
+        drop((db, other_db));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.to_string_lossy()));
+        }
+
+        assert_eq!(as_of_of_db, as_of.timestamp());
+        // the other connection still values everything up to now
+        assert_eq!(as_of_of_other_db, i64::MAX);
+    }
+}
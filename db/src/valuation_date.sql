@@ -0,0 +1,310 @@
+-- The valuation date is a parameter of the reports, so it lives in the TEMP schema of each connection instead of the
+-- ledger: a report of one process can't change the valuation of another. `Db::from_path` runs this file on every
+-- connection, and the views that value holdings are TEMP views because a view of the ledger can't read a TEMP table.
+-- Views only look at entries up to this date; NULL means no cut-off.
+CREATE TEMP TABLE ValuationDate (
+    valuation_date_id INTEGER NOT NULL PRIMARY KEY CHECK (valuation_date_id = 1),
+    date INTEGER CHECK (
+        date IS NULL
+        OR date >= 0
+    )
+) STRICT;
+
+INSERT INTO
+    temp.ValuationDate (valuation_date_id, date)
+VALUES
+    (1, NULL);
+
+CREATE TEMP VIEW ValuationCutoff AS
+SELECT
+    date IS NOT NULL AS is_historical,
+    COALESCE(date, 9223372036854775807) AS as_of
+FROM
+    ValuationDate;
+
+CREATE TEMP VIEW CashView AS
+SELECT
+    cash_account_holder_id,
+    person_id,
+    emergency_target,
+    MAX(
+        0,
+        MIN(
+            emergency_target,
+            balance - min_balance_waiver
+        )
+    ) AS emergency_fund,
+    MAX(
+        0,
+        (emergency_target + min_balance_waiver) - balance
+    ) AS injection_needed,
+    MAX(
+        0,
+        balance - (emergency_target + min_balance_waiver)
+    ) AS unallocated_fund,
+    currency_id
+FROM
+    CashAccountEntry
+    INNER JOIN CashAccountHolder USING (cash_account_holder_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN (
+        SELECT
+            account_id,
+            SUM(debit_amount - credit_amount) AS balance
+        FROM
+            FinancialEntryAmount
+            INNER JOIN CashAccountEntry USING (account_id)
+        WHERE
+            date <= (
+                SELECT
+                    as_of
+                FROM
+                    ValuationCutoff
+            )
+        GROUP BY
+            account_id
+    ) USING (account_id)
+WHERE
+    account_subtype = 'CASH';
+
+CREATE TEMP VIEW MarketExchangeRate AS
+SELECT
+    currency_id,
+    CASE
+        WHEN currency_id IN (
+            SELECT
+                currency_id
+            FROM
+                HomeCurrency
+        ) THEN 1.0
+        ELSE (
+            SELECT
+                rate
+            FROM
+                HomeExchangeRate
+            WHERE
+                HomeExchangeRate.currency_id = Currency.currency_id
+                AND date <= (
+                    SELECT
+                        as_of
+                    FROM
+                        ValuationCutoff
+                )
+            ORDER BY
+                date DESC
+            LIMIT
+                1
+        )
+    END AS market_exchange_rate
+FROM
+    Currency;
+
+CREATE TEMP VIEW NormalizedFixedIncomeBalance AS
+SELECT
+    person_id,
+    SUM(
+        CAST(
+            ROUND(
+                (debit_amount - credit_amount) * market_exchange_rate
+            ) AS INTEGER
+        )
+    ) AS balance
+FROM
+    Account
+    INNER JOIN GicEntry USING (account_id)
+    INNER JOIN GicAccountHolder USING (gic_account_holder_id)
+    INNER JOIN AccountSubtype USING (account_subtype_id)
+    INNER JOIN AccountKind USING (account_kind_id)
+    INNER JOIN CashAccountProduct USING (account_type_id)
+    INNER JOIN FinancialEntryAmount USING (account_id)
+    INNER JOIN MarketExchangeRate USING (currency_id)
+WHERE
+    account_kind = 'ASSET'
+    AND date <= (
+        SELECT
+            as_of
+        FROM
+            ValuationCutoff
+    )
+GROUP BY
+    person_id;
+
+-- the price of each security on the valuation date, NULL when there is no quote on or before it
+CREATE TEMP VIEW SecurityPriceAsOf AS
+SELECT
+    security_id,
+    (
+        SELECT
+            SecurityPrice.price
+        FROM
+            SecurityPrice
+        WHERE
+            SecurityPrice.security_id = SECURITY.security_id
+            AND SecurityPrice.date <= (
+                SELECT
+                    as_of
+                FROM
+                    ValuationCutoff
+            )
+        ORDER BY
+            SecurityPrice.date DESC
+        LIMIT
+            1
+    ) AS price
+FROM
+    SECURITY;
+
+CREATE TEMP VIEW NormalizedStockBalance AS WITH StockCount AS (
+    SELECT
+        person_id,
+        security_id,
+        CAST(
+            ROUND(
+                CAST(
+                    ROUND(SUM(unit) * SecurityPriceAsOf.price / 1000000.0) AS INTEGER
+                ) * market_exchange_rate
+            ) AS INTEGER
+        ) AS balance
+    FROM
+        StockUnitHistory
+        INNER JOIN SECURITY USING(security_id)
+        INNER JOIN SecurityPriceAsOf USING(security_id)
+        INNER JOIN MarketExchangeRate USING (currency_id)
+    WHERE
+        date <= (
+            SELECT
+                as_of
+            FROM
+                ValuationCutoff
+        )
+    GROUP BY
+        person_id,
+        account_type_id,
+        security_id
+    HAVING
+        balance <> 0
+)
+SELECT
+    person_id,
+    asset_class_id,
+    SUM(CAST(ROUND(balance * rate) AS INTEGER)) AS balance
+FROM
+    StockCount
+    INNER JOIN PerClassAllocationRate USING (person_id, security_id)
+GROUP BY
+    person_id,
+    asset_class_id;
+
+CREATE TEMP VIEW BalanceByAssetClass (
+    person_id,
+    asset_class_id,
+    liquid_balance,
+    reserve_balance,
+    total_balance
+) AS
+SELECT
+    person_id,
+    asset_class_id,
+    COALESCE(balance, 0),
+    0,
+    COALESCE(balance, 0)
+FROM
+    NormalizedStockBalance
+UNION
+ALL
+SELECT
+    person_id,
+    asset_class_id,
+    CAST(
+        ROUND(
+            COALESCE(SUM(unallocated_fund), 0) * market_exchange_rate
+        ) AS INTEGER
+    ),
+    CAST(
+        ROUND(
+            COALESCE(SUM(emergency_fund), 0) * market_exchange_rate
+        ) AS INTEGER
+    ),
+    CAST(
+        ROUND(
+            COALESCE(SUM(unallocated_fund) + SUM(emergency_fund), 0) * market_exchange_rate
+        ) AS INTEGER
+    )
+FROM
+    CashView
+    INNER JOIN AssetClass USING (person_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+    INNER JOIN MarketExchangeRate USING (currency_id)
+WHERE
+    asset_class_name = 'Cash'
+    AND -- exclude prepaid accounts (i.e. Presto) from rebalancing
+    cash_account_holder_id NOT IN (
+        SELECT
+            cash_account_holder_id
+        FROM
+            PrepaidAccount
+            INNER JOIN CashAccountHolder USING (account_type_id)
+    )
+GROUP BY
+    person_id,
+    asset_class_id
+UNION
+ALL
+SELECT
+    person_id,
+    asset_class_id,
+    0,
+    COALESCE(balance, 0),
+    COALESCE(balance, 0)
+FROM
+    NormalizedFixedIncomeBalance
+    INNER JOIN AssetClass USING (person_id)
+    INNER JOIN AssetClassName USING (asset_class_name_id)
+WHERE
+    asset_class_name = 'Fixed Income';
+
+CREATE TEMP VIEW TotalAssetBalance AS
+SELECT
+    person_id,
+    SUM(liquid_balance) AS liquid_sum,
+    SUM(total_balance) AS total_sum
+FROM
+    BalanceByAssetClass
+GROUP BY
+    person_id;
+
+CREATE TEMP VIEW AllocationView AS WITH RebalanceRate AS (
+    SELECT
+        CAST(COALESCE(liquid_balance, 0) AS REAL) / liquid_sum AS liquid_rate,
+        CAST(COALESCE(total_balance, 0) AS REAL) / total_sum AS total_rate,
+        asset_class_id,
+        person_id,
+        parent_id,
+        -- class,
+        class_rate,
+        real_rate,
+        COALESCE(liquid_balance, 0) AS liquid_balance,
+        COALESCE(reserve_balance, 0) AS reserve_balance,
+        COALESCE(total_balance, 0) AS total_balance,
+        liquid_sum,
+        total_sum
+    FROM
+        PortfolioAllocationRate
+        LEFT JOIN BalanceByAssetClass b USING (person_id, asset_class_id)
+        INNER JOIN TotalAssetBalance USING (person_id)
+)
+SELECT
+    person_id,
+    asset_class_id,
+    CAST(
+        ROUND((real_rate - liquid_rate) * liquid_sum) AS INTEGER
+    ) AS current_rebalance_amount,
+    CAST(
+        ROUND((real_rate - total_rate) * total_sum) AS INTEGER
+    ) AS potential_rebalance_amount
+FROM
+    RebalanceRate
+WHERE
+    current_rebalance_amount <> 0
+    OR potential_rebalance_amount <> 0;